@@ -1,14 +1,22 @@
 use clap::Parser;
+use std::fmt;
+use std::io::{self, BufRead};
 use std::path::PathBuf;
 
 use super::Prompter;
+use crate::cli;
 use crate::context::Context;
 use crate::entities::change::Data;
+use crate::entities::tag::{self, TagError};
+use crate::entities::thing::{self, ThingError};
 use crate::lenses;
-use crate::services::staging;
+use crate::lenses::thing::{FetchedThing, SchemeAllowlist};
+use crate::services::fetcher::{FetchConfig, Fetcher};
+use crate::services::staging::{self, CommitMode};
+use crate::settings::Settings;
 use crate::store::{Repository, TagStore, ThingStore};
 use crate::entities::tag_set::TagSet;
-use crate::{Report, Result};
+use crate::{Report, Result, SomeError};
 
 /// Adds a new item to the collection.
 #[derive(Debug, Parser)]
@@ -16,42 +24,122 @@ pub struct Cmd {
     /// The location where to find the Some package to be used.
     #[clap(default_value = ".")]
     path: PathBuf,
+    /// Use the URL found in the clipboard instead of asking for it.
+    #[clap(long = "from-clipboard")]
+    clipboard_flag: bool,
+    /// Scrape the author, publication date and favicon as well.
+    #[clap(long = "rich")]
+    rich_flag: bool,
+    /// Always fetch the page, bypassing the responses cached by previous runs.
+    #[clap(long = "no-cache")]
+    no_cache_flag: bool,
+    /// Write the resource files concurrently on commit.
+    #[clap(long = "concurrent")]
+    concurrent_flag: bool,
+    /// Fail unless a category is explicitly chosen instead of falling back to `miscellaneous`.
+    /// Also enabled by setting `SOME_REQUIRE_CATEGORY`.
+    #[clap(long = "require-category")]
+    require_category_flag: bool,
+    /// The category id to use instead of asking for it.
+    #[clap(long, value_name = "id")]
+    category: Option<String>,
+    /// A comma separated list of tag ids to use instead of asking for them.
+    #[clap(long, value_name = "ids")]
+    tags: Option<String>,
+    /// Stage any `--category` or `--tags` id that doesn't exist yet as a new tag.
+    #[clap(long = "create-missing")]
+    create_missing_flag: bool,
+    /// Read the URLs to add from stdin, one per line, without asking anything. Each thing takes
+    /// the `--category` and `--tags` given. Duplicates and failed fetches are reported at the end.
+    #[clap(long = "stdin")]
+    stdin_flag: bool,
+    /// Don't fetch the pages, using each URL as the thing name. Only used with `--stdin`.
+    #[clap(long = "no-network")]
+    no_network_flag: bool,
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<Report> {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        if self.stdin_flag {
+            return self.run_stdin(dry_run);
+        }
+
         let mut prompter = Prompter::new()?;
         let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+        let fallback_id = self.fallback_category(context.settings());
+        let fetch_config = cli::fetch_config(context.settings());
+        let url_schemes = context.settings().url_schemes.clone();
         let store = context.store();
 
         // Main info
-        let url = prompter.demand("url")?;
+        let clipboard_url = if self.clipboard_flag {
+            url_from_clipboard(read_clipboard, &url_schemes)
+        } else {
+            None
+        };
+        let url = match clipboard_url {
+            Some(url) => url,
+            None => prompter.demand("url")?,
+        };
+
+        lenses::thing::validate_url_with(&url, &url_schemes)?;
 
         // TODO: Consider offering the option to amend it.
         if ThingStore::get(&store.conn, &url)?.is_some() {
             return Ok(Report::new("This thing already exists."));
         }
 
-        // TODO: Move to a new service 'fetcher'.
-        lenses::thing::fetch_thing(&url)?;
+        let mut fetcher = Fetcher::new(FetchConfig {
+            cache: !self.no_cache_flag,
+            ..fetch_config
+        });
+        let fetched = lenses::thing::fetch_thing(&mut fetcher, &url, self.rich_flag)?;
 
-        let name = prompter.demand("name")?;
-        let summary = prompter.ask_once("summary")?;
+        let name = match &fetched.title {
+            Some(title) => prompter.ask_default("name", title)?,
+            None => prompter.demand("name")?,
+        };
+        let summary = match &fetched.summary {
+            Some(summary) => Some(prompter.ask_default("summary", summary)?),
+            None => prompter.ask_once("summary")?,
+        }
+        .and_then(|summary| thing::normalize_summary(&summary));
 
         // Category
-        let category_set = TagSet::from_iter(TagStore::list(&store.conn)?);
+        let all_tags = TagStore::list(&store.conn)?;
+        let known_ids: Vec<tag::Id> = all_tags.iter().map(|tag| tag.id().clone()).collect();
         let default_category_id = "miscellaneous";
-        let category_id = ask_category(&mut prompter, default_category_id, category_set);
+        let category_id = match &self.category {
+            Some(id) => id.clone(),
+            None => ask_category(
+                &mut prompter,
+                fallback_id.as_deref(),
+                TagSet::from_iter(all_tags),
+            )?,
+        };
 
         // Tags
-        let tag_set = TagSet::from_iter(TagStore::list_without(
-            &store.conn,
-            &[default_category_id.into()],
-        )?);
-        let tags = ask_tags(&mut prompter, tag_set);
+        let tags = match &self.tags {
+            Some(ids) => split_ids(ids),
+            None => {
+                let tag_set = TagSet::from_iter(TagStore::list_without(
+                    &store.conn,
+                    &[default_category_id.into()],
+                )?);
+
+                ask_tags(&mut prompter, tag_set)
+            }
+        };
 
         prompter.flush()?;
 
+        let mut given: Vec<tag::Id> = tags.clone();
+        given.extend(self.category.clone());
+        let missing = missing_ids(&known_ids, &given, self.create_missing_flag)?;
+
+        stage_tags(&mut context, missing)?;
+
         // Build the thing
         let data = Data::Thing {
             url,
@@ -62,25 +150,256 @@ impl Cmd {
         };
 
         staging::add(&mut context, data)?;
+        staging::commit_with(&mut context, self.commit_mode())?;
 
-        staging::commit(&mut context)?;
+        if context.dry_run() {
+            return Ok(Report::new("Dry run. Nothing added."));
+        }
 
         Ok(Report::new("Success"))
     }
+
+    /// Adds every URL read from stdin, committing them all at once.
+    fn run_stdin(&self, dry_run: bool) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+        let category_id = category_or_default(
+            self.category.clone(),
+            self.fallback_category(context.settings()).as_deref(),
+        )?;
+        let tags = self.tags.as_deref().map(split_ids).unwrap_or_default();
+
+        let known_ids: Vec<tag::Id> = context
+            .tags()?
+            .as_slice()
+            .iter()
+            .map(|tag| tag.id().clone())
+            .collect();
+        let mut given: Vec<tag::Id> = tags.clone();
+        given.push(category_id.clone());
+        let missing = missing_ids(&known_ids, &given, self.create_missing_flag)?;
+
+        stage_tags(&mut context, missing)?;
+
+        let mut fetcher = Fetcher::new(FetchConfig {
+            cache: !self.no_cache_flag,
+            ..cli::fetch_config(context.settings())
+        });
+        let scrape = |url: &str| {
+            if self.no_network_flag {
+                Ok(FetchedThing::default())
+            } else {
+                lenses::thing::fetch_thing(&mut fetcher, url, self.rich_flag)
+            }
+        };
+        let stdin = io::stdin();
+        let bulk = add_lines(&mut context, stdin.lock(), &category_id, &tags, scrape)?;
+
+        staging::commit_with(&mut context, self.commit_mode())?;
+
+        if context.dry_run() {
+            return Ok(Report::new("Dry run. Nothing added."));
+        }
+
+        Ok(Report::new(bulk.to_string()))
+    }
+
+    fn commit_mode(&self) -> CommitMode {
+        if self.concurrent_flag {
+            CommitMode::Concurrent
+        } else {
+            CommitMode::Sequential
+        }
+    }
+
+    /// The category to use when none is chosen: `miscellaneous`, or none when a category must be
+    /// explicitly chosen, either by flag or by environment.
+    fn fallback_category(&self, settings: &Settings) -> Option<String> {
+        if self.require_category_flag || settings.require_category {
+            None
+        } else {
+            Some("miscellaneous".into())
+        }
+    }
+}
+
+/// Stages a bare tag for each of the given ids.
+fn stage_tags(context: &mut Context, ids: Vec<tag::Id>) -> Result<()> {
+    for id in ids {
+        staging::add(
+            context,
+            Data::Tag {
+                id,
+                name: None,
+                summary: None,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The outcome of adding URLs in bulk.
+#[derive(Debug, Default)]
+struct Bulk {
+    added: usize,
+    /// The URLs left out, with the reason why.
+    skipped: Vec<(String, String)>,
 }
 
-/// Ask for a category or fallback to the default category.
-fn ask_category(prompter: &mut Prompter, default_id: &str, items: TagSet) -> String {
-    if items.len() == 1 {
-        items.first().expect("always an item present").to_string()
+impl fmt::Display for Bulk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Added {} things.", self.added)?;
+
+        if !self.skipped.is_empty() {
+            write!(f, " Skipped {}:", self.skipped.len())?;
+
+            for (url, reason) in &self.skipped {
+                write!(f, "\n  {}: {}", url, reason)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Stages a thing for each URL read, one per line, using `scrape` to find its name and summary.
+///
+/// Duplicates and URLs that fail to fetch are skipped and reported in the outcome, any other
+/// failure stops the whole batch. Blank lines are ignored.
+fn add_lines<R, F>(
+    context: &mut Context,
+    rdr: R,
+    category_id: &str,
+    tags: &[tag::Id],
+    mut scrape: F,
+) -> Result<Bulk>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Result<FetchedThing>,
+{
+    let mut bulk = Bulk::default();
+
+    for line in rdr.lines() {
+        let line = line?;
+        let url = line.trim();
+
+        if url.is_empty() {
+            continue;
+        }
+
+        match add_url(context, url, category_id, tags, &mut scrape) {
+            Ok(()) => bulk.added += 1,
+            Err(err) if err.is_network() || err.is_validation() => {
+                let reason = match &err {
+                    SomeError::Fetch(source) => source.to_string(),
+                    _ => err.to_string(),
+                };
+
+                bulk.skipped.push((url.to_string(), reason));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(bulk)
+}
+
+fn add_url<F>(
+    context: &mut Context,
+    url: &str,
+    category_id: &str,
+    tags: &[tag::Id],
+    scrape: &mut F,
+) -> Result<()>
+where
+    F: FnMut(&str) -> Result<FetchedThing>,
+{
+    lenses::thing::validate_url_with(url, &context.settings().url_schemes)?;
+
+    // Checked upfront to avoid fetching a page only to find it is already known.
+    if ThingStore::get(&context.store().conn, &url.to_string())?.is_some() {
+        return Err(ThingError::Duplicate(url.to_string()).into());
+    }
+
+    let fetched = scrape(url)?;
+    let data = Data::Thing {
+        url: url.to_string(),
+        name: fetched.title.unwrap_or_else(|| url.to_string()),
+        summary: fetched.summary,
+        category: category_id.to_string(),
+        tags: tags.to_vec(),
+    };
+
+    staging::add(context, data)
+}
+
+/// Ask for a category or fallback to the default category, if any.
+///
+/// Without a default category the only category available is still offered as a choice.
+fn ask_category(
+    prompter: &mut Prompter,
+    default_id: Option<&str>,
+    items: TagSet,
+) -> Result<String> {
+    if items.len() == 1 && default_id.is_some() {
+        Ok(items.first().expect("always an item present").to_string())
     } else {
-        prompter
+        let choice = prompter
             .read_choice(items.clone(), "category")
-            .expect("always to read a choice")
-            .unwrap_or(default_id.into())
+            .expect("always to read a choice");
+
+        category_or_default(choice, default_id)
     }
 }
 
+/// Returns the chosen category, the default one when nothing was chosen, or fails when there is
+/// no default.
+fn category_or_default(choice: Option<String>, default_id: Option<&str>) -> Result<String> {
+    choice
+        .or_else(|| default_id.map(String::from))
+        .ok_or_else(|| SomeError::FieldRequired("category".into()))
+}
+
+/// Splits a comma separated list of ids, ignoring blanks.
+fn split_ids(raw: &str) -> Vec<tag::Id> {
+    raw.split(',')
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the given ids that are not known, once each, to be created.
+///
+/// Fails on the first unknown id unless `create_missing` is set, suggesting the closest known id
+/// when it looks like a typo.
+fn missing_ids(known: &[tag::Id], given: &[tag::Id], create_missing: bool) -> Result<Vec<tag::Id>> {
+    let mut missing: Vec<tag::Id> = Vec::new();
+
+    for id in given {
+        if known.contains(id) || missing.contains(id) {
+            continue;
+        }
+
+        if !create_missing {
+            let error = match tag::closest(id, known.iter().map(String::as_str)) {
+                Some(suggestion) => TagError::Misspelled {
+                    id: id.clone(),
+                    suggestion: suggestion.to_string(),
+                },
+                None => TagError::NotFound(id.clone()),
+            };
+
+            return Err(error.into());
+        }
+
+        missing.push(id.clone());
+    }
+
+    Ok(missing)
+}
+
 /// Ask to choose zero or more tags.
 fn ask_tags(prompter: &mut Prompter, items: TagSet) -> Vec<String> {
     if items.len() == 0 {
@@ -91,3 +410,150 @@ fn ask_tags(prompter: &mut Prompter, items: TagSet) -> Vec<String> {
             .expect("always to read a tag choice")
     }
 }
+
+/// Reads the system clipboard as text, if any.
+fn read_clipboard() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+/// Returns the clipboard contents when they are a valid URL.
+fn url_from_clipboard<F>(read: F, allowlist: &SchemeAllowlist) -> Option<String>
+where
+    F: FnOnce() -> Option<String>,
+{
+    let text = read()?;
+    let url = text.trim();
+
+    lenses::thing::validate_url_with(url, allowlist).ok()?;
+
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::store::ChangeStore;
+    use std::io::Cursor;
+
+    #[test]
+    fn clipboard_with_url() {
+        let actual = url_from_clipboard(
+            || Some(" https://www.rust-lang.org/\n".into()),
+            &SchemeAllowlist::default(),
+        );
+
+        assert_eq!(actual, Some("https://www.rust-lang.org/".into()));
+    }
+
+    #[test]
+    fn clipboard_without_url() {
+        let allowlist = SchemeAllowlist::default();
+
+        assert_eq!(
+            url_from_clipboard(|| Some("not a url".into()), &allowlist),
+            None
+        );
+        assert_eq!(url_from_clipboard(|| None, &allowlist), None);
+    }
+
+    #[test]
+    fn missing_category_with_fallback() -> Result<()> {
+        assert_eq!(
+            category_or_default(None, Some("miscellaneous"))?,
+            "miscellaneous"
+        );
+        assert_eq!(
+            category_or_default(Some("tool".into()), Some("miscellaneous"))?,
+            "tool"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fallback_category() {
+        let default = Settings::default();
+        let required = Settings {
+            require_category: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Cmd::parse_from(["add"]).fallback_category(&default),
+            Some("miscellaneous".into())
+        );
+        assert_eq!(Cmd::parse_from(["add"]).fallback_category(&required), None);
+        assert_eq!(
+            Cmd::parse_from(["add", "--require-category"]).fallback_category(&default),
+            None
+        );
+    }
+
+    fn known() -> Vec<tag::Id> {
+        vec!["miscellaneous".into(), "language".into()]
+    }
+
+    #[test]
+    fn valid_category_and_tags() -> Result<()> {
+        let given = split_ids("language, miscellaneous,");
+
+        assert_eq!(given, vec!["language", "miscellaneous"]);
+        assert!(missing_ids(&known(), &given, false)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn typo_with_suggestion() {
+        let actual = missing_ids(&known(), &["languge".into()], false);
+
+        assert!(matches!(
+            actual,
+            Err(SomeError::Tag(TagError::Misspelled { id, suggestion }))
+                if id == "languge" && suggestion == "language"
+        ));
+        assert!(matches!(
+            missing_ids(&known(), &["graphics".into()], false),
+            Err(SomeError::Tag(TagError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn create_missing() -> Result<()> {
+        let given = split_ids("graphics,language,graphics");
+
+        assert_eq!(missing_ids(&known(), &given, true)?, vec!["graphics"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn urls_from_stdin() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+        let input = Cursor::new(concat!(
+            "https://www.rust-lang.org/\n",
+            "\n",
+            "https://ziglang.org/\n",
+            "https://www.sqlite.org/\n",
+            "https://www.rust-lang.org/\n",
+        ));
+        let scrape = |url: &str| {
+            Ok(FetchedThing {
+                title: Some(format!("Title for {}", url)),
+                ..Default::default()
+            })
+        };
+        let bulk = add_lines(&mut context, input, "miscellaneous", &[], scrape)?;
+
+        assert_eq!(bulk.added, 3);
+        assert_eq!(bulk.skipped.len(), 1);
+        assert_eq!(bulk.skipped[0].0, "https://www.rust-lang.org/");
+
+        let tx = context.tx()?;
+        assert_eq!(ChangeStore::len(&tx)?, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use clap::Parser;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::info;
+use crate::package::{Package, DESCRIPTOR_PATH};
+use crate::{Report, Result, SomeError};
+
+/// Bundles the package descriptor and its resource files into a single gzip tarball.
+///
+/// Staged changes are not included. Use `some restore` to unpack the archive elsewhere.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be backed up.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// The archive to write.
+    #[clap(long, short, value_name = "file", default_value = "collection.tar.gz")]
+    output: PathBuf,
+    /// Overwrite the archive if it exists.
+    #[clap(long = "force")]
+    force_flag: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let root = self.path.canonicalize()?;
+        let package = Package::from_path(&root)?;
+
+        if self.output.exists() && !self.force_flag {
+            return Err(SomeError::Conflict(self.output.display().to_string()));
+        }
+
+        if dry_run {
+            info!("Would write:", self.output.display());
+
+            return Ok(Report::new("Dry run. Nothing written."));
+        }
+
+        archive(&root, &package, &self.output)?;
+
+        Ok(Report::new(format!(
+            "Package `{}` backed up to `{}`.",
+            package.name(),
+            self.output.display()
+        )))
+    }
+}
+
+/// Writes the descriptor and every resource file with a path to a gzip tarball in `output`.
+///
+/// Entries keep their location relative to the package so the archive unpacks as a package.
+pub fn archive(root: &Path, package: &Package, output: &Path) -> Result<()> {
+    let encoder = GzEncoder::new(File::create(output)?, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    builder.append_path_with_name(root.join(DESCRIPTOR_PATH), DESCRIPTOR_PATH)?;
+
+    for resource in package.resources().iter().filter(|r| r.has_path()) {
+        let path = resource.absolute_path(root);
+
+        if !path.is_file() {
+            return Err(SomeError::MissingResource(
+                resource.path().display().to_string(),
+            ));
+        }
+
+        builder.append_path_with_name(path, resource.path())?;
+    }
+
+    builder.into_inner()?.finish()?;
+
+    Ok(())
+}
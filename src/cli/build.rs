@@ -1,11 +1,23 @@
+use anyhow::anyhow;
+use chrono::Local;
 use clap::Parser;
-use std::fs::OpenOptions;
+use std::collections::HashMap;
+use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use crate::cli;
 use crate::context::Context;
-use crate::store::{TagStore, ThingStore};
+use crate::info;
+use crate::markdown::escape_md;
+use crate::package::Package;
+use crate::services::batch::Interrupt;
+use crate::services::favicon::{FaviconFinder, HttpFavicon, NoFavicon};
+use crate::services::link_check::{self, HttpChecker, LinkChecker, NoCheck};
+use crate::services::watch::{self, DEBOUNCE};
+use crate::store::{Repository, TagStore, ThingStore};
 use crate::entities::thing;
 use crate::{Report, Result};
 
@@ -18,6 +30,46 @@ pub struct Cmd {
     /// Flag to use the README.md found in the given path.
     #[clap(short, action, default_value_t = false)]
     output_flag: bool,
+    /// The name of the section markers delimiting the generated region of the README.md.
+    ///
+    /// Only the content between `<!-- <section>:begin -->` and `<!-- <section>:end -->` is
+    /// replaced. The whole file is written when the markers are absent.
+    #[clap(long, value_name = "name", default_value = "some")]
+    section: String,
+    /// How to group things: by `category` or by `tag`.
+    #[clap(long, value_name = "layout", default_value = "category")]
+    group_by: GroupBy,
+    /// Only write the table of contents, a link to each section.
+    #[clap(long = "toc-only")]
+    toc_only_flag: bool,
+    /// Mark each thing with ✓ or ✗ depending on whether its URL responds. Skipped when offline.
+    /// `Ctrl-C` stops checking, leaving the rest unmarked.
+    #[clap(long = "check-links")]
+    check_links_flag: bool,
+    /// Show each thing's favicon before its link. Skipped when offline or when none is found.
+    #[clap(long = "favicons")]
+    favicons_flag: bool,
+    /// The separator between the tags of a thing. Occurrences within a tag id are escaped.
+    #[clap(long, value_name = "str", default_value = "; ")]
+    tag_delimiter: String,
+    /// Link each tag to its section, when the grouping gives it one.
+    #[clap(long = "tag-links")]
+    tag_links_flag: bool,
+    /// Truncate the summaries shown in the tables to this many characters, on a word boundary.
+    #[clap(long, value_name = "n")]
+    summary_max: Option<usize>,
+    /// Only include the things with the term in their name, summary or tag ids, ignoring case.
+    /// Repeat it to include the things matching any of the terms.
+    #[clap(long = "keyword", value_name = "term")]
+    keywords: Vec<String>,
+    /// Read the resource CSVs with this encoding, e.g. `windows-1252`, instead of the one the
+    /// descriptor declares.
+    #[clap(long, value_name = "label")]
+    encoding: Option<String>,
+    /// Keep running, building again whenever the `data/` CSVs or `datapackage.json` change.
+    /// A failed build is reported and the watch goes on. Stop with `Ctrl-C`.
+    #[clap(long = "watch")]
+    watch_flag: bool,
     /// The location where to find the Some package to be destroyed.
     #[clap(default_value = ".")]
     path: PathBuf,
@@ -25,43 +77,359 @@ pub struct Cmd {
 
 impl Cmd {
     pub fn run(&self) -> Result<Report> {
-        let mut context = Context::new(&self.path)?;
-        let mut writer: Box<dyn Write> = if self.output_flag {
-            let file = OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(self.path.join("README.md"))?;
-
-            Box::new(file)
+        let encoding = cli::encoding(self.encoding.as_deref())?;
+        let mut context = Context::with_encoding(&self.path, encoding)?;
+        let mut links: Box<dyn LinkChecker> = if self.check_links_flag {
+            Box::new(HttpChecker::new(cli::fetch_config(context.settings())))
         } else {
-            Box::new(io::stdout())
+            Box::new(NoCheck)
         };
+        let mut favicons: Box<dyn FaviconFinder> = if self.favicons_flag {
+            Box::new(HttpFavicon::new(cli::fetch_config(context.settings())))
+        } else {
+            Box::new(NoFavicon)
+        };
+
+        let interrupt = if self.watch_flag || self.check_links_flag {
+            Interrupt::install()?
+        } else {
+            Interrupt::new()
+        };
+
+        self.build(&mut context, links.as_mut(), favicons.as_mut(), &interrupt)?;
 
-        write_readme(&mut context, &mut writer)?;
+        if self.watch_flag {
+            watch::watch(&self.path, DEBOUNCE, &interrupt, || {
+                context.reload()?;
+                self.build(&mut context, links.as_mut(), favicons.as_mut(), &interrupt)?;
+
+                let stamp = Local::now().format("[%H:%M:%S]").to_string();
+                info!(&stamp, "Rebuilt after a change.");
+
+                Ok(())
+            })?;
+        }
 
         Ok(Report::new(""))
     }
+
+    /// Writes the generated output to the README.md or to stdout.
+    ///
+    /// Links are checked upfront, stopping when the interrupt is raised.
+    fn build(
+        &self,
+        context: &mut Context,
+        links: &mut dyn LinkChecker,
+        favicons: &mut dyn FaviconFinder,
+        interrupt: &Interrupt,
+    ) -> Result<()> {
+        if !self.keywords.is_empty() {
+            context.store().filter_things(&self.keywords)?;
+        }
+
+        let mut checked = if self.check_links_flag {
+            let things = context.things()?;
+            let urls = things.as_slice().iter().map(|thing| thing.url());
+            let (checked, progress) = link_check::check_all(links, urls, interrupt)?;
+
+            if !progress.is_complete() {
+                eprintln!("Link check {}.", progress);
+            }
+
+            checked
+        } else {
+            Default::default()
+        };
+        let links: &mut dyn LinkChecker = &mut checked;
+
+        if self.output_flag {
+            let readme_path = self.path.join("README.md");
+            let mut buffer = Vec::new();
+
+            self.generate(context, links, favicons, &mut buffer)?;
+
+            let generated = String::from_utf8_lossy(&buffer);
+            let content = match fs::read_to_string(&readme_path) {
+                Ok(existing) => splice(&existing, &self.section, &generated)
+                    .unwrap_or_else(|| generated.to_string()),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => generated.to_string(),
+                Err(err) => return Err(err.into()),
+            };
+
+            fs::write(readme_path, content)?;
+        } else {
+            self.generate(context, links, favicons, &mut io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    fn generate<W: Write + ?Sized>(
+        &self,
+        context: &mut Context,
+        links: &mut dyn LinkChecker,
+        favicons: &mut dyn FaviconFinder,
+        writer: &mut W,
+    ) -> Result<()> {
+        if self.toc_only_flag {
+            write_toc(context, self.group_by, writer)
+        } else {
+            let style = self.table_style(context)?;
+
+            write_readme(context, self.group_by, &style, links, favicons, writer)
+        }
+    }
+
+    fn table_style(&self, context: &mut Context) -> Result<TableStyle> {
+        let anchors = if self.tag_links_flag {
+            let anchors = sections(context, self.group_by)?
+                .into_iter()
+                .filter_map(|(id, heading)| Some((id?, anchor(&heading))))
+                .collect();
+
+            Some(anchors)
+        } else {
+            None
+        };
+
+        Ok(TableStyle {
+            tag_delimiter: self.tag_delimiter.clone(),
+            anchors,
+            summary_max: self.summary_max,
+        })
+    }
+}
+
+/// The grouping of things in the generated README.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// A section per category, each thing appearing once.
+    Category,
+    /// A section per tag, each thing appearing under every tag it has.
+    Tag,
+}
+
+impl FromStr for GroupBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "category" => Ok(Self::Category),
+            "tag" => Ok(Self::Tag),
+            _ => Err(anyhow!(
+                "{} is not a valid grouping. Expected one of: category, tag",
+                s
+            )),
+        }
+    }
+}
+
+/// How the cells of a thing table are written.
+#[derive(Debug, Clone)]
+struct TableStyle {
+    tag_delimiter: String,
+    /// The section anchor of each tag id when tags link to their section.
+    anchors: Option<HashMap<String, String>>,
+    /// The maximum number of characters of a summary, unlimited when `None`.
+    summary_max: Option<usize>,
 }
 
-fn write_readme<W: Write + ?Sized>(context: &mut Context, mut writer: &mut W) -> Result<()> {
-    write_header(context, &mut writer)?;
-    write_body(context, &mut writer)?;
-    write_footer(context, &mut writer)?;
+impl Default for TableStyle {
+    fn default() -> Self {
+        Self {
+            tag_delimiter: "; ".into(),
+            anchors: None,
+            summary_max: None,
+        }
+    }
+}
+
+impl TableStyle {
+    /// The summary, truncated when longer than allowed.
+    fn summary(&self, summary: &str) -> String {
+        match self.summary_max {
+            Some(max) => truncate(summary, max),
+            None => summary.to_string(),
+        }
+    }
+
+    /// Joins the tags with the delimiter, escaping any delimiter found in a tag id.
+    fn tags(&self, tags: &[String]) -> String {
+        let delimiter = self.tag_delimiter.trim();
+        let escaped = format!("\\{}", delimiter);
+        // A delimiter made of Markdown metacharacters is escaped along with the rest of the id.
+        let escape_delimiter = !delimiter.is_empty() && escape_md(delimiter) == delimiter;
+
+        tags.iter()
+            .map(|id| {
+                let text = escape_md(id);
+                let text = if escape_delimiter {
+                    text.replace(delimiter, &escaped)
+                } else {
+                    text
+                };
+                let anchor = self.anchors.as_ref().and_then(|anchors| anchors.get(id));
+
+                match anchor {
+                    Some(anchor) => format!("[{}](#{})", text, anchor),
+                    None => text,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&self.tag_delimiter)
+    }
+}
+
+/// Shortens `text` to at most `max` characters, ellipsis included, cutting on the last word
+/// boundary when there is one.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    // The byte offset right after the first `max - 1` characters, leaving room for the ellipsis.
+    let end = text
+        .char_indices()
+        .nth(max.saturating_sub(1))
+        .map_or(text.len(), |(idx, _)| idx);
+    let head = &text[..end];
+    let head = match head.rfind(char::is_whitespace) {
+        Some(idx) if !text[end..].starts_with(char::is_whitespace) => &head[..idx],
+        _ => head,
+    };
+
+    format!("{}…", head.trim_end())
+}
+
+/// Replaces the region between the `section` markers found in `existing` with `generated`.
+///
+/// Returns `None` when either marker is absent.
+fn splice(existing: &str, section: &str, generated: &str) -> Option<String> {
+    let begin = format!("<!-- {}:begin -->", section);
+    let end = format!("<!-- {}:end -->", section);
+    let start = existing.find(&begin)? + begin.len();
+    let stop = start + existing[start..].find(&end)?;
+
+    Some(format!(
+        "{}\n{}{}",
+        &existing[..start],
+        generated,
+        &existing[stop..]
+    ))
+}
+
+fn write_readme<W: Write + ?Sized>(
+    context: &mut Context,
+    group_by: GroupBy,
+    style: &TableStyle,
+    links: &mut dyn LinkChecker,
+    favicons: &mut dyn FaviconFinder,
+    mut writer: &mut W,
+) -> Result<()> {
+    write_header(context.package(), &mut writer)?;
+
+    match group_by {
+        GroupBy::Category => write_body(context, style, links, favicons, &mut writer)?,
+        GroupBy::Tag => write_body_by_tag(context, style, links, favicons, &mut writer)?,
+    }
+
+    write_footer(context.package(), &mut writer)?;
 
     Ok(())
 }
 
-fn write_header<W: Write>(context: &mut Context, writer: &mut W) -> Result<()> {
-    let package = context.package();
+/// Writes a list linking to every section the body would have for the given grouping.
+fn write_toc<W: Write + ?Sized>(
+    context: &mut Context,
+    group_by: GroupBy,
+    writer: &mut W,
+) -> Result<()> {
+    for (_, heading) in sections(context, group_by)? {
+        writeln!(writer, "* [{}](#{})", escape_md(&heading), anchor(&heading))?;
+    }
+
+    Ok(())
+}
+
+/// The sections the body has for the given grouping, with the id of the tag each one is for.
+///
+/// The untagged and uncategorised sections have no tag.
+fn sections(context: &mut Context, group_by: GroupBy) -> Result<Vec<(Option<String>, String)>> {
+    let store = context.store();
+    let mut sections = Vec::new();
+
+    match group_by {
+        GroupBy::Category => {
+            for category in TagStore::list_categories(&store.conn)? {
+                let heading = category.name().unwrap_or(category.id()).clone();
+                sections.push((Some(category.id().clone()), heading));
+            }
+
+            if !ThingStore::list_uncategorised(&store.conn)?.is_empty() {
+                sections.push((None, "Uncategorised".to_string()));
+            }
+        }
+        GroupBy::Tag => {
+            for tag in TagStore::list(&store.conn)? {
+                if !ThingStore::list_by_tag(&store.conn, tag.id())?.is_empty() {
+                    let heading = tag.name().unwrap_or(tag.id()).clone();
+                    sections.push((Some(tag.id().clone()), heading));
+                }
+            }
+
+            if !ThingStore::list_untagged(&store.conn)?.is_empty() {
+                sections.push((None, "Untagged".to_string()));
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+/// The anchor GitHub generates for a heading: lowercase, punctuation dropped and spaces as
+/// dashes.
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Writes the title and description, plus the homepage and keywords when present.
+fn write_header<W: Write>(package: &Package, writer: &mut W) -> Result<()> {
     writeln!(writer, "# {}\n", package.title())?;
     writeln!(writer, "{}\n", package.description())?;
 
+    if let Some(homepage) = package.homepage() {
+        writeln!(writer, "Homepage: <{}>\n", homepage)?;
+    }
+
+    if !package.keywords().is_empty() {
+        writeln!(writer, "Keywords: {}\n", package.keywords().join(", "))?;
+    }
+
     Ok(())
 }
 
-fn write_footer<W: Write>(context: &mut Context, writer: &mut W) -> Result<()> {
-    let package = context.package();
+/// Writes the contributors, licences and last update when present.
+fn write_footer<W: Write>(package: &Package, writer: &mut W) -> Result<()> {
+    if !package.contributors().is_empty() {
+        writeln!(writer, "\n## Contributors\n")?;
+
+        for contributor in package.contributors() {
+            match &contributor.path {
+                Some(path) => write!(writer, "* [{}]({})", contributor.title, path)?,
+                None => write!(writer, "* {}", contributor.title)?,
+            }
+
+            writeln!(writer, " ({})", contributor.role)?;
+        }
+    }
 
     if !package.licenses().is_empty() {
         writeln!(writer, "\n## Licence\n")?;
@@ -76,59 +444,605 @@ fn write_footer<W: Write>(context: &mut Context, writer: &mut W) -> Result<()> {
         }
     }
 
+    if let Some(modified) = package.modified() {
+        writeln!(writer, "\nLast updated: {}.", modified.format("%Y-%m-%d"))?;
+    }
+
     Ok(())
 }
 
-fn write_body<W: Write>(context: &mut Context, writer: &mut W) -> Result<()> {
+fn write_body<W: Write>(
+    context: &mut Context,
+    style: &TableStyle,
+    links: &mut dyn LinkChecker,
+    favicons: &mut dyn FaviconFinder,
+    writer: &mut W,
+) -> Result<()> {
     let store = context.store();
-    let categories = TagStore::list_categories(&store.conn)?;
 
-    if categories.is_empty() {
+    if ThingStore::is_empty(&store.conn)? {
         writeln!(writer, "**This collection is empty**")?;
 
         return Ok(());
     }
 
-    for category in categories {
+    for category in TagStore::list_categories(&store.conn)? {
         let things = ThingStore::list_categorised(&store.conn, &category.id())?;
         writeln!(
             writer,
             "\n## {}\n",
-            category.name().unwrap_or(category.id())
+            escape_md(category.name().unwrap_or(category.id()))
         )?;
 
         if let Some(summary) = category.summary() {
             writeln!(writer, "{}\n", summary)?;
         }
 
-        write_table(writer, &things)?;
+        write_table(writer, &things, style, links, favicons)?;
+    }
+
+    // Things whose category is missing from the tags would be lost otherwise.
+    let uncategorised = ThingStore::list_uncategorised(&store.conn)?;
+
+    if !uncategorised.is_empty() {
+        writeln!(writer, "\n## Uncategorised\n")?;
+        write_table(writer, &uncategorised, style, links, favicons)?;
     }
 
     Ok(())
 }
 
-fn write_table<W: Write>(writer: &mut W, things: &Vec<thing::Thing>) -> Result<()> {
+/// Writes a section per tag with its things, followed by the untagged things.
+fn write_body_by_tag<W: Write>(
+    context: &mut Context,
+    style: &TableStyle,
+    links: &mut dyn LinkChecker,
+    favicons: &mut dyn FaviconFinder,
+    writer: &mut W,
+) -> Result<()> {
+    let store = context.store();
+    let mut is_empty = true;
+
+    for tag in TagStore::list(&store.conn)? {
+        let things = ThingStore::list_by_tag(&store.conn, tag.id())?;
+
+        if things.is_empty() {
+            continue;
+        }
+
+        is_empty = false;
+        writeln!(
+            writer,
+            "\n## {}\n",
+            escape_md(tag.name().unwrap_or(tag.id()))
+        )?;
+
+        if let Some(summary) = tag.summary() {
+            writeln!(writer, "{}\n", summary)?;
+        }
+
+        write_table(writer, &things, style, links, favicons)?;
+    }
+
+    let untagged = ThingStore::list_untagged(&store.conn)?;
+
+    if !untagged.is_empty() {
+        is_empty = false;
+        writeln!(writer, "\n## Untagged\n")?;
+        write_table(writer, &untagged, style, links, favicons)?;
+    }
+
+    if is_empty {
+        writeln!(writer, "**This collection is empty**")?;
+    }
+
+    Ok(())
+}
+
+fn write_table<W: Write>(
+    writer: &mut W,
+    things: &Vec<thing::Thing>,
+    style: &TableStyle,
+    links: &mut dyn LinkChecker,
+    favicons: &mut dyn FaviconFinder,
+) -> Result<()> {
     writeln!(writer, "| name | summary | tags |")?;
     writeln!(writer, "| - | - | - |")?;
 
     for thing in things {
-        write_row(writer, thing)?;
+        write_row(writer, thing, style, links, favicons)?;
     }
 
     Ok(())
 }
 
-fn write_row<W: Write>(writer: &mut W, thing: &thing::Thing) -> Result<()> {
-    let link = format!("[{}]({})", &thing.name, &thing.url);
-    let summary = &thing.summary;
+fn write_row<W: Write>(
+    writer: &mut W,
+    thing: &thing::Thing,
+    style: &TableStyle,
+    links: &mut dyn LinkChecker,
+    favicons: &mut dyn FaviconFinder,
+) -> Result<()> {
+    let mut link = match favicons.find(&thing.url) {
+        Some(favicon) => format!("![]({}) ", favicon),
+        None => String::new(),
+    };
+    link.push_str(&format!("[{}]({})", escape_md(&thing.name), &thing.url));
+
+    match links.check(&thing.url) {
+        Some(true) => link.push_str(" ✓"),
+        Some(false) => link.push_str(" ✗"),
+        None => {}
+    }
+    let summary = thing
+        .summary
+        .as_deref()
+        .map(|summary| escape_md(&style.summary(summary)))
+        .unwrap_or_default();
 
     writeln!(
         writer,
         "| {} | {} | {} |",
         link,
-        summary.as_ref().unwrap_or(&"".to_string()),
-        thing.tags.join("; ")
+        summary,
+        style.tags(&thing.tags)
     )?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::change::Data;
+    use crate::entities::tag;
+    use crate::fixtures;
+    use crate::services::staging;
+
+    #[test]
+    fn canonical_header_and_footer() -> Result<()> {
+        let package: Package = serde_json::from_str(&fixtures::canonical())?;
+        let mut buffer = Vec::new();
+
+        write_header(&package, &mut buffer)?;
+        write_footer(&package, &mut buffer)?;
+
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(actual.contains("Homepage: <https://github.com/arnau/some-nothingness>"));
+        assert!(actual.contains("Keywords: sqlite, some-thing"));
+        assert!(actual.contains("* [Arnau Siches](https://www.seachess.net/) (author)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn section_preserves_surrounding_content() -> Result<()> {
+        let dir = fixtures::package();
+        let readme_path = dir.path().join("README.md");
+        let cmd = Cmd {
+            output_flag: true,
+            section: "some".into(),
+            group_by: GroupBy::Category,
+            toc_only_flag: false,
+            check_links_flag: false,
+            favicons_flag: false,
+            tag_delimiter: "; ".into(),
+            tag_links_flag: false,
+            summary_max: None,
+            keywords: Vec::new(),
+            encoding: None,
+            watch_flag: false,
+            path: dir.path().to_path_buf(),
+        };
+
+        fs::write(
+            &readme_path,
+            "Intro prose.\n\n<!-- some:begin -->\nstale\n<!-- some:end -->\n\nOutro prose.\n",
+        )?;
+
+        cmd.run()?;
+        let first = fs::read_to_string(&readme_path)?;
+        cmd.run()?;
+        let second = fs::read_to_string(&readme_path)?;
+
+        assert!(second.starts_with("Intro prose.\n\n<!-- some:begin -->\n# Some Test"));
+        assert!(second.ends_with("<!-- some:end -->\n\nOutro prose.\n"));
+        assert!(!second.contains("stale"));
+        assert_eq!(first, second);
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_tag_repeats_multi_tag_things() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        for id in ["database", "embedded"] {
+            staging::add(
+                &mut context,
+                Data::Tag {
+                    id: id.into(),
+                    name: None,
+                    summary: None,
+                },
+            )?;
+        }
+        staging::add(
+            &mut context,
+            Data::Thing {
+                url: "https://www.sqlite.org/".into(),
+                name: "SQLite".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["database".into(), "embedded".into()],
+            },
+        )?;
+        staging::add(
+            &mut context,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            },
+        )?;
+        staging::commit(&mut context)?;
+
+        let mut buffer = Vec::new();
+        write_body_by_tag(
+            &mut context,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut NoFavicon,
+            &mut buffer,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+        let row = "| [SQLite](https://www.sqlite.org/) |  | database; embedded |";
+
+        assert_eq!(actual.matches(row).count(), 2);
+        assert!(actual.contains("## database"));
+        assert!(actual.contains("## embedded"));
+        assert!(actual.ends_with(
+            "## Untagged\n\n| name | summary | tags |\n| - | - | - |\n| [Rust](https://www.rust-lang.org/) |  |  |\n"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn category_summary_is_section_prose() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        staging::add(
+            &mut context,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            },
+        )?;
+        staging::replace_tag(
+            &mut context,
+            tag::Record::new(
+                "miscellaneous".into(),
+                Some("Miscellaneous".into()),
+                Some("Curated odds and ends.".into()),
+            ),
+        )?;
+        staging::commit(&mut context)?;
+
+        let mut buffer = Vec::new();
+        write_body(
+            &mut context,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut NoFavicon,
+            &mut buffer,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(actual.starts_with("\n## Miscellaneous\n\nCurated odds and ends.\n\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn splice_without_markers() {
+        assert_eq!(splice("Hand-written.\n", "some", "generated"), None);
+    }
+
+    #[test]
+    fn toc_lists_categories() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        staging::add(
+            &mut context,
+            Data::Tag {
+                id: "database".into(),
+                name: Some("Data & Storage".into()),
+                summary: None,
+            },
+        )?;
+        for (url, category) in [
+            ("https://www.sqlite.org/", "database"),
+            ("https://www.rust-lang.org/", "miscellaneous"),
+        ] {
+            staging::add(
+                &mut context,
+                Data::Thing {
+                    url: url.into(),
+                    name: url.into(),
+                    summary: None,
+                    category: category.into(),
+                    tags: vec![],
+                },
+            )?;
+        }
+        staging::commit(&mut context)?;
+
+        let mut buffer = Vec::new();
+        write_toc(&mut context, GroupBy::Category, &mut buffer)?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert_eq!(
+            actual,
+            "* [Data & Storage](#data--storage)\n* [Miscellaneous](#miscellaneous)\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rows_annotated_with_link_status() -> Result<()> {
+        struct Mocked;
+
+        impl LinkChecker for Mocked {
+            fn check(&mut self, url: &str) -> Option<bool> {
+                match url {
+                    "https://up.test/" => Some(true),
+                    "https://down.test/" => Some(false),
+                    _ => None,
+                }
+            }
+        }
+
+        let things: Vec<thing::Thing> = ["up", "down", "unknown"]
+            .iter()
+            .map(|name| thing::Thing {
+                url: format!("https://{}.test/", name),
+                name: name.to_string(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            })
+            .collect();
+
+        let mut buffer = Vec::new();
+        write_table(
+            &mut buffer,
+            &things,
+            &TableStyle::default(),
+            &mut Mocked,
+            &mut NoFavicon,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(actual.contains("| [up](https://up.test/) ✓ |"));
+        assert!(actual.contains("| [down](https://down.test/) ✗ |"));
+        assert!(actual.contains("| [unknown](https://unknown.test/) |"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rows_escape_markdown() -> Result<()> {
+        let thing = thing::Thing {
+            url: "https://fish.test/".into(),
+            name: "<b>Fish</b>".into(),
+            summary: Some(r#"Fish & "chips" | *fried*"#.into()),
+            category: "miscellaneous".into(),
+            tags: vec!["take_away".into()],
+        };
+
+        let mut buffer = Vec::new();
+        write_row(
+            &mut buffer,
+            &thing,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut NoFavicon,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert_eq!(
+            actual,
+            concat!(
+                r#"| [\<b\>Fish\</b\>](https://fish.test/) "#,
+                r#"| Fish \& "chips" \| \*fried\* | take\_away |"#,
+                "\n"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rows_with_favicon_when_found() -> Result<()> {
+        struct Mocked;
+
+        impl FaviconFinder for Mocked {
+            fn find(&mut self, url: &str) -> Option<String> {
+                match url {
+                    "https://found.test/" => Some("https://found.test/favicon.ico".into()),
+                    _ => None,
+                }
+            }
+        }
+
+        let things: Vec<thing::Thing> = ["found", "missing"]
+            .iter()
+            .map(|name| thing::Thing {
+                url: format!("https://{}.test/", name),
+                name: name.to_string(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            })
+            .collect();
+
+        let mut buffer = Vec::new();
+        write_table(
+            &mut buffer,
+            &things,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut Mocked,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(
+            actual.contains("| ![](https://found.test/favicon.ico) [found](https://found.test/) |")
+        );
+        assert!(actual.contains("| [missing](https://missing.test/) |"));
+        assert_eq!(actual.matches("![]").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tag_delimiter_and_links() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        for id in ["database", "a,b"] {
+            staging::add(
+                &mut context,
+                Data::Tag {
+                    id: id.into(),
+                    name: None,
+                    summary: None,
+                },
+            )?;
+        }
+        staging::add(
+            &mut context,
+            Data::Thing {
+                url: "https://www.sqlite.org/".into(),
+                name: "SQLite".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["database".into(), "a,b".into()],
+            },
+        )?;
+        staging::commit(&mut context)?;
+
+        let cmd = Cmd::parse_from(["build", "--group-by", "tag", "--tag-delimiter", ", "]);
+        let plain = cmd.table_style(&mut context)?;
+
+        assert_eq!(
+            plain.tags(&["database".into(), "a,b".into()]),
+            "database, a\\,b"
+        );
+
+        let cmd = Cmd::parse_from(["build", "--group-by", "tag", "--tag-links"]);
+        let style = cmd.table_style(&mut context)?;
+        let mut buffer = Vec::new();
+        write_body_by_tag(
+            &mut context,
+            &style,
+            &mut NoCheck,
+            &mut NoFavicon,
+            &mut buffer,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(actual.contains("| [a,b](#ab); [database](#database) |"));
+
+        // Every link points at a section heading.
+        for (_, heading) in sections(&mut context, GroupBy::Tag)? {
+            assert!(actual.contains(&format!("## {}", heading)));
+        }
+        assert!(actual.contains("\n## a,b\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncate_ascii_summary() {
+        let summary = "A language empowering everyone.";
+
+        assert_eq!(truncate(summary, 31), summary);
+        assert_eq!(truncate(summary, 30), "A language empowering…");
+        assert_eq!(truncate(summary, 11), "A language…");
+        assert_eq!(truncate(summary, 12), "A language…");
+        assert_eq!(truncate("Supercalifragilistic", 6), "Super…");
+    }
+
+    #[test]
+    fn truncate_multibyte_summary() {
+        let summary = "Café à la carte";
+
+        assert_eq!(truncate(summary, 15), summary);
+        assert_eq!(truncate(summary, 7), "Café à…");
+        assert_eq!(truncate(summary, 6), "Café…");
+        assert_eq!(truncate("日本語のテキスト", 4), "日本語…");
+    }
+
+    #[test]
+    fn empty_package() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        let mut buffer = Vec::new();
+        write_body(
+            &mut context,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut NoFavicon,
+            &mut buffer,
+        )?;
+
+        assert_eq!(
+            String::from_utf8(buffer).expect("valid UTF-8"),
+            "**This collection is empty**\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn things_without_a_known_category() -> Result<()> {
+        let dir = fixtures::package();
+        let mut thing = fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(
+            thing,
+            "https://www.rust-lang.org/,Rust,A language.,language"
+        )?;
+
+        let mut context = Context::new(dir.path())?;
+        let mut buffer = Vec::new();
+        write_body(
+            &mut context,
+            &TableStyle::default(),
+            &mut NoCheck,
+            &mut NoFavicon,
+            &mut buffer,
+        )?;
+        let actual = String::from_utf8(buffer).expect("valid UTF-8");
+
+        assert!(!actual.contains("This collection is empty"));
+        assert!(actual.starts_with("\n## Uncategorised\n"));
+        assert!(actual.contains("| [Rust](https://www.rust-lang.org/) | A language. |  |"));
+
+        Ok(())
+    }
+}
@@ -1,11 +1,14 @@
 use clap::Parser;
-use std::fs::{remove_dir, remove_file, File};
+use std::fs::{read_dir, remove_dir, remove_dir_all, remove_file, File};
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
 use super::Prompter;
+use crate::info;
 use crate::package::resource::Resource;
 use crate::package::{self, Package};
+use crate::services::history::HISTORY_PATH;
+use crate::store::{RUN_METADATA_PATH, STAGING_PATH};
 use crate::{Event, Report, Result, SomeError};
 
 /// Destroys a Some package in an existing directory.
@@ -18,6 +21,9 @@ use crate::{Event, Report, Result, SomeError};
 /// * `data/tag.csv`
 /// * `data/thing_tag.csv`
 /// * `data/` if after removing all the above it is empty
+///
+/// With `--all` the whole package directory is removed instead, refusing when it has files unknown
+/// to Some unless `--force` is given.
 #[derive(Debug, Parser)]
 pub struct Cmd {
     /// The location where to find the Some package to be destroyed.
@@ -26,31 +32,68 @@ pub struct Cmd {
     /// Run without any user confirmation.
     #[clap(long = "force")]
     force_flag: bool,
+    /// Remove the whole package directory, including the staging database.
+    #[clap(long = "all")]
+    all_flag: bool,
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<Report> {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
         let mut prompter = Prompter::new()?;
         let full_path = &self.path.canonicalize()?;
-        let package_file = File::open(package::DESCRIPTOR_PATH)
+        let package_file = File::open(full_path.join(package::DESCRIPTOR_PATH))
             .map_err(|_| SomeError::MissingPackageDescriptor(full_path.display().to_string()))?;
         let package_reader = BufReader::new(package_file);
-        let package: Package = serde_json::from_reader(package_reader)?;
+        let package = Package::from_reader(package_reader)?;
         let package_name = package.name().to_string();
 
+        if dry_run {
+            if self.all_flag {
+                info!("Would remove:", full_path.display());
+            } else {
+                for resource in package.resources().iter().filter(|r| r.has_path()) {
+                    info!("Would remove:", resource.absolute_path(full_path).display());
+                }
+                info!(
+                    "Would remove:",
+                    full_path.join(package::DESCRIPTOR_PATH).display()
+                );
+            }
+
+            return Ok(Report::new("Dry run. Nothing destroyed."));
+        }
+
         if !self.force_flag {
             let name = prompter.demand("Confirm the name of the package to destroy")?;
 
-            if name != package_name {
-                return Err(SomeError::SealError(format!(
-                    "The given name `{}` does not match the data package `{}`.",
-                    name, package_name
-                )));
+            confirm_name(&package_name, &name)?;
+        }
+
+        if self.all_flag {
+            if !self.force_flag {
+                let answer = prompter.ask_once(&format!(
+                    "remove `{}` and everything in it? (y/N)",
+                    full_path.display()
+                ))?;
+
+                if answer.as_deref() != Some("y") {
+                    prompter.flush()?;
+
+                    return Ok(Report::new("Nothing destroyed."));
+                }
             }
+
+            destroy_all(full_path, &package, self.force_flag)?;
+            prompter.flush()?;
+
+            return Ok(Report::new(format!(
+                "Package `{}` and its directory destroyed.",
+                &package_name
+            )));
         }
 
-        for resource in package.resources {
-            remove_resource(&self.path, &resource)?;
+        for resource in package.resources.iter().filter(|r| r.has_path()) {
+            remove_resource(&self.path, resource)?;
         }
 
         if remove_dir(&self.path.join(package::DATA_PATH)).is_err() {
@@ -66,9 +109,134 @@ impl Cmd {
     }
 }
 
+/// Fails unless the name `got` from the user matches the `expected` package name.
+fn confirm_name(expected: &str, got: &str) -> Result<()> {
+    if got != expected {
+        return Err(SomeError::ConfirmationMismatch {
+            expected: expected.to_string(),
+            got: got.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Removes the package directory, failing when it has unknown files unless `force` is set.
+fn destroy_all(root: &Path, package: &Package, force: bool) -> Result<()> {
+    if !force {
+        let files = unknown_files(root, package)?;
+
+        if !files.is_empty() {
+            return Err(SomeError::UnknownFiles {
+                path: root.display().to_string(),
+                files: files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect(),
+            });
+        }
+    }
+
+    remove_dir_all(root)?;
+
+    Ok(())
+}
+
+/// Lists the files under `root`, relative to it, that are neither the descriptor, a resource, the
+/// staging database, the run metadata nor the history.
+fn unknown_files(root: &Path, package: &Package) -> Result<Vec<PathBuf>> {
+    let mut known: Vec<PathBuf> = package
+        .resources()
+        .iter()
+        .map(|resource| resource.path().to_path_buf())
+        .collect();
+    known.push(PathBuf::from(package::DESCRIPTOR_PATH));
+    known.push(PathBuf::from(STAGING_PATH));
+    known.push(PathBuf::from(format!("{}-journal", STAGING_PATH)));
+    known.push(PathBuf::from(RUN_METADATA_PATH));
+    known.push(PathBuf::from(HISTORY_PATH));
+
+    let mut unknown = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in read_dir(&dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+
+                if !known.contains(&relative) {
+                    unknown.push(relative);
+                }
+            }
+        }
+    }
+
+    unknown.sort();
+
+    Ok(unknown)
+}
+
 fn remove_resource<P: AsRef<Path>>(path: P, resource: &Resource) -> Result<()> {
-    let path = path.as_ref().join(resource.path());
+    let path = resource.absolute_path(path.as_ref());
     remove_file(&path)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn confirmation_mismatch() {
+        assert!(confirm_name("some-test", "some-test").is_ok());
+        assert!(matches!(
+            confirm_name("some-test", "some-tset"),
+            Err(SomeError::ConfirmationMismatch { expected, got })
+                if expected == "some-test" && got == "some-tset"
+        ));
+    }
+
+    #[test]
+    fn all_with_an_extra_file() -> Result<()> {
+        let dir = fixtures::package();
+        let root = dir.path().to_path_buf();
+        let package = Package::from_path(&root)?;
+        std::fs::write(root.join("notes.txt"), "Not part of the package.")?;
+
+        assert_eq!(
+            unknown_files(&root, &package)?,
+            vec![PathBuf::from("notes.txt")]
+        );
+        assert!(matches!(
+            destroy_all(&root, &package, false),
+            Err(SomeError::UnknownFiles { .. })
+        ));
+        assert!(root.join(package::DESCRIPTOR_PATH).is_file());
+
+        destroy_all(&root, &package, true)?;
+
+        assert!(!root.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn all_and_force_remove_unknown_files() -> Result<()> {
+        let dir = fixtures::package();
+        let root = dir.path().to_path_buf();
+        std::fs::write(root.join("notes.txt"), "Not part of the package.")?;
+        let path = root.display().to_string();
+
+        Cmd::parse_from(["destroy", &path, "--all", "--force"]).run(false)?;
+
+        assert!(!root.exists());
+
+        Ok(())
+    }
+}
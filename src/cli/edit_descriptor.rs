@@ -0,0 +1,157 @@
+use clap::Parser;
+use std::env;
+use std::fs::{copy, remove_file};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::validate;
+use crate::package;
+use crate::{Report, Result, SomeError};
+
+/// The environment variable holding the command to edit the descriptor with.
+pub const EDITOR_VAR: &str = "EDITOR";
+
+/// The backup taken before editing, next to the descriptor.
+const BACKUP_PATH: &str = "datapackage.json.bak";
+
+/// Where an edit that doesn't validate is kept, next to the descriptor.
+const REJECTED_PATH: &str = "datapackage.json.rejected";
+
+/// Opens the descriptor of a Some package in `$EDITOR`, keeping the edit only when it validates.
+///
+/// A backup is taken before editing. When the edited descriptor doesn't validate the original is
+/// restored and the edit is kept in `datapackage.json.rejected` to pick up from.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be edited.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<Report> {
+        let editor = env::var(EDITOR_VAR)
+            .ok()
+            .filter(|editor| !editor.trim().is_empty())
+            .ok_or(SomeError::NoEditor)?;
+
+        edit_descriptor(&self.path, |location| open_editor(&editor, location))?;
+
+        Ok(Report::new("Descriptor updated."))
+    }
+}
+
+/// Runs the editor command, which may carry its own arguments, on the given file.
+fn open_editor(editor: &str, location: &Path) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or(SomeError::NoEditor)?;
+    let status = Command::new(program).args(words).arg(location).status()?;
+
+    if !status.success() {
+        return Err(SomeError::Unknown(format!(
+            "editor exit status `{}`",
+            status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Backs up the descriptor in `root`, lets `edit` change it and validates the outcome.
+///
+/// The backup is dropped once done. When the edited descriptor is invalid it is copied to
+/// [`REJECTED_PATH`] before restoring the original. When `edit` fails the original is restored.
+fn edit_descriptor<F>(root: &Path, edit: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let location = root.join(package::DESCRIPTOR_PATH);
+
+    if !location.exists() {
+        return Err(SomeError::MissingPackageDescriptor(
+            root.display().to_string(),
+        ));
+    }
+
+    let backup = root.join(BACKUP_PATH);
+    copy(&location, &backup)?;
+
+    if let Err(err) = edit(&location) {
+        copy(&backup, &location)?;
+        remove_file(&backup)?;
+
+        return Err(err);
+    }
+
+    let validation = validate::Cmd::new(root.to_path_buf()).run()?;
+
+    if validation.exit_code() != 0 {
+        let rejected = root.join(REJECTED_PATH);
+
+        copy(&location, &rejected)?;
+        copy(&backup, &location)?;
+        remove_file(&backup)?;
+
+        return Err(SomeError::InvalidDescriptor {
+            rejected: rejected.display().to_string(),
+            findings: validation
+                .findings()
+                .iter()
+                .filter(|finding| finding.severity == validate::Severity::Error)
+                .map(|finding| finding.message.clone())
+                .collect(),
+        });
+    }
+
+    remove_file(&backup)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs::{read_to_string, write};
+
+    #[test]
+    fn invalid_json_restores_original() -> Result<()> {
+        let dir = fixtures::package();
+        let location = dir.path().join(package::DESCRIPTOR_PATH);
+        let original = read_to_string(&location)?;
+
+        let actual = edit_descriptor(dir.path(), |location| {
+            write(location, "{ \"name\": ")?;
+
+            Ok(())
+        });
+
+        assert!(matches!(actual, Err(SomeError::InvalidDescriptor { .. })));
+        assert_eq!(read_to_string(&location)?, original);
+        assert_eq!(
+            read_to_string(dir.path().join(REJECTED_PATH))?,
+            "{ \"name\": "
+        );
+        assert!(!dir.path().join(BACKUP_PATH).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn valid_edit_is_kept() -> Result<()> {
+        let dir = fixtures::package();
+        let location = dir.path().join(package::DESCRIPTOR_PATH);
+        let edited = read_to_string(&location)?.replacen("Some Test", "Some Edited", 1);
+
+        edit_descriptor(dir.path(), |location| {
+            write(location, &edited)?;
+
+            Ok(())
+        })?;
+
+        assert_eq!(read_to_string(&location)?, edited);
+        assert!(!dir.path().join(BACKUP_PATH).exists());
+
+        Ok(())
+    }
+}
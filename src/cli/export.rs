@@ -0,0 +1,313 @@
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::context::Context;
+use crate::entities::change::Event;
+use crate::info;
+use crate::package::resource::InlineRow;
+use crate::package::Package;
+use crate::services::history;
+use crate::store::{quote_identifier, ChangeStore};
+use crate::{Report, Result, SomeError};
+
+/// Writes the package descriptor, optionally with the data of every resource embedded in it.
+///
+/// With `--inline` the result is a single file carrying the whole collection, handy to share a
+/// small package. Staged changes are not included.
+///
+/// With `--since` the result is instead a patch with the changes made after the given time, in
+/// the order they were made, for another copy of the collection to apply.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be exported.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// Embed the rows of every resource as inline `data` instead of pointing to their files.
+    #[clap(long = "inline")]
+    inline_flag: bool,
+    /// The file to write. Defaults to stdout.
+    #[clap(long, short, value_name = "file")]
+    output: Option<PathBuf>,
+    /// Only write the changes made after this RFC 3339 timestamp, e.g.
+    /// `2024-01-31T10:00:00Z`. Deletions are kept as tombstones with just the id.
+    #[clap(long, value_name = "rfc3339", conflicts_with = "inline_flag")]
+    since: Option<String>,
+    /// Overwrite the output file if it exists.
+    #[clap(long = "force")]
+    force_flag: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+        let (kind, document) = match &self.since {
+            Some(since) => {
+                let patch = patch(&mut context, parse_since(since)?, Utc::now())?;
+
+                ("Patch", serde_json::to_string_pretty(&patch)?)
+            }
+            None => {
+                let package = if self.inline_flag {
+                    inline_package(&mut context)?
+                } else {
+                    context.package().clone()
+                };
+
+                ("Package", package.to_canonical_string()?)
+            }
+        };
+
+        let output = match &self.output {
+            Some(output) => output,
+            None => {
+                io::stdout().write_all(document.as_bytes())?;
+
+                return Ok(Report::new(""));
+            }
+        };
+
+        if output.exists() && !self.force_flag {
+            return Err(SomeError::Conflict(output.display().to_string()));
+        }
+
+        if context.dry_run() {
+            info!("Would write:", output.display());
+
+            return Ok(Report::new("Dry run. Nothing written."));
+        }
+
+        fs::write(output, document)?;
+
+        Ok(Report::new(format!(
+            "{} written to `{}`.",
+            kind,
+            output.display()
+        )))
+    }
+}
+
+/// The changes made to a package within a period of time.
+#[derive(Debug, Serialize)]
+pub struct Patch {
+    /// The id of the package the changes apply to.
+    pub package: String,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// The changes in the order they were made, each with its operation, kind and data.
+    pub changes: Vec<Event>,
+}
+
+/// The changes made after `since` and up to `until`: the committed ones recorded in the package
+/// history followed by any still staged.
+fn patch(context: &mut Context, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Patch> {
+    let package = context.package().id.clone();
+    let mut changes = history::between(context.path(), since.timestamp(), until.timestamp())?;
+    let staged = ChangeStore::between(&context.tx()?, since.timestamp(), until.timestamp())?;
+
+    changes.extend(staged);
+
+    Ok(Patch {
+        package,
+        since,
+        until,
+        changes,
+    })
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-01-31T10:00:00Z`.
+fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    let timestamp = DateTime::parse_from_rfc3339(value)?;
+
+    Ok(timestamp.with_timezone(&Utc))
+}
+
+/// A copy of the package with the committed rows of every resource inline.
+///
+/// Empty values are left out of the rows, the same way an empty CSV cell means no value.
+fn inline_package(context: &mut Context) -> Result<Package> {
+    let mut package = context.package().clone();
+
+    for resource in package.resources.iter_mut() {
+        let fields: Vec<String> = resource
+            .field_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let columns: Vec<String> = fields.iter().map(|field| quote_identifier(field)).collect();
+        let query = format!(
+            "SELECT {} FROM source.{}",
+            columns.join(", "),
+            quote_identifier(&resource.id().to_string())
+        );
+        let rows = context.store().query(&query, [], |row| {
+            let mut inline = InlineRow::new();
+
+            for (idx, field) in fields.iter().enumerate() {
+                let value: Option<String> = row.get(idx)?;
+
+                if let Some(value) = value.filter(|value| !value.is_empty()) {
+                    inline.insert(field.clone(), value.into());
+                }
+            }
+
+            Ok(inline)
+        })?;
+
+        resource.inline(rows);
+    }
+
+    Ok(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::change::{Change, Data, DataRef};
+    use crate::fixtures;
+    use crate::package::DESCRIPTOR_PATH;
+    use crate::services::staging;
+    use crate::store::{params, Repository, TagStore, ThingStore};
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn patch_since_timestamp() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+        let changes = [
+            (
+                "2024-01-01 10:00:00",
+                Change::Insert(Data::Tag {
+                    id: "old".into(),
+                    name: None,
+                    summary: None,
+                }),
+            ),
+            (
+                "2024-01-02 10:00:00",
+                Change::Insert(Data::Thing {
+                    url: "https://www.rust-lang.org/".into(),
+                    name: "Rust".into(),
+                    summary: None,
+                    category: "miscellaneous".into(),
+                    tags: vec![],
+                }),
+            ),
+            (
+                "2024-01-03 10:00:00",
+                Change::Delete(DataRef::Tag { id: "old".into() }),
+            ),
+        ];
+
+        for (timestamp, change) in &changes {
+            context.store().conn.execute(
+                "INSERT INTO staging.changelog (timestamp, data) VALUES (?1, ?2)",
+                params![timestamp, serde_json::to_string(change)?],
+            )?;
+        }
+
+        let patch = patch(
+            &mut context,
+            parse_since("2024-01-01T12:00:00Z")?,
+            parse_since("2024-02-01T00:00:00Z")?,
+        )?;
+        let applied: Vec<String> = patch
+            .changes
+            .iter()
+            .map(|event| event.change.to_string())
+            .collect();
+
+        assert_eq!(
+            applied,
+            vec!["insert thing https://www.rust-lang.org/", "delete tag old"]
+        );
+
+        let document = serde_json::to_value(&patch)?;
+
+        assert_eq!(
+            document["changes"][1]["change"],
+            serde_json::json!({"operation": "Delete", "kind": "Tag", "id": "old"})
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn patch_from_a_previous_run() -> Result<()> {
+        let dir = fixtures::package();
+
+        {
+            let mut context = Context::new(dir.path())?;
+            staging::add(
+                &mut context,
+                Data::Thing {
+                    url: "https://www.rust-lang.org/".into(),
+                    name: "Rust".into(),
+                    summary: None,
+                    category: "miscellaneous".into(),
+                    tags: vec![],
+                },
+            )?;
+            staging::commit(&mut context)?;
+        }
+
+        let mut context = Context::new(dir.path())?;
+        let patch = patch(
+            &mut context,
+            parse_since("2000-01-01T00:00:00Z")?,
+            Utc::now(),
+        )?;
+        let applied: Vec<String> = patch
+            .changes
+            .iter()
+            .map(|event| event.change.to_string())
+            .collect();
+
+        assert_eq!(applied, vec!["insert thing https://www.rust-lang.org/"]);
+
+        let now = Utc::now();
+        assert!(patch(&mut context, now, now)?.changes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_round_trip() -> Result<()> {
+        let dir = fixtures::package();
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(
+            thing,
+            "https://www.rust-lang.org/,Rust,A language.,miscellaneous"
+        )?;
+
+        let mut context = Context::new(dir.path())?;
+        let package = inline_package(&mut context)?;
+
+        assert!(package.resources().iter().all(|r| r.is_inline()));
+
+        let shared = tempfile::tempdir()?;
+        fs::write(
+            shared.path().join(DESCRIPTOR_PATH),
+            package.to_canonical_string()?,
+        )?;
+
+        let mut loaded = Context::new(shared.path())?;
+        let store = loaded.store();
+        let rust = ThingStore::get(&store.conn, &"https://www.rust-lang.org/".to_string())?
+            .expect("the inline thing");
+
+        assert_eq!(rust.name(), "Rust");
+        assert_eq!(rust.summary(), Some("A language.".into()));
+        assert_eq!(ThingStore::len(&store.conn)?, 1);
+        assert_eq!(TagStore::list(&store.conn)?.len(), 1);
+        assert!(!shared.path().join("data").exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use clap::Parser;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use crate::info;
+use crate::package::{self, Package};
+use crate::{Report, Result, SomeError};
+
+/// Rewrites the descriptor of a Some package in its canonical form.
+///
+/// Keys follow the Some declaration order with two-space indentation so hand-edited descriptors
+/// produce quiet diffs. Descriptors with keys unknown to Some are left untouched.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be formatted.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let message = match format_descriptor(&self.path, dry_run)? {
+            true if dry_run => "Dry run. Descriptor not formatted.",
+            true => "Descriptor formatted.",
+            false => "Descriptor already formatted.",
+        };
+
+        Ok(Report::new(message))
+    }
+}
+
+/// Rewrites the descriptor in `root` when it differs from its canonical form, returning whether it
+/// changed. On a dry run the descriptor is only reported.
+fn format_descriptor(root: &Path, dry_run: bool) -> Result<bool> {
+    let location = root.join(package::DESCRIPTOR_PATH);
+    let raw = read_to_string(&location)
+        .map_err(|_| SomeError::MissingPackageDescriptor(root.display().to_string()))?;
+    let canonical = Package::from_str_strict(&raw)?.to_canonical_string()?;
+
+    if canonical == raw {
+        return Ok(false);
+    }
+
+    if dry_run {
+        info!("Would format:", location.display());
+
+        return Ok(true);
+    }
+
+    write(&location, canonical)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use serde_json::Value;
+
+    #[test]
+    fn idempotent() -> Result<()> {
+        let dir = fixtures::package();
+        let location = dir.path().join(package::DESCRIPTOR_PATH);
+
+        // A hand edit with every key sorted alphabetically and no indentation.
+        let descriptor: Value = serde_json::from_str(&read_to_string(&location)?)?;
+        write(&location, serde_json::to_string(&descriptor)?)?;
+
+        assert!(format_descriptor(dir.path(), false)?);
+
+        let first = std::fs::read(&location)?;
+
+        assert!(!format_descriptor(dir.path(), false)?);
+        assert_eq!(std::fs::read(&location)?, first);
+        assert!(String::from_utf8(first)
+            .unwrap()
+            .starts_with("{\n  \"profile\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run_leaves_the_descriptor() -> Result<()> {
+        let dir = fixtures::package();
+        let location = dir.path().join(package::DESCRIPTOR_PATH);
+        let descriptor: Value = serde_json::from_str(&read_to_string(&location)?)?;
+        write(&location, serde_json::to_string(&descriptor)?)?;
+        let before = std::fs::read(&location)?;
+
+        assert!(format_descriptor(dir.path(), true)?);
+        assert_eq!(std::fs::read(&location)?, before);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,540 @@
+use anyhow::anyhow;
+use clap::Parser;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::context::Context;
+use crate::entities::change::Data;
+use crate::entities::row::{locate, RowError};
+use crate::entities::tag::TagError;
+use crate::entities::thing::ThingError;
+use crate::lenses::thing::{validate_url_with, SchemeAllowlist};
+use crate::services::batch::{self, Interrupt, Progress};
+use crate::services::staging;
+use crate::store::ThingtagStore;
+use crate::{Report, Result, SomeError};
+
+/// Imports the rows of an external CSV file into a resource of a Some package.
+///
+/// Columns are mapped by header name. Use `--no-header` for a file without a header row whose
+/// columns follow the resource schema order.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The resource to import into: thing or tag.
+    resource: String,
+    /// The CSV file to import.
+    file: PathBuf,
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// Read the first row as data, taking the columns in the resource schema order.
+    #[clap(long = "no-header")]
+    no_header_flag: bool,
+    /// What to do with a row whose key already exists: skip, replace or error.
+    #[clap(long, value_name = "strategy", default_value = "error")]
+    on_conflict: OnConflict,
+}
+
+/// What to do with a row whose key already exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnConflict {
+    /// Leave the existing row as it is.
+    Skip,
+    /// Replace the existing row with the imported one.
+    Replace,
+    /// Abort the whole import.
+    Error,
+}
+
+impl FromStr for OnConflict {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "replace" => Ok(Self::Replace),
+            "error" => Ok(Self::Error),
+            _ => Err(anyhow!(
+                "{} is not a valid strategy. Expected one of: skip, replace, error",
+                s
+            )),
+        }
+    }
+}
+
+/// How many rows ended up in each outcome.
+#[derive(Debug, Default, PartialEq)]
+struct Outcome {
+    added: usize,
+    replaced: usize,
+    skipped: usize,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+        let fields = resource_fields(&context, &self.resource)?;
+        let file = File::open(&self.file)?;
+        let rows = read_rows(file, &fields, !self.no_header_flag, &self.resource)?;
+        let interrupt = Interrupt::install()?;
+        let (outcome, progress) = stage_rows(
+            &mut context,
+            &self.resource,
+            &fields,
+            rows,
+            self.on_conflict,
+            &interrupt,
+        )?;
+
+        staging::commit(&mut context)?;
+
+        let mut message = format!(
+            "Imported into `{}`: {} added, {} replaced, {} skipped.",
+            self.resource, outcome.added, outcome.replaced, outcome.skipped
+        );
+
+        if !progress.is_complete() {
+            message.push_str(&format!(" Import {}.", progress));
+        }
+
+        Ok(Report::new(message))
+    }
+}
+
+/// Stages a change for every row, handling the ones whose key exists as `on_conflict` says.
+///
+/// When the interrupt is raised the rows staged so far are kept and the rest are left out.
+fn stage_rows(
+    context: &mut Context,
+    resource: &str,
+    fields: &[String],
+    rows: Vec<Vec<String>>,
+    on_conflict: OnConflict,
+    interrupt: &Interrupt,
+) -> Result<(Outcome, Progress)> {
+    let mut outcome = Outcome::default();
+    let url_schemes = context.settings().url_schemes.clone();
+
+    let progress = batch::run(rows, interrupt, |row| {
+        let data = to_data(resource, fields, row, &url_schemes)?;
+
+        match staging::add(context, data.clone()) {
+            Ok(()) => outcome.added += 1,
+            Err(err) if is_duplicate(&err) => match on_conflict {
+                OnConflict::Skip => outcome.skipped += 1,
+                OnConflict::Replace => {
+                    let data = with_current_tags(context, data)?;
+                    staging::replace(context, data)?;
+                    outcome.replaced += 1;
+                }
+                OnConflict::Error => return Err(err),
+            },
+            Err(err) => return Err(err),
+        }
+
+        Ok(())
+    })?;
+
+    Ok((outcome, progress))
+}
+
+/// Gives a thing the tags it already has, as the imported rows carry none and a replacement
+/// would drop them otherwise.
+fn with_current_tags(context: &mut Context, data: Data) -> Result<Data> {
+    match data {
+        Data::Thing {
+            url,
+            name,
+            summary,
+            category,
+            ..
+        } => {
+            let tags = ThingtagStore::list_for_thing(&context.store().conn, &url)?
+                .iter()
+                .map(|relation| relation.tag_id().clone())
+                .collect();
+
+            Ok(Data::Thing {
+                url,
+                name,
+                summary,
+                category,
+                tags,
+            })
+        }
+        data => Ok(data),
+    }
+}
+
+/// Whether the error is about a key that already exists.
+fn is_duplicate(err: &SomeError) -> bool {
+    matches!(
+        err,
+        SomeError::Thing(ThingError::Duplicate(_)) | SomeError::Tag(TagError::Duplicate(_))
+    )
+}
+
+/// The field names of the given resource schema.
+fn resource_fields(context: &Context, resource: &str) -> Result<Vec<String>> {
+    let resource = context
+        .package()
+        .resources()
+        .iter()
+        .find(|candidate| candidate.id().to_string() == resource)
+        .ok_or_else(|| SomeError::Unknown(format!("resource `{}`", resource)))?;
+
+    Ok(resource
+        .field_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// Reads the CSV rows returning their values in the `fields` order.
+///
+/// With a header, columns are mapped by name and any field missing from the header is left empty.
+/// Without one, every row must have exactly one value per field.
+fn read_rows<R: Read>(
+    rdr: R,
+    fields: &[String],
+    has_header: bool,
+    resource: &str,
+) -> Result<Vec<Vec<String>>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(rdr);
+
+    // The position of each field in the file, if present.
+    let positions: Vec<Option<usize>> = if has_header {
+        let headers = rdr
+            .headers()
+            .map_err(|err| locate::<SomeError>(resource, err))?
+            .clone();
+
+        if let Some(unknown) = headers
+            .iter()
+            .find(|header| !fields.iter().any(|field| field == header))
+        {
+            return Err(SomeError::Unknown(format!(
+                "field `{}`, expected one of: {}",
+                unknown,
+                fields.join(", ")
+            )));
+        }
+
+        fields
+            .iter()
+            .map(|field| headers.iter().position(|header| header == field))
+            .collect()
+    } else {
+        (0..fields.len()).map(Some).collect()
+    };
+    let expected = if has_header {
+        rdr.headers()?.len()
+    } else {
+        fields.len()
+    };
+    let mut rows = Vec::new();
+
+    for result in rdr.records() {
+        let record = result.map_err(|err| locate::<SomeError>(resource, err))?;
+
+        if record.len() != expected {
+            return Err(RowError {
+                resource: resource.to_string(),
+                row: record
+                    .position()
+                    .map_or(0, |pos| pos.record() + u64::from(!has_header)),
+                reason: format!(
+                    "wrong number of fields (expected {}, found {})",
+                    expected,
+                    record.len()
+                ),
+            }
+            .into());
+        }
+
+        let row = positions
+            .iter()
+            .map(|position| {
+                position
+                    .and_then(|idx| record.get(idx))
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect();
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Builds the change data for a row of the given resource.
+fn to_data(
+    resource: &str,
+    fields: &[String],
+    row: Vec<String>,
+    url_schemes: &SchemeAllowlist,
+) -> Result<Data> {
+    let value = |name: &str| -> Option<String> {
+        fields
+            .iter()
+            .position(|field| field == name)
+            .and_then(|idx| row.get(idx))
+            .filter(|value| !value.is_empty())
+            .cloned()
+    };
+    let required = |name: &str| -> Result<String> {
+        value(name).ok_or_else(|| SomeError::FieldRequired(name.to_string()))
+    };
+
+    match resource {
+        "thing" => {
+            let url = required("url")?;
+            validate_url_with(&url, url_schemes)?;
+
+            Ok(Data::Thing {
+                url,
+                name: required("name")?,
+                summary: value("summary"),
+                category: required("category_id")?,
+                tags: Vec::new(),
+            })
+        }
+        "tag" => Ok(Data::Tag {
+            id: required("id")?,
+            name: value("name"),
+            summary: value("summary"),
+        }),
+        _ => Err(SomeError::Unknown(format!(
+            "resource `{}`, expected thing or tag",
+            resource
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::store::ChangeStore;
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    fn fields() -> Vec<String> {
+        ["url", "name", "summary", "category_id"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect()
+    }
+
+    fn rust() -> Vec<String> {
+        vec![
+            "https://www.rust-lang.org/".into(),
+            "Rust".into(),
+            "A language.".into(),
+            "miscellaneous".into(),
+        ]
+    }
+
+    #[test]
+    fn headered() -> Result<()> {
+        let csv = concat!(
+            "url,name,summary,category_id\n",
+            "https://www.rust-lang.org/,Rust,A language.,miscellaneous\n",
+        );
+        let rows = read_rows(csv.as_bytes(), &fields(), true, "thing")?;
+
+        assert_eq!(rows, vec![rust()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn headerless() -> Result<()> {
+        let csv = "https://www.rust-lang.org/,Rust,A language.,miscellaneous\n";
+        let rows = read_rows(csv.as_bytes(), &fields(), false, "thing")?;
+
+        assert_eq!(rows, vec![rust()]);
+
+        let short = "https://www.rust-lang.org/,Rust\n";
+
+        assert!(matches!(
+            read_rows(short.as_bytes(), &fields(), false, "thing"),
+            Err(SomeError::Row(RowError { row: 1, .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reordered_header() -> Result<()> {
+        let csv = "name,category_id,url\nRust,miscellaneous,https://www.rust-lang.org/\n";
+        let rows = read_rows(csv.as_bytes(), &fields(), true, "thing")?;
+        let mut expected = rust();
+        expected[2] = String::new();
+
+        assert_eq!(rows, vec![expected]);
+        assert!(matches!(
+            to_data(
+                "thing",
+                &fields(),
+                rows[0].clone(),
+                &SchemeAllowlist::default()
+            )?,
+            Data::Thing { summary: None, .. }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn disallowed_scheme() {
+        let row = vec![
+            "javascript:alert(1)".into(),
+            "Alert".into(),
+            String::new(),
+            "miscellaneous".into(),
+        ];
+
+        assert!(matches!(
+            to_data("thing", &fields(), row, &SchemeAllowlist::default()),
+            Err(SomeError::Thing(ThingError::DisallowedScheme { .. }))
+        ));
+    }
+
+    fn tag_fields() -> Vec<String> {
+        vec!["id".into(), "name".into(), "summary".into()]
+    }
+
+    /// Stages a file overlapping the `miscellaneous` tag of the fixture, returning the outcome
+    /// and the tag resource once committed.
+    fn import_overlapping(on_conflict: OnConflict) -> Result<(Outcome, String)> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+        let csv = "id,name,summary\nmiscellaneous,Misc,Changed.\nrust,Rust,\n";
+        let rows = read_rows(csv.as_bytes(), &tag_fields(), true, "tag")?;
+        let (outcome, _) = stage_rows(
+            &mut context,
+            "tag",
+            &tag_fields(),
+            rows,
+            on_conflict,
+            &Interrupt::new(),
+        )?;
+
+        staging::commit(&mut context)?;
+
+        Ok((outcome, fs::read_to_string(context.resource_path("tag"))?))
+    }
+
+    #[test]
+    fn on_conflict_skip() -> Result<()> {
+        let (outcome, tags) = import_overlapping(OnConflict::Skip)?;
+
+        assert_eq!(
+            outcome,
+            Outcome {
+                added: 1,
+                replaced: 0,
+                skipped: 1
+            }
+        );
+        assert!(tags.contains("miscellaneous,Miscellaneous,The unclassifiable."));
+        assert!(tags.contains("rust,Rust,"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn on_conflict_replace() -> Result<()> {
+        let (outcome, tags) = import_overlapping(OnConflict::Replace)?;
+
+        assert_eq!(
+            outcome,
+            Outcome {
+                added: 1,
+                replaced: 1,
+                skipped: 0
+            }
+        );
+        assert!(tags.contains("miscellaneous,Misc,Changed."));
+        assert!(!tags.contains("Miscellaneous"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn on_conflict_error() {
+        assert!(matches!(
+            import_overlapping(OnConflict::Error),
+            Err(SomeError::Tag(TagError::Duplicate(id))) if id == "miscellaneous"
+        ));
+    }
+
+    #[test]
+    fn interrupted_import() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+        let csv = "id,name,summary\nrust,Rust,\ngo,Go,\n";
+        let rows = read_rows(csv.as_bytes(), &tag_fields(), true, "tag")?;
+        let interrupt = Interrupt::new();
+        interrupt.raise();
+
+        let (outcome, progress) = stage_rows(
+            &mut context,
+            "tag",
+            &tag_fields(),
+            rows,
+            OnConflict::Error,
+            &interrupt,
+        )?;
+
+        assert_eq!(outcome, Outcome::default());
+        assert_eq!(progress.to_string(), "interrupted; 0 of 2 processed");
+        assert_eq!(ChangeStore::len(&context.tx()?)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn on_conflict_replace_keeps_tags() -> Result<()> {
+        let dir = fixtures::package();
+        let data = dir.path().join("data");
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(data.join("thing.csv"))?;
+        writeln!(thing, "https://www.rust-lang.org/,Rust,,miscellaneous")?;
+        let mut thing_tag = OpenOptions::new()
+            .append(true)
+            .open(data.join("thing_tag.csv"))?;
+        writeln!(thing_tag, "https://www.rust-lang.org/,miscellaneous")?;
+
+        let mut context = Context::new(dir.path())?;
+        let csv = "url,name,summary,category_id\nhttps://www.rust-lang.org/,Rust,A language.,miscellaneous\n";
+        let rows = read_rows(csv.as_bytes(), &fields(), true, "thing")?;
+        let (outcome, _) = stage_rows(
+            &mut context,
+            "thing",
+            &fields(),
+            rows,
+            OnConflict::Replace,
+            &Interrupt::new(),
+        )?;
+
+        staging::commit(&mut context)?;
+
+        assert_eq!(outcome.replaced, 1);
+        assert!(fs::read_to_string(data.join("thing.csv"))?.contains("A language."));
+        assert!(fs::read_to_string(data.join("thing_tag.csv"))?
+            .contains("https://www.rust-lang.org/,miscellaneous"));
+
+        Ok(())
+    }
+}
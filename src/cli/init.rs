@@ -1,16 +1,21 @@
+use chrono::{DateTime, Utc};
 use clap::Parser;
-use std::fs::{create_dir, File};
+use std::fs::{create_dir_all, File};
 use std::io::prelude::*;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use uuid::Uuid;
 
 use super::Prompter;
+use crate::info;
 use crate::lenses;
-use crate::lenses::licence::{fetch_licenses, LicenceSet};
-use crate::package::core::{Licence, Name};
-use crate::package::resource::Resource;
+use crate::lenses::licence::{fetch_licenses, licences_or_bundled, LicenceChoices, LicenceSet};
+use crate::package::core::{Licence, Name, PackageError};
+use crate::package::resource::{Fingerprint, Resource};
 use crate::package::{self, Package, PackageBuilder};
-use crate::{Report, Result};
+use crate::settings;
+use crate::{Report, Result, SomeError};
 
 /// Creates a new Some package in an existing directory.
 ///
@@ -20,37 +25,102 @@ use crate::{Report, Result};
 /// [Tabular Data Package]: https://specs.frictionlessdata.io/tabular-data-package/
 #[derive(Debug, Parser)]
 pub struct Cmd {
-    /// The location where to scaffold a new Some package.
+    /// The location where to scaffold a new Some package. With `--dir`, the name of the new
+    /// package directory instead.
     #[clap(default_value = ".")]
     path: PathBuf,
+    /// The parent directory where to create a new package directory named after `path`.
+    #[clap(long, alias = "output-dir", value_name = "parent")]
+    dir: Option<PathBuf>,
+    /// The creation timestamp (RFC 3339) for the package. Defaults to now.
+    #[clap(long, value_name = "timestamp")]
+    created: Option<String>,
+    /// The package id (UUID). Defaults to a random one, or one derived from `SOME_UUID_SEED` when
+    /// set.
+    #[clap(long, value_name = "uuid")]
+    id: Option<String>,
+    /// A URL or path to an existing package descriptor to scaffold from instead of asking. Its id
+    /// and creation timestamp are kept as they are.
+    #[clap(
+        long,
+        value_name = "url-or-path",
+        conflicts_with_all = &["id", "created", "minimal_flag"]
+    )]
+    from: Option<String>,
+    /// Overwrite any existing package file.
+    #[clap(long = "force")]
+    force_flag: bool,
+    /// Only ask for the name, title and description, skipping every optional field.
+    #[clap(long = "minimal")]
+    minimal_flag: bool,
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<Report> {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let package = match &self.from {
+            Some(source) => load_package(source)?,
+            None => self.ask_package()?,
+        };
+
+        let target = self.target(dry_run)?;
+
+        if dry_run {
+            for path in package_files(&target, &package) {
+                info!("Would create:", path.display());
+            }
+
+            return Ok(Report::new("Dry run. Package not created."));
+        }
+
+        scaffold(&target, &package, self.force_flag)?;
+
+        let report = Report::new(format!(
+            "Success. Package created at {}.",
+            package.created.to_rfc3339()
+        ));
+        Ok(report)
+    }
+
+    /// Builds a new package from the user answers.
+    fn ask_package(&self) -> Result<Package> {
+        let builder = self.base_builder()?;
         let mut prompter = Prompter::new()?;
 
         // TODO: Nicely recover from a bad package name.
         // TODO: Provide hint for allowed chars.
-        let raw_name = prompter.demand("package name")?;
+        let raw_name = match self.default_name() {
+            Some(name) => prompter.ask_default("package name", &name)?,
+            None => prompter.demand("package name")?,
+        };
         let name = Name::from_str(&raw_name)?;
         let title = prompter.demand("title")?;
         let description = prompter.demand("description")?;
+        let mut builder = builder.name(name).title(title).description(description);
+
+        if self.minimal_flag {
+            prompter.flush()?;
+
+            return Ok(builder.build()?);
+        }
+
         let homepage = prompter.ask_once("homepage (URL)")?;
-        let resources = lenses::package::resources();
         let licenses: Vec<Licence> =
             if let Some(answer) = prompter.ask_once("do you want to add a licence? (y/N)")? {
                 if answer == "y" {
-                    let items = fetch_licenses()?;
+                    let LicenceChoices { items, offline } = licences_or_bundled(fetch_licenses);
+                    let items: LicenceSet = items.into_iter().collect();
+                    let field = if offline {
+                        "licenses (offline subset)"
+                    } else {
+                        "licenses"
+                    };
 
-                    let choices = prompter.read_choices(
-                        items.clone().into_iter().collect::<LicenceSet>(),
-                        "licenses",
-                    )?;
+                    let choices = prompter.read_choices(items.as_skim_buffer(), field)?;
 
                     let mut result: Vec<Licence> = Vec::new();
-                    for licence in items {
+                    for licence in &items {
                         if choices.iter().any(|name| name == &licence.name) {
-                            result.push(licence);
+                            result.push(licence.clone());
                         }
                     }
 
@@ -64,12 +134,7 @@ impl Cmd {
         // let contributors: Vec<Contributor>,
         // let keywords: Vec<String>,
 
-        let mut builder = PackageBuilder::default()
-            .name(name)
-            .title(title)
-            .description(description)
-            .licenses(licenses)
-            .resources(resources);
+        builder = builder.licenses(licenses);
 
         if let Some(value) = homepage {
             builder = builder.homepage(value);
@@ -77,22 +142,139 @@ impl Cmd {
 
         let package = builder.build()?;
 
-        // Write Package
-        write_package(&self.path.join(package::DESCRIPTOR_PATH), &package)?;
-        create_dir(&self.path.join(package::DATA_PATH))?;
-        for resource in package.resources {
-            write_resource(&self.path, &resource)?;
+        prompter.flush()?;
+
+        Ok(package)
+    }
+
+    /// The directory to scaffold into, created when `--dir` is given unless on a dry run.
+    fn target(&self, dry_run: bool) -> Result<PathBuf> {
+        match &self.dir {
+            Some(parent) => {
+                let target = parent.join(&self.path);
+
+                if !dry_run {
+                    create_dir_all(&target)?;
+                }
+
+                Ok(target)
+            }
+            None => Ok(self.path.clone()),
         }
+    }
 
-        prompter.flush()?;
+    /// The name of the new package directory, when creating one.
+    fn default_name(&self) -> Option<String> {
+        if self.dir.is_none() {
+            return None;
+        }
 
-        let report = Report::new("Success");
-        Ok(report)
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+
+    /// A builder with the Some resources and the `--created` and `--id` flags applied.
+    ///
+    /// Flags are parsed before asking anything so a bad value fails early.
+    fn base_builder(&self) -> Result<PackageBuilder> {
+        let created = self.created.as_deref().map(parse_created).transpose()?;
+        let id = match self.id.as_deref() {
+            Some(raw) => Some(parse_id(raw)?),
+            None => settings::current()
+                .uuid_seed
+                .as_deref()
+                .map(package::seeded_id),
+        };
+        let mut builder = PackageBuilder::default().resources(lenses::package::resources());
+
+        if let Some(value) = created {
+            builder = builder.timestamp(value);
+        }
+
+        if let Some(value) = id {
+            builder = builder.id(value);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Reads a package descriptor from either a URL or a local path.
+fn load_package(source: &str) -> Result<Package> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let body = reqwest::blocking::get(source)?.error_for_status()?.text()?;
+
+        Ok(Package::from_reader(body.as_bytes())?)
+    } else {
+        let file = File::open(source)?;
+
+        Ok(Package::from_reader(BufReader::new(file))?)
+    }
+}
+
+/// Writes an empty file for each of the package resources and the package descriptor recording
+/// their size and hash.
+///
+/// Fails when any of these files already exist unless `force` is set.
+pub(crate) fn scaffold(path: &Path, package: &Package, force: bool) -> Result<()> {
+    let descriptor_path = path.join(package::DESCRIPTOR_PATH);
+
+    if !force {
+        let paths = package_files(path, package);
+
+        if let Some(existing) = paths.iter().find(|path| path.exists()) {
+            return Err(SomeError::Conflict(existing.display().to_string()));
+        }
+    }
+
+    let mut package = package.clone();
+
+    for resource in package.resources.iter_mut() {
+        let resource_path = resource.absolute_path(path);
+
+        if let Some(parent) = resource_path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        write_resource(path, resource)?;
+        resource.set_fingerprint(Fingerprint::from_path(&resource_path)?);
     }
+
+    write_package(&descriptor_path, &package)?;
+
+    Ok(())
+}
+
+/// The descriptor and resource files a package scaffolded in `path` consists of.
+fn package_files(path: &Path, package: &Package) -> Vec<PathBuf> {
+    let mut paths = vec![path.join(package::DESCRIPTOR_PATH)];
+    paths.extend(
+        package
+            .resources()
+            .iter()
+            .map(|resource| resource.absolute_path(path)),
+    );
+
+    paths
+}
+
+/// Parses an RFC 3339 timestamp such as `2020-12-29T10:11:12Z`.
+fn parse_created(value: &str) -> Result<DateTime<Utc>> {
+    let timestamp = DateTime::parse_from_rfc3339(value)?;
+
+    Ok(timestamp.with_timezone(&Utc))
+}
+
+/// Parses a UUID such as `4e6a1a3e-5a8e-4e4b-9f3a-2b9c1c1d2e3f`, normalising it to lowercase.
+fn parse_id(value: &str) -> Result<String> {
+    let id = Uuid::parse_str(value).map_err(|_| PackageError::MalformedId(value.into()))?;
+
+    Ok(id.to_string())
 }
 
 fn write_package<P: AsRef<Path>>(path: P, package: &Package) -> Result<()> {
-    let s = serde_json::to_string_pretty(&package)?;
+    let s = package.to_canonical_string()?;
     let mut file = File::create(path)?;
     file.write_all(s.as_bytes())?;
 
@@ -100,7 +282,7 @@ fn write_package<P: AsRef<Path>>(path: P, package: &Package) -> Result<()> {
 }
 
 fn write_resource<P: AsRef<Path>>(path: P, resource: &Resource) -> Result<()> {
-    let path = path.as_ref().join(resource.path());
+    let path = resource.absolute_path(path.as_ref());
     let file = File::create(path)?;
     let mut wtr = csv::Writer::from_writer(&file);
     let field_names = resource.field_names();
@@ -116,3 +298,134 @@ fn write_resource<P: AsRef<Path>>(path: P, resource: &Resource) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn valid_created() -> Result<()> {
+        let actual = parse_created("2020-12-29T10:11:12+01:00")?;
+
+        assert_eq!(actual.to_rfc3339(), "2020-12-29T09:11:12+00:00");
+
+        Ok(())
+    }
+
+    #[test]
+    fn scaffold_from_local_descriptor() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = dir.path().join("remote.json");
+        std::fs::write(&source, fixtures::canonical())?;
+
+        let package = load_package(source.to_str().expect("a UTF-8 path"))?;
+        scaffold(dir.path(), &package, false)?;
+
+        let thing = std::fs::read_to_string(dir.path().join("data/thing.csv"))?;
+        let thing_tag = std::fs::read_to_string(dir.path().join("data/thing_tag.csv"))?;
+
+        assert_eq!(thing, "url,name,summary,category_id\n");
+        assert_eq!(thing_tag, "thing_id,tag_id\n");
+        assert!(matches!(
+            scaffold(dir.path(), &package, false),
+            Err(SomeError::Conflict(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_conflicts_with_the_asked_fields() {
+        for extra in [
+            &["--id", "1d3a8b5e-7a51-4e4c-9a4f-0d0cbe5ef2a4"][..],
+            &["--created", "2020-12-29T10:11:12+01:00"],
+            &["--minimal"],
+        ] {
+            let args = [&["init", "--from", "datapackage.json"][..], extra].concat();
+
+            assert!(Cmd::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn valid_and_invalid_id() -> Result<()> {
+        let actual = parse_id("4E6A1A3E-5A8E-4E4B-9F3A-2B9C1C1D2E3F")?;
+
+        assert_eq!(actual, "4e6a1a3e-5a8e-4e4b-9f3a-2b9c1c1d2e3f");
+        assert!(matches!(
+            parse_id("not-a-uuid"),
+            Err(SomeError::Package(PackageError::MalformedId(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_created() {
+        let actual = parse_created("29/12/2020");
+
+        assert!(matches!(actual, Err(SomeError::Date(_))));
+    }
+
+    #[test]
+    fn minimal_package() -> Result<()> {
+        let cmd = Cmd {
+            path: ".".into(),
+            created: Some("2020-12-29T10:11:12Z".into()),
+            id: None,
+            dir: None,
+            from: None,
+            force_flag: false,
+            minimal_flag: true,
+        };
+        let package = cmd
+            .base_builder()?
+            .name(Name::from_str("some-minimal")?)
+            .title("Some Minimal")
+            .description("A minimal collection.")
+            .build()?;
+        let descriptor: serde_json::Value = serde_json::to_value(&package)?;
+        let reloaded = Package::from_str_strict(&descriptor.to_string())?;
+
+        assert!(reloaded.licenses().is_empty());
+        assert!(reloaded.homepage().is_none());
+        assert!(descriptor.get("licenses").is_none());
+        assert!(descriptor.get("homepage").is_none());
+        assert_eq!(reloaded.resources().len(), package.resources().len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scaffold_in_new_subdirectory() -> Result<()> {
+        let parent = tempfile::tempdir()?;
+        let cmd = Cmd {
+            path: "my-collection".into(),
+            dir: Some(parent.path().to_path_buf()),
+            created: None,
+            id: None,
+            from: None,
+            force_flag: false,
+            minimal_flag: true,
+        };
+        let name = cmd.default_name().expect("a default name");
+        let package = cmd
+            .base_builder()?
+            .name(Name::from_str(&name)?)
+            .title("My Collection")
+            .description("A collection.")
+            .build()?;
+
+        scaffold(&cmd.target()?, &package, false)?;
+
+        let target = parent.path().join("my-collection");
+
+        assert_eq!(package.name().to_string(), "my-collection");
+        assert!(target.join(package::DESCRIPTOR_PATH).is_file());
+        assert!(target.join("data/thing.csv").is_file());
+        assert!(target.join("data/tag.csv").is_file());
+
+        Ok(())
+    }
+}
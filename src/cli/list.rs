@@ -0,0 +1,170 @@
+use clap::Parser;
+use std::io::{stdout, Write};
+use std::path::PathBuf;
+
+use crate::cli;
+use crate::context::Context;
+use crate::shell::render::{render, Values};
+use crate::shell::{Config, OutputMode};
+use crate::{Result, SomeError};
+
+/// Lists the things of a Some package, including the staged ones.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// How to render the things: tabbed, table, jsonline or vertical.
+    #[clap(long, default_value = "tabbed")]
+    format: OutputMode,
+    /// A comma separated list of the fields to show, e.g. `url,name`. Defaults to all fields.
+    #[clap(long, value_name = "fields")]
+    fields: Option<String>,
+    /// Only list the things with the term in their name, summary or tag ids, ignoring case.
+    /// Repeat it to list the things matching any of the terms.
+    #[clap(long = "keyword", value_name = "term")]
+    keywords: Vec<String>,
+    /// Read the resource CSVs with this encoding, e.g. `windows-1252`, instead of the one the
+    /// descriptor declares.
+    #[clap(long, value_name = "label")]
+    encoding: Option<String>,
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<()> {
+        let mut out = stdout();
+
+        self.write(&mut out)?;
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// Renders every thing to the given writer, returning the number of things written.
+    fn write(&self, out: &mut dyn Write) -> Result<usize> {
+        let encoding = cli::encoding(self.encoding.as_deref())?;
+        let mut context = Context::with_encoding(&self.path, encoding)?;
+        let columns = self.projection(&thing_fields(&context)?)?;
+        let store = context.store();
+
+        if !self.keywords.is_empty() {
+            store.filter_things(&self.keywords)?;
+        }
+
+        // Every column is known to be a field of the thing schema.
+        let query = format!("SELECT {} FROM thing ORDER BY url", columns.join(", "));
+        let mut stmt = store.conn.prepare(&query)?;
+        let rows = Values::new(stmt.query([])?, columns.len());
+        let config = Config {
+            max_rows: 0,
+            ..Default::default()
+        };
+
+        render(self.format, rows, &columns, out, &config)
+    }
+
+    /// The fields to show, in the requested order, failing on any field unknown to the schema.
+    fn projection(&self, known: &[String]) -> Result<Vec<String>> {
+        let fields = match &self.fields {
+            Some(fields) => fields,
+            None => return Ok(known.to_vec()),
+        };
+
+        fields
+            .split(',')
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                if known.iter().any(|name| name == field) {
+                    Ok(field.to_string())
+                } else {
+                    Err(SomeError::Unknown(format!(
+                        "field `{}`, expected one of: {}",
+                        field,
+                        known.join(", ")
+                    )))
+                }
+            })
+            .collect()
+    }
+}
+
+/// The field names of the thing resource schema.
+fn thing_fields(context: &Context) -> Result<Vec<String>> {
+    let resource = context
+        .package()
+        .resources()
+        .iter()
+        .find(|resource| resource.id().to_string() == "thing")
+        .ok_or_else(|| SomeError::Unknown("resource `thing`".into()))?;
+
+    Ok(resource
+        .field_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn projects_two_fields() -> Result<()> {
+        let dir = fixtures::package();
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(
+            thing,
+            "https://www.rust-lang.org/,Rust,A language.,miscellaneous"
+        )?;
+
+        let cmd = Cmd {
+            path: dir.path().to_path_buf(),
+            format: OutputMode::Tabbed,
+            fields: Some("url,name".into()),
+            keywords: Vec::new(),
+            encoding: None,
+        };
+        let mut out = Vec::new();
+        let count = cmd.write(&mut out)?;
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "url                         name\nhttps://www.rust-lang.org/  Rust\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_field() -> Result<()> {
+        let cmd = Cmd {
+            path: ".".into(),
+            format: OutputMode::Tabbed,
+            fields: Some("url,author".into()),
+            keywords: Vec::new(),
+            encoding: None,
+        };
+        let known = vec!["url".to_string(), "name".to_string()];
+
+        assert!(matches!(
+            cmd.projection(&known),
+            Err(SomeError::Unknown(message)) if message.starts_with("field `author`")
+        ));
+        assert_eq!(
+            Cmd {
+                fields: None,
+                ..cmd
+            }
+            .projection(&known)?,
+            known
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::store::{Store, Strategy, STAGING_PATH};
+use crate::{Report, Result};
+
+/// Compacts the on-disk staging database of a Some package.
+///
+/// Packages without a staging database on disk are left as they are.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<Report> {
+        let path = self.path.canonicalize()?;
+        let staging_path = path.join(STAGING_PATH);
+
+        if !staging_path.exists() {
+            return Ok(Report::new("There is no staging database to optimise."));
+        }
+
+        let store = Store::open(path, &Strategy::Disk(staging_path))?;
+        store.optimize()?;
+
+        Ok(Report::new("Staging database optimised."))
+    }
+}
@@ -1,30 +1,78 @@
 use directories::ProjectDirs;
+use encoding_rs::{Encoding, UTF_8};
 use rustyline::{error::ReadlineError, Editor};
 use skim::prelude::*;
 use std::fs;
 use std::path::PathBuf;
 
 pub mod add;
+pub mod backup;
 pub mod build;
 pub mod destroy;
+pub mod edit_descriptor;
+pub mod export;
+pub mod fmt;
+pub mod import;
 pub mod init;
+pub mod list;
+pub mod maintenance;
+pub mod open_db;
+pub mod restore;
 pub mod shell;
+pub mod tag;
+pub mod validate;
+pub mod verify;
 
+use crate::services::fetcher::FetchConfig;
+use crate::settings::{self, Settings};
 use crate::{Result, SomeError};
 
 const HISTORY_PATH: &str = "history.txt";
 const PROJECT_TRIPLE: (&str, &str, &str) = ("", "seachess", "some");
 
+/// How the choice lists are laid out, in skim syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceLayout {
+    pub height: String,
+    pub preview_window: String,
+}
+
+impl ChoiceLayout {
+    /// The skim options for a choice list with the given prompt.
+    fn options<'a>(&'a self, prompt: &'a str, multi: bool) -> Result<SkimOptions<'a>> {
+        SkimOptionsBuilder::default()
+            .height(Some(&self.height))
+            .multi(multi)
+            // An empty command enables the preview window without a command of its own, so each
+            // item shows its own preview.
+            .preview(Some(""))
+            .preview_window(Some(&self.preview_window))
+            .prompt(Some(prompt))
+            .build()
+            .map_err(SomeError::Unknown)
+    }
+}
+
+impl Default for ChoiceLayout {
+    fn default() -> Self {
+        Self {
+            height: "100%".into(),
+            preview_window: "down:10%".into(),
+        }
+    }
+}
+
 /// A support for interacting with the user.
 #[derive(Debug)]
 pub struct Prompter {
     editor: Editor<()>,
     history_path: Option<PathBuf>,
+    layout: ChoiceLayout,
 }
 
 impl Prompter {
     pub fn new() -> Result<Self> {
-        let mut editor = Editor::<()>::new()?;
+        let editor = Editor::<()>::new()?;
         let project_dirs = ProjectDirs::from(PROJECT_TRIPLE.0, PROJECT_TRIPLE.1, PROJECT_TRIPLE.2)
             .ok_or(SomeError::ProjectDir);
         let history_path = project_dirs.and_then(|pd| {
@@ -42,16 +90,25 @@ impl Prompter {
             Ok(file)
         });
 
-        if let Ok(path) = &history_path {
-            let _ = editor.load_history(&path);
+        match history_path {
+            Ok(path) => Self::with_history(editor, path),
+            Err(_) => Ok(Self {
+                editor,
+                history_path: None,
+                layout: settings::current().choice_layout.clone(),
+            }),
         }
+    }
 
-        let prompter = Self {
-            editor,
-            history_path: history_path.ok(),
-        };
+    /// A prompter keeping its history in the given file.
+    fn with_history(mut editor: Editor<()>, path: PathBuf) -> Result<Self> {
+        let _ = editor.load_history(&path);
 
-        Ok(prompter)
+        Ok(Self {
+            editor,
+            history_path: Some(path),
+            layout: settings::current().choice_layout.clone(),
+        })
     }
 
     pub fn history_path(&self) -> Option<&PathBuf> {
@@ -62,6 +119,23 @@ impl Prompter {
         &mut self.editor
     }
 
+    /// Ask for an input offering a default, returned when the answer is empty.
+    ///
+    /// Errors if the user triggers `CTRL-C` or `CTRL-D`.
+    pub fn ask_default(&mut self, field: &str, default: &str) -> Result<String> {
+        match self.editor.readline(&default_prompt(field, default)) {
+            Ok(line) => {
+                self.keep_line(line.as_str());
+
+                Ok(or_default(&line, default))
+            }
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
+                Err(SomeError::FieldRequired(field.to_string()))
+            }
+            Err(err) => Err(SomeError::Readline(err)),
+        }
+    }
+
     /// Ask for an input a `limit` amount of times, but allow no answer after that.
     pub fn read_line_times(&mut self, field: &str, limit: Option<u32>) -> Result<Option<String>> {
         let mut answer = None;
@@ -128,14 +202,7 @@ impl Prompter {
         field: &str,
     ) -> Result<Vec<String>> {
         let prompt = format!("{} (select many): ", field);
-        let options = SkimOptionsBuilder::default()
-            .height(Some("100%"))
-            .multi(true)
-            .preview(Some(""))
-            .preview_window(Some("down:10%"))
-            .prompt(Some(&prompt))
-            .build()
-            .map_err(SomeError::Unknown)?;
+        let options = self.layout.options(&prompt, true)?;
 
         let selected_items = Skim::run_with(&options, Some(items.into()))
             .map(|out| out.selected_items)
@@ -154,14 +221,7 @@ impl Prompter {
         field: &str,
     ) -> Result<Option<String>> {
         let prompt = format!("{} (select one): ", field);
-        let options = SkimOptionsBuilder::default()
-            .height(Some("100%"))
-            .multi(false)
-            .preview(Some(""))
-            .preview_window(Some("down:10%"))
-            .prompt(Some(&prompt))
-            .build()
-            .map_err(SomeError::Unknown)?;
+        let options = self.layout.options(&prompt, false)?;
 
         let selected_item = Skim::run_with(&options, Some(items.into()))
             .map(|out| out.selected_items)
@@ -187,3 +247,117 @@ impl Prompter {
         Ok(())
     }
 }
+
+/// Saves the history on a best-effort basis so it survives an early return. Call
+/// [`Prompter::flush`] to handle any error.
+impl Drop for Prompter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// The encoding for the given `--encoding` label, UTF-8 when there is none.
+///
+/// Labels are the ones browsers accept, e.g. `latin1` or `windows-1252`.
+pub fn encoding(label: Option<&str>) -> Result<&'static Encoding> {
+    match label {
+        None => Ok(UTF_8),
+        Some(label) => Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| SomeError::UnknownEncoding(label.to_string())),
+    }
+}
+
+/// The fetcher settings for a command, spacing requests as the settings say and keeping the
+/// responses in the user cache directory across runs.
+pub fn fetch_config(settings: &Settings) -> FetchConfig {
+    let cache_dir = ProjectDirs::from(PROJECT_TRIPLE.0, PROJECT_TRIPLE.1, PROJECT_TRIPLE.2)
+        .map(|dirs| dirs.cache_dir().to_path_buf());
+
+    FetchConfig {
+        delay: settings.fetch_delay,
+        cache_dir,
+        ..Default::default()
+    }
+}
+
+fn default_prompt(field: &str, default: &str) -> String {
+    format!("{} [{}]: ", field, default)
+}
+
+/// The trimmed answer or the default when there is none.
+fn or_default(answer: &str, default: &str) -> String {
+    match answer.trim() {
+        "" => default.to_string(),
+        answer => answer.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choice_options_from_layout() -> Result<()> {
+        let layout = ChoiceLayout {
+            height: "40%".into(),
+            preview_window: "right:50%".into(),
+        };
+        let options = layout.options("tags (select many): ", true)?;
+
+        assert_eq!(options.height, Some("40%"));
+        assert_eq!(options.preview_window, Some("right:50%"));
+        assert_eq!(options.preview, Some(""));
+        assert_eq!(options.prompt, Some("tags (select many): "));
+        assert!(options.multi);
+
+        Ok(())
+    }
+
+    #[test]
+    fn encoding_labels() -> Result<()> {
+        assert_eq!(encoding(None)?, UTF_8);
+        assert_eq!(encoding(Some("latin1"))?, encoding_rs::WINDOWS_1252);
+        assert!(matches!(
+            encoding(Some("klingon")),
+            Err(SomeError::UnknownEncoding(label)) if label == "klingon"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_in_prompt() {
+        assert_eq!(
+            default_prompt("name", "Scraped Title"),
+            "name [Scraped Title]: "
+        );
+    }
+
+    #[test]
+    fn accept_default() {
+        assert_eq!(or_default("", "Scraped Title"), "Scraped Title");
+        assert_eq!(or_default("  \n", "Scraped Title"), "Scraped Title");
+    }
+
+    #[test]
+    fn override_default() {
+        assert_eq!(or_default(" My Title ", "Scraped Title"), "My Title");
+    }
+
+    #[test]
+    fn drop_saves_history() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(HISTORY_PATH);
+
+        {
+            let mut prompter = Prompter::with_history(Editor::<()>::new()?, path.clone())?;
+            prompter.keep_line("https://www.rust-lang.org/");
+        }
+
+        let history = fs::read_to_string(&path)?;
+
+        assert!(history.contains("https://www.rust-lang.org/"));
+
+        Ok(())
+    }
+}
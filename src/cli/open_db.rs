@@ -0,0 +1,55 @@
+use clap::Parser;
+use std::fs::remove_file;
+use std::path::PathBuf;
+
+use crate::context::Context;
+use crate::info;
+use crate::{Report, Result, SomeError};
+
+/// Writes the collection to a standalone SQLite database for use in external tools.
+///
+/// The database has a `tag`, `thing` and `thing_tag` table with the data found in the resource
+/// files.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be exported.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// The SQLite file to write.
+    #[clap(long, short, value_name = "file", default_value = "collection.sqlite")]
+    output: PathBuf,
+    /// Include the staged changes not committed yet, as the shell sees them.
+    #[clap(long = "overlay")]
+    overlay_flag: bool,
+    /// Overwrite the output file if it exists.
+    #[clap(long = "force")]
+    force_flag: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+
+        if self.output.exists() && !self.force_flag {
+            return Err(SomeError::Conflict(self.output.display().to_string()));
+        }
+
+        if context.dry_run() {
+            info!("Would write:", self.output.display());
+
+            return Ok(Report::new("Dry run. Nothing written."));
+        }
+
+        if self.output.exists() {
+            remove_file(&self.output)?;
+        }
+
+        context.store().export(&self.output, self.overlay_flag)?;
+
+        Ok(Report::new(format!(
+            "Collection written to `{}`.",
+            self.output.display()
+        )))
+    }
+}
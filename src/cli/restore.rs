@@ -0,0 +1,126 @@
+use clap::Parser;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::cli::validate;
+use crate::info;
+use crate::package::Package;
+use crate::{Report, Result, SomeError};
+
+/// Unpacks an archive written by `some backup` and validates the package in it.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The archive to restore.
+    archive: PathBuf,
+    /// The directory to restore the package into. Created when missing.
+    #[clap(long, value_name = "dir", default_value = ".")]
+    into: PathBuf,
+    /// Restore into a directory that is not empty, overwriting any file in the archive.
+    #[clap(long = "force")]
+    force_flag: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        if !self.archive.is_file() {
+            return Err(SomeError::MissingResource(
+                self.archive.display().to_string(),
+            ));
+        }
+
+        if !self.force_flag && !is_empty_dir(&self.into)? {
+            return Err(SomeError::Conflict(self.into.display().to_string()));
+        }
+
+        if dry_run {
+            info!("Would restore into:", self.into.display());
+
+            return Ok(Report::new("Dry run. Nothing written."));
+        }
+
+        let package = restore(&self.archive, &self.into)?;
+
+        Ok(Report::new(format!(
+            "Package `{}` restored into `{}`.",
+            package.name(),
+            self.into.display()
+        )))
+    }
+}
+
+/// Unpacks the archive into `dir`, failing when the result is not a valid package.
+///
+/// The unpacked files are left in place when invalid so they can be inspected.
+pub fn restore(archive: &Path, dir: &Path) -> Result<Package> {
+    fs::create_dir_all(dir)?;
+
+    let decoder = GzDecoder::new(File::open(archive)?);
+    tar::Archive::new(decoder).unpack(dir)?;
+
+    let validation = validate::Cmd::new(dir.to_path_buf()).run()?;
+
+    if validation.exit_code() != 0 {
+        return Err(SomeError::InvalidArchive {
+            archive: archive.display().to_string(),
+            findings: validation
+                .findings()
+                .iter()
+                .filter(|finding| finding.severity == validate::Severity::Error)
+                .map(|finding| finding.message.clone())
+                .collect(),
+        });
+    }
+
+    Ok(Package::from_path(dir)?)
+}
+
+/// Whether the directory has no entries. A missing directory counts as empty.
+fn is_empty_dir(dir: &Path) -> Result<bool> {
+    if !dir.exists() {
+        return Ok(true);
+    }
+
+    Ok(fs::read_dir(dir)?.next().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::backup;
+    use crate::context::Context;
+    use crate::fixtures;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn backup_round_trip() -> Result<()> {
+        let dir = fixtures::package();
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(
+            thing,
+            "https://www.rust-lang.org/,Rust,A language.,miscellaneous"
+        )?;
+
+        let scratch = tempfile::tempdir()?;
+        let archive = scratch.path().join("collection.tar.gz");
+        let package = Package::from_path(dir.path())?;
+        backup::archive(dir.path(), &package, &archive)?;
+
+        let target = scratch.path().join("restored");
+        restore(&archive, &target)?;
+
+        let mut original = Context::new(dir.path())?;
+        let mut restored = Context::new(&target)?;
+
+        assert_eq!(restored.things()?.as_slice(), original.things()?.as_slice());
+        assert_eq!(restored.tags()?.as_slice(), original.tags()?.as_slice());
+        assert_eq!(restored.things()?.len(), 1);
+
+        assert!(!is_empty_dir(&target)?);
+
+        Ok(())
+    }
+}
@@ -1,16 +1,19 @@
 use ansi_term::Colour::Red;
 use clap::Parser;
-use rusqlite::{Connection, Error as RusqliteError, Rows};
+use rusqlite::{Connection, Error as RusqliteError};
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
-use std::collections::HashMap;
+use std::fs::{read_to_string, File};
 use std::io::{stdout, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
-use tabwriter::TabWriter;
+use unicode_width::UnicodeWidthStr;
 
+use crate::color::Palette;
 use crate::context::Context;
-use crate::shell::{Config, OutputMode};
+use crate::shell::render::{render, Values};
+use crate::shell::{Arrangement, Config, OutputMode};
+use crate::store::functions;
 use crate::{Report, Result, SomeError};
 
 /// Starts a new interactive shell (repl-like).
@@ -19,14 +22,31 @@ pub struct Cmd {
     /// The location where to find the Some package to be used.
     #[clap(default_value = ".")]
     path: PathBuf,
+    /// A file of dot commands and `;` terminated SQL statements to run before the prompt.
+    #[clap(long, value_name = "file")]
+    init: Option<PathBuf>,
 }
 
 impl Cmd {
     pub fn run(&self) -> Result<Report> {
         let mut context = Context::new(&self.path)?;
         let store = context.store();
+        let palette = Palette::current();
 
         let mut config = Config::default();
+
+        // A broken init file is reported but never keeps the shell from starting.
+        if let Some(path) = &self.init {
+            match read_to_string(path) {
+                Ok(script) => {
+                    for err in run_script(&store.conn, &script, &mut config) {
+                        println!("{}: {}", path.display(), err);
+                    }
+                }
+                Err(err) => println!("{}: {}", path.display(), err),
+            }
+        }
+
         let mut editor = Editor::<()>::new()?;
         let mut next_expression: String = String::new();
 
@@ -63,7 +83,7 @@ impl Cmd {
                     next_expression.push_str(expr);
                     editor.add_history_entry(&next_expression);
 
-                    match process_query(&store.conn, &next_expression, &config) {
+                    match process_query(&store.conn, &next_expression, &mut config) {
                         Ok(_) => {}
                         Err(SomeError::Sqlite(RusqliteError::SqlInputError {
                             msg,
@@ -74,7 +94,10 @@ impl Cmd {
                             display_error(msg, sql, offset as usize);
                         }
                         Err(SomeError::Sqlite(RusqliteError::SqliteFailure(_, msg))) => {
-                            println!("{}", Red.paint(msg.unwrap_or("unknown error".to_string())));
+                            println!(
+                                "{}",
+                                palette.paint(Red, &msg.unwrap_or("unknown error".to_string()))
+                            );
                         }
 
                         Err(err) => {
@@ -103,7 +126,10 @@ impl Cmd {
                             display_error(msg, sql, offset as usize);
                         }
                         Err(SomeError::Sqlite(RusqliteError::SqliteFailure(_, msg))) => {
-                            println!("{}", Red.paint(msg.unwrap_or("unknown error".to_string())));
+                            println!(
+                                "{}",
+                                palette.paint(Red, &msg.unwrap_or("unknown error".to_string()))
+                            );
                         }
 
                         Err(err) => {
@@ -129,6 +155,59 @@ impl Cmd {
     }
 }
 
+/// Runs every dot command and `;` terminated statement in the script, returning the errors
+/// instead of stopping at the first one.
+///
+/// A dot command takes a whole line. A statement may span several lines.
+fn run_script(conn: &Connection, script: &str, config: &mut Config) -> Vec<SomeError> {
+    let mut errors = Vec::new();
+    let mut statement = String::new();
+
+    for line in script.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with("--") {
+            continue;
+        }
+
+        if statement.is_empty() && line.starts_with('.') {
+            if let Err(err) = process_dotcommand(&line[1..], config) {
+                errors.push(err);
+            }
+
+            continue;
+        }
+
+        statement.push_str(line);
+        statement.push('\n');
+
+        if line.ends_with(';') {
+            if let Err(err) = process_statement(conn, &statement, config) {
+                errors.push(err);
+            }
+
+            statement.clear();
+        }
+    }
+
+    if !statement.trim().is_empty() {
+        if let Err(err) = process_statement(conn, &statement, config) {
+            errors.push(err);
+        }
+    }
+
+    errors
+}
+
+/// Runs a statement as a query when it reads data, as an expression otherwise.
+fn process_statement(conn: &Connection, statement: &str, config: &mut Config) -> Result<()> {
+    if statement.trim_start().to_lowercase().starts_with("select") {
+        process_query(conn, statement, config)
+    } else {
+        process_expression(conn, statement)
+    }
+}
+
 #[inline]
 fn process_expression<'a>(conn: &Connection, query: &str) -> Result<()> {
     let mut stmt = conn.prepare(query)?;
@@ -139,7 +218,7 @@ fn process_expression<'a>(conn: &Connection, query: &str) -> Result<()> {
 }
 
 #[inline]
-fn process_query(conn: &Connection, query: &str, config: &Config) -> Result<()> {
+fn process_query(conn: &Connection, query: &str, config: &mut Config) -> Result<()> {
     let mut stmt = conn.prepare(query)?;
 
     let column_names = stmt
@@ -148,103 +227,90 @@ fn process_query(conn: &Connection, query: &str, config: &Config) -> Result<()>
         .map(|c| c.to_string())
         .collect::<Vec<String>>();
 
-    let rows = stmt.query([])?;
+    let mut rows = Values::new(stmt.query([])?, column_names.len());
 
-    config.output_mode.render(rows, column_names)?;
-
-    Ok(())
-}
-
-impl OutputMode {
-    fn render(self, rows: Rows, column_names: Vec<String>) -> Result<()> {
-        match self {
-            Self::Tabbed => display_tabbed(rows, column_names),
-            Self::Table => display_table(rows, column_names),
-            Self::Jsonline => display_jsonline(rows, column_names),
-        }
-    }
-}
+    // A `.once` sink is consumed by the first query that follows it.
+    let mut out: Box<dyn Write> = match config.once.take() {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(stdout()),
+    };
 
-fn display_tabbed(mut rows: Rows, column_names: Vec<String>) -> Result<()> {
-    let mut tw = TabWriter::new(stdout()).padding(2);
-    tw.write(column_names.join("\t").as_bytes())?;
-    tw.write("\n".as_bytes())?;
-
-    while let Some(row) = rows.next()? {
-        let tup: Vec<String> = column_names
-            .iter()
-            .enumerate()
-            .map(|(idx, _name)| {
-                let value: Option<String> = row.get(idx).unwrap();
-                value.unwrap_or("".to_owned())
-            })
-            .collect();
-        tw.write(tup.join("\t").as_bytes())?;
-        tw.write("\n".as_bytes())?;
+    let taken = render(
+        config.output_mode,
+        &mut rows,
+        &column_names,
+        &mut out,
+        config,
+    )?;
+    out.flush()?;
+
+    for notice in notices(taken, rows.count(), config) {
+        println!("{}", notice);
     }
 
-    tw.flush()?;
-
     Ok(())
 }
 
-fn display_table(mut rows: Rows, column_names: Vec<String>) -> Result<()> {
-    use comfy_table::presets::UTF8_FULL;
-    use comfy_table::*;
-
-    let num = column_names.len();
-    let mut table = Table::new();
-    let headers: Vec<Cell> = column_names.iter().map(|c| Cell::new(c)).collect();
+/// The notices to print after rendering a query result with `taken` rows shown and `remaining`
+/// rows left out.
+fn notices(taken: usize, remaining: usize, config: &Config) -> Vec<String> {
+    let mut list = Vec::new();
 
-    table
-        .load_preset(UTF8_FULL)
-        .set_header(headers)
-        .set_content_arrangement(ContentArrangement::DynamicFullWidth);
+    if taken == 0 && !config.quiet {
+        list.push("(0 rows)".to_string());
+    }
 
-    while let Some(row) = rows.next()? {
-        let mut tup: Vec<Cell> = Vec::with_capacity(num);
+    if let Some(notice) = overflow_notice(remaining) {
+        list.push(notice);
+    }
 
-        for idx in 0..num {
-            let value: Option<String> = row.get(idx).unwrap();
-            tup.push(Cell::new(value.unwrap_or("".to_owned())));
-        }
+    list
+}
 
-        table.add_row(tup);
+/// The notice for rows left out by the `.maxrows` cap, if any.
+fn overflow_notice(remaining: usize) -> Option<String> {
+    if remaining == 0 {
+        None
+    } else {
+        Some(format!(
+            "\u{2026} {} more rows (increase with .maxrows)",
+            remaining
+        ))
     }
+}
 
-    println!("{table}");
+fn display_error(msg: String, query: String, offset: usize) {
+    println!("{}", format_error(&msg, &query, offset, Palette::current()));
+}
 
-    Ok(())
+/// Formats a query error pointing at the given byte offset.
+///
+/// The caret is indented by the display width of the text before the offset so multibyte and
+/// wide characters don't push it off. An offset within a character points at that character.
+fn format_error(msg: &str, query: &str, offset: usize, palette: Palette) -> String {
+    let (left, right) = query.split_at(char_boundary(query, offset));
+    let left = left.replacen('\n', " ", 20);
+    let indent = " ".repeat(left.width());
+
+    format!(
+        "{}{}\n{}^\n{}{}",
+        left,
+        palette.paint(Red, &right.replacen('\n', "", 20)),
+        indent,
+        indent,
+        msg
+    )
 }
 
-fn display_jsonline(mut rows: Rows, column_names: Vec<String>) -> Result<()> {
-    while let Some(row) = rows.next()? {
-        // TODO: column names are unqualified so joins with name clashes won't be colleced
-        // correctly using a HashMap.
-        let tup: HashMap<String, Option<String>> = column_names
-            .iter()
-            .enumerate()
-            .map(|(idx, name)| {
-                let value: Option<String> = row.get(idx).unwrap();
-                (name.clone(), value)
-            })
-            .collect();
-
-        println!("{}", serde_json::to_string_pretty(&tup)?);
-    }
+/// The closest character boundary at or before the byte `offset`, within the text.
+fn char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
 
-    Ok(())
-}
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
 
-fn display_error(msg: String, query: String, offset: usize) {
-    let (left, right) = query.split_at(offset);
-    println!(
-        "{}{}",
-        left.replacen('\n', " ", 20),
-        Red.paint(right.replacen('\n', "", 20))
-    );
-    println!("{}^", " ".repeat(offset));
-    println!("{}{}", " ".repeat(offset), msg);
+    offset
 }
 
 fn process_dotcommand(expr: &str, config: &mut Config) -> Result<()> {
@@ -253,8 +319,30 @@ fn process_dotcommand(expr: &str, config: &mut Config) -> Result<()> {
             "mode" => {
                 process_dotmode(value, config)?;
             }
+            "once" => {
+                config.once = Some(PathBuf::from(value.trim()));
+            }
+            "maxrows" => {
+                config.max_rows = value.trim().parse()?;
+            }
+            "tablewidth" => {
+                config.width = match value.trim().parse()? {
+                    0 => None,
+                    width => Some(width),
+                };
+            }
+            "arrangement" => {
+                config.arrangement = Some(Arrangement::from_str(value.trim())?);
+            }
+            "quiet" => match value.trim() {
+                "on" => config.quiet = true,
+                "off" => config.quiet = false,
+                _ => return Err(SomeError::from(anyhow::anyhow!("Expected `on` or `off`"))),
+            },
             _ => return Err(SomeError::from(anyhow::anyhow!("Unknown command"))),
         }
+    } else if expr.trim() == "help" {
+        print!("{}", help());
     } else {
         println!("A dot command requires a value.");
     };
@@ -262,13 +350,223 @@ fn process_dotcommand(expr: &str, config: &mut Config) -> Result<()> {
     Ok(())
 }
 
+/// The dot commands and the extra SQL functions available in the shell.
+fn help() -> String {
+    let mut text = format!(
+        r#"Dot commands:
+
+  .help               Show this message.
+  .mode <mode>        Set the output mode. One of: {}.
+  .maxrows <n>        Cap the rows printed per query, 0 for no cap.
+  .tablewidth <n>     Fix the width of tables, 0 for the terminal width.
+  .arrangement <a>    Fit table columns to the width. One of: {}.
+  .quiet on|off       Hide or show row count notices.
+  .once <path>        Write the next query result to the given file.
+
+SQL functions beyond the SQLite ones:
+
+"#,
+        OutputMode::help(),
+        Arrangement::help()
+    );
+
+    for (name, usage) in functions::FUNCTIONS {
+        text.push_str(&format!("  {:<18}  {}\n", name, usage));
+    }
+
+    text
+}
+
 fn process_dotmode(expr: &str, config: &mut Config) -> Result<()> {
     if !expr.trim().is_empty() {
         let mode = OutputMode::from_str(expr)?;
         config.output_mode = mode;
     } else {
-        println!("The command `.mode` requires a value.");
+        println!(
+            "The command `.mode` requires a value. One of: {}",
+            OutputMode::help()
+        );
     };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mode_roundtrips_and_renders() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+
+        for mode in OutputMode::all() {
+            let actual = OutputMode::from_str(&mode.to_string())?;
+            assert_eq!(actual, mode);
+
+            let mut config = Config::default();
+            config.output_mode = mode;
+            process_query(&conn, "SELECT 'a' AS x", &mut config)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn max_rows_caps_and_counts_the_rest() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let query = r#"
+            WITH RECURSIVE seq(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM seq WHERE x < 5)
+            SELECT CAST(x AS TEXT) FROM seq
+            "#;
+
+        for (limit, expected_taken, expected_remaining) in [(2, 2, 3), (0, 5, 0), (10, 5, 0)] {
+            let mut stmt = conn.prepare(query)?;
+            let mut rows = Values::new(stmt.query([])?, 1);
+            let config = Config {
+                max_rows: limit,
+                ..Default::default()
+            };
+            let taken = render(
+                OutputMode::Tabbed,
+                &mut rows,
+                &["x".into()],
+                &mut Vec::<u8>::new(),
+                &config,
+            )?;
+
+            assert_eq!(taken, expected_taken);
+            assert_eq!(rows.count(), expected_remaining);
+        }
+
+        assert_eq!(
+            overflow_notice(3),
+            Some("\u{2026} 3 more rows (increase with .maxrows)".into())
+        );
+        assert_eq!(overflow_notice(0), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn vertical_aligns_columns() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let mut stmt = conn.prepare(
+            "SELECT 'rust' AS id, 'Rust' AS name UNION ALL SELECT 'zig', NULL ORDER BY id",
+        )?;
+        let column_names: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let rows = Values::new(stmt.query([])?, column_names.len());
+        let mut out = Vec::new();
+
+        render(
+            OutputMode::Vertical,
+            rows,
+            &column_names,
+            &mut out,
+            &Config::default(),
+        )?;
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  id = rust\nname = Rust\n\n  id = zig\nname = \n"
+        );
+        assert_eq!(OutputMode::from_str("line")?, OutputMode::Vertical);
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_result_notice() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let mut stmt = conn.prepare("SELECT 1 WHERE 0")?;
+        let mut rows = Values::new(stmt.query([])?, 1);
+        let mut config = Config::default();
+        let taken = render(
+            OutputMode::Table,
+            &mut rows,
+            &["1".into()],
+            &mut Vec::<u8>::new(),
+            &config,
+        )?;
+        let remaining = rows.count();
+
+        assert_eq!(notices(taken, remaining, &config), vec!["(0 rows)"]);
+
+        config.quiet = true;
+        assert!(notices(taken, remaining, &config).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn once_captures_a_single_query() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("once.txt");
+        let conn = Connection::open_in_memory()?;
+        let mut config = Config::default();
+
+        process_dotcommand(&format!("once {}", path.display()), &mut config)?;
+        process_dotcommand("maxrows 10", &mut config)?;
+        process_query(&conn, "SELECT 'first' AS x", &mut config)?;
+        process_query(&conn, "SELECT 'second' AS x", &mut config)?;
+
+        let actual = std::fs::read_to_string(&path)?;
+
+        assert_eq!(actual, "x\nfirst\n");
+        assert!(config.once.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn uncoloured_error_has_no_escapes() {
+        let actual = format_error(
+            "near \"form\": syntax error",
+            "SELECT * form thing;",
+            9,
+            Palette::new(false),
+        );
+
+        assert!(!actual.contains('\u{1b}'));
+        assert!(actual.starts_with("SELECT * form thing;\n"));
+    }
+
+    #[test]
+    fn error_caret_after_multibyte_text() {
+        let query = "SELECT 'café' form thing;";
+        let offset = query.find("form").expect("the typo");
+        let actual = format_error("syntax error", query, offset, Palette::new(false));
+        let caret: Vec<&str> = actual.lines().collect();
+
+        assert_eq!(caret[1], format!("{}^", " ".repeat(14)));
+
+        // An offset within `é` points at it instead of panicking.
+        let within = query.find('é').expect("the accent") + 1;
+        let actual = format_error("syntax error", query, within, Palette::new(false));
+
+        assert_eq!(actual.lines().nth(1), Some("           ^"));
+    }
+
+    #[test]
+    fn init_script_sets_mode() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let mut config = Config::default();
+        let script = r#"
+            -- Session defaults.
+            .mode table
+            .mode nope
+            CREATE TEMP VIEW answer AS
+                SELECT '42' AS x;
+            .maxrows 5
+        "#;
+
+        let errors = run_script(&conn, script, &mut config);
+        let answer: String = conn.query_row("SELECT x FROM answer", [], |row| row.get(0))?;
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(config.output_mode, OutputMode::Table);
+        assert_eq!(config.max_rows, 5);
+        assert_eq!(answer, "42");
+
+        Ok(())
+    }
+}
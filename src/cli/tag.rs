@@ -0,0 +1,347 @@
+use clap::Parser;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::context::Context;
+use crate::entities::tag::{self, TagError};
+use crate::services::staging;
+use crate::shell::render::{render, Row};
+use crate::shell::{Config, OutputMode};
+use crate::store::{Repository, TagStore};
+use crate::{Report, Result};
+
+/// Manages the tags of a Some package.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    #[clap(subcommand)]
+    action: Action,
+}
+
+#[derive(Debug, Parser)]
+enum Action {
+    List(ListCmd),
+    Tree(TreeCmd),
+    Merge(MergeCmd),
+    SetSummary(SetSummaryCmd),
+    SetName(SetNameCmd),
+}
+
+/// Lists every tag, flagging the ones used as a category.
+#[derive(Debug, Parser)]
+struct ListCmd {
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// Show the number of things referencing each tag.
+    #[clap(long = "counts")]
+    counts_flag: bool,
+    /// How to render the tags: tabbed, table, jsonline or vertical.
+    #[clap(long, default_value = "tabbed")]
+    format: OutputMode,
+}
+
+/// Shows the tags as a tree, splitting their ids on `/`, with the number of things under each.
+///
+/// The count of a node is the number of distinct things with its tag or any of its descendants,
+/// so a thing with both `lang` and `lang/rust` is counted once for `lang`.
+#[derive(Debug, Parser)]
+struct TreeCmd {
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+/// Merges a tag into another one, rewriting every thing that references it.
+#[derive(Debug, Parser)]
+struct MergeCmd {
+    /// The identifier of the tag to merge and remove.
+    from: String,
+    /// The identifier of the tag to keep.
+    into: String,
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+/// Sets the summary of a tag, used as the section prose for categories.
+#[derive(Debug, Parser)]
+struct SetSummaryCmd {
+    /// The identifier of the tag to update.
+    id: String,
+    /// The new summary.
+    text: String,
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+/// Sets the human readable name of a tag.
+#[derive(Debug, Parser)]
+struct SetNameCmd {
+    /// The identifier of the tag to update.
+    id: String,
+    /// The new name.
+    name: String,
+    /// The location where to find the Some package to be used.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Report> {
+        match &self.action {
+            Action::List(cmd) => cmd.run(),
+            Action::Tree(cmd) => cmd.run(),
+            Action::Merge(cmd) => cmd.run(dry_run),
+            Action::SetSummary(cmd) => {
+                update_tag(&cmd.path, &cmd.id, dry_run, |tag| {
+                    tag::Record::new(
+                        tag.id().clone(),
+                        tag.name().cloned(),
+                        Some(cmd.text.clone()),
+                    )
+                })?;
+
+                Ok(Report::new(format!("Updated the summary of `{}`.", cmd.id)))
+            }
+            Action::SetName(cmd) => {
+                update_tag(&cmd.path, &cmd.id, dry_run, |tag| {
+                    tag::Record::new(
+                        tag.id().clone(),
+                        Some(cmd.name.clone()),
+                        tag.summary().cloned(),
+                    )
+                })?;
+
+                Ok(Report::new(format!("Updated the name of `{}`.", cmd.id)))
+            }
+        }
+    }
+}
+
+/// Replaces the tag with the result of `update` and commits the change.
+fn update_tag<F>(path: &Path, id: &str, dry_run: bool, update: F) -> Result<()>
+where
+    F: FnOnce(&tag::Record) -> tag::Record,
+{
+    let mut context = Context::new(path)?;
+    context.set_dry_run(dry_run)?;
+    let current = TagStore::get(&context.store().conn, &id.to_string())?
+        .ok_or_else(|| TagError::NotFound(id.to_string()))?;
+
+    staging::replace_tag(&mut context, update(&current))?;
+    staging::commit(&mut context)?;
+
+    Ok(())
+}
+
+impl ListCmd {
+    fn run(&self) -> Result<Report> {
+        let mut out = Vec::new();
+
+        self.write(&mut out)?;
+
+        Ok(Report::new(String::from_utf8_lossy(&out).trim_end()))
+    }
+
+    fn write(&self, out: &mut dyn Write) -> Result<usize> {
+        let mut context = Context::new(&self.path)?;
+        let tags = TagStore::list_with_counts(&context.store().conn)?;
+        let mut columns = vec!["id", "name", "summary", "category"];
+
+        if self.counts_flag {
+            columns.push("things");
+        }
+
+        let columns: Vec<String> = columns.into_iter().map(String::from).collect();
+        let rows = tags.into_iter().map(|count| {
+            let mut row: Row = vec![
+                Some(count.tag.id().clone()),
+                count.tag.name().cloned(),
+                count.tag.summary().cloned(),
+                count.is_category.then(|| "yes".to_string()),
+            ];
+
+            if self.counts_flag {
+                row.push(Some(count.things.to_string()));
+            }
+
+            Ok(row)
+        });
+        let config = Config {
+            max_rows: 0,
+            ..Default::default()
+        };
+
+        render(self.format, rows, &columns, out, &config)
+    }
+}
+
+impl TreeCmd {
+    fn run(&self) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        let tags = TagStore::list(&context.store().conn)?;
+        let tagged = TagStore::list_tagged(&context.store().conn)?;
+        let mut root = TagNode::default();
+
+        for tag in &tags {
+            root.insert(tag.id(), None);
+        }
+
+        for (id, url) in &tagged {
+            root.insert(id, Some(url.as_str()));
+        }
+
+        let mut out = Vec::new();
+        root.write(0, &mut out)?;
+
+        Ok(Report::new(String::from_utf8_lossy(&out).trim_end()))
+    }
+}
+
+/// A segment of the tag tree.
+#[derive(Debug, Default)]
+struct TagNode {
+    /// The URLs of the things with the tag ending at this node, if any.
+    things: BTreeSet<String>,
+    children: BTreeMap<String, TagNode>,
+}
+
+impl TagNode {
+    /// Adds the tag, creating the nodes for any of its ancestors missing, and records the thing
+    /// URL under it when given.
+    fn insert(&mut self, id: &str, url: Option<&str>) {
+        let node = id.split('/').fold(self, |node, segment| {
+            node.children.entry(segment.to_string()).or_default()
+        });
+
+        if let Some(url) = url {
+            node.things.insert(url.to_string());
+        }
+    }
+
+    /// The distinct things of this node and all its descendants.
+    fn total(&self) -> usize {
+        let mut urls = BTreeSet::new();
+        self.collect(&mut urls);
+
+        urls.len()
+    }
+
+    fn collect<'a>(&'a self, urls: &mut BTreeSet<&'a str>) {
+        urls.extend(self.things.iter().map(String::as_str));
+
+        for child in self.children.values() {
+            child.collect(urls);
+        }
+    }
+
+    /// Writes each child on its own line, indented two spaces per level, followed by its total.
+    fn write(&self, depth: usize, out: &mut dyn Write) -> Result<()> {
+        for (segment, child) in &self.children {
+            writeln!(out, "{}{} ({})", "  ".repeat(depth), segment, child.total())?;
+            child.write(depth + 1, out)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MergeCmd {
+    fn run(&self, dry_run: bool) -> Result<Report> {
+        let mut context = Context::new(&self.path)?;
+        context.set_dry_run(dry_run)?;
+        let count = staging::merge_tags(&mut context, &self.from, &self.into)?;
+
+        staging::commit(&mut context)?;
+
+        Ok(Report::new(format!(
+            "Merged `{}` into `{}`, updating {} things.",
+            self.from, self.into, count
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs::OpenOptions;
+
+    fn append(path: &Path, line: &str) -> Result<()> {
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn list_with_counts() -> Result<()> {
+        let dir = fixtures::package();
+        let data = dir.path().join("data");
+        append(
+            &data.join("tag.csv"),
+            "language,Language,Programming languages.",
+        )?;
+        append(&data.join("tag.csv"), "unused,Unused,")?;
+        append(
+            &data.join("thing.csv"),
+            "https://www.rust-lang.org/,Rust,,miscellaneous",
+        )?;
+        append(
+            &data.join("thing_tag.csv"),
+            "https://www.rust-lang.org/,language",
+        )?;
+
+        let cmd = ListCmd {
+            path: dir.path().to_path_buf(),
+            counts_flag: true,
+            format: OutputMode::Jsonline,
+        };
+        let mut out = Vec::new();
+
+        assert_eq!(cmd.write(&mut out)?, 3);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"category":null,"id":"language","name":"Language","summary":"Programming languages.","things":"1"}"#,
+                "\n",
+                r#"{"category":"yes","id":"miscellaneous","name":"Miscellaneous","summary":"The unclassifiable.","things":"1"}"#,
+                "\n",
+                r#"{"category":null,"id":"unused","name":"Unused","summary":null,"things":"0"}"#,
+                "\n",
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tree_aggregates_descendants() -> Result<()> {
+        let mut root = TagNode::default();
+
+        // `x` has both `a/b` and `a/b/c`, and `y` both `a` and `a/b`, yet each counts once.
+        for (id, url) in [
+            ("a", Some("y")),
+            ("a/b", Some("x")),
+            ("a/b", Some("y")),
+            ("a/b/c", Some("x")),
+            ("a/b/c", Some("z")),
+            ("a/d", None),
+            ("e", Some("x")),
+        ] {
+            root.insert(id, url);
+        }
+
+        let mut out = Vec::new();
+        root.write(0, &mut out)?;
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "a (3)\n  b (3)\n    c (2)\n  d (0)\ne (1)\n"
+        );
+
+        Ok(())
+    }
+}
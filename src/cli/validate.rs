@@ -0,0 +1,453 @@
+use clap::Parser;
+use encoding_rs::Encoding;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs::{self, read_to_string};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tabwriter::TabWriter;
+use url::Url;
+
+use crate::cli;
+use crate::entities::row::RowError;
+use crate::package::core::PackageError;
+use crate::package::{self, schema, Package};
+use crate::services::fetcher::Fetcher;
+use crate::settings;
+use crate::shell::OutputMode;
+use crate::{Result, SomeError};
+
+/// Validates the descriptor of a Some package.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be validated.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// Reject any key unknown to Some instead of ignoring it.
+    #[clap(long)]
+    strict: bool,
+    /// Also validate against the given JSON Schema, either a path or a URL.
+    #[clap(long, value_name = "PATH_OR_URL")]
+    schema: Option<String>,
+    /// Read the resource CSVs with this encoding, e.g. `windows-1252`, instead of the one the
+    /// descriptor declares.
+    #[clap(long, value_name = "label")]
+    encoding: Option<String>,
+    /// How to report the findings: tabbed, table, jsonline or vertical.
+    #[clap(long, default_value = "table")]
+    format: OutputMode,
+}
+
+impl Cmd {
+    /// Validates the package in `path` leniently, without any extra JSON Schema.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            strict: false,
+            schema: None,
+            encoding: None,
+            format: OutputMode::Table,
+        }
+    }
+
+    pub fn run(&self) -> Result<Validation> {
+        let full_path = self.path.canonicalize()?;
+        let encoding = cli::encoding(self.encoding.as_deref())?;
+        let raw = read_to_string(full_path.join(package::DESCRIPTOR_PATH))
+            .map_err(|_| SomeError::MissingPackageDescriptor(full_path.display().to_string()))?;
+
+        let mut validation = Validation::new(self.format);
+
+        if let Some(package) = self.check_descriptor(&raw, &mut validation) {
+            check_resources(&package, &full_path, encoding, &mut validation);
+            validation.package = Some(package.name().to_string());
+        }
+
+        if let Some(location) = &self.schema {
+            let descriptor: Value = serde_json::from_str(&raw)?;
+            let schema: Value = serde_json::from_str(&read_schema(location)?)?;
+
+            match schema::validate(&descriptor, &schema) {
+                Err(PackageError::SchemaViolations(violations)) => {
+                    for violation in violations {
+                        validation.push(Finding {
+                            severity: Severity::Error,
+                            resource: None,
+                            field: Some(violation.pointer),
+                            message: violation.message,
+                            row: None,
+                        });
+                    }
+                }
+                result => result?,
+            }
+        }
+
+        Ok(validation)
+    }
+
+    /// Parses the descriptor, reporting unknown keys as errors in strict mode and as warnings
+    /// otherwise.
+    fn check_descriptor(&self, raw: &str, validation: &mut Validation) -> Option<Package> {
+        let result = match Package::from_str_strict(raw) {
+            Err(PackageError::UnknownFields(keys)) => {
+                let severity = if self.strict {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                };
+
+                for key in keys {
+                    validation.push(Finding {
+                        severity,
+                        resource: None,
+                        field: Some(key),
+                        message: "Unknown field.".into(),
+                        row: None,
+                    });
+                }
+
+                Package::from_reader(raw.as_bytes())
+            }
+            result => result,
+        };
+
+        match result {
+            Ok(package) => Some(package),
+            Err(err) => {
+                validation.push(Finding::error(err.to_string()));
+
+                None
+            }
+        }
+    }
+}
+
+/// Reads every resource file with the given encoding reporting the rows that can't be parsed.
+///
+/// Inline resources have no file and are skipped.
+fn check_resources(
+    package: &Package,
+    root: &Path,
+    encoding: &'static Encoding,
+    validation: &mut Validation,
+) {
+    for resource in package.resources().iter().filter(|r| r.has_path()) {
+        let name = resource.id().to_string();
+        let location = resource.path().display().to_string();
+        let path = resource.absolute_path(root);
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                validation.push(Finding {
+                    resource: Some(name),
+                    ..Finding::error(SomeError::MissingResource(location).to_string())
+                });
+
+                continue;
+            }
+        };
+        let (text, _, malformed) = encoding.decode(&bytes);
+
+        if malformed {
+            validation.push(Finding {
+                resource: Some(name.clone()),
+                ..Finding::error(format!("Not valid {} text.", encoding.name()))
+            });
+        }
+
+        let mut rdr = csv::Reader::from_reader(text.as_bytes());
+
+        for result in rdr.records() {
+            if let Err(err) = result {
+                let finding = match RowError::from_csv(&location, &err) {
+                    Some(row_error) => Finding {
+                        resource: Some(name.clone()),
+                        row: Some(row_error.row),
+                        ..Finding::error(row_error.reason)
+                    },
+                    None => Finding {
+                        resource: Some(name.clone()),
+                        ..Finding::error(err.to_string())
+                    },
+                };
+
+                validation.push(finding);
+            }
+        }
+    }
+}
+
+/// Reads a JSON Schema from a remote URL or from a local path.
+fn read_schema(location: &str) -> Result<String> {
+    match Url::parse(location) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            let mut fetcher = Fetcher::new(cli::fetch_config(settings::current()));
+            let page = fetcher.get(&url)?;
+
+            if page.status != 200 {
+                return Err(SomeError::BadUrl(location.to_string()));
+            }
+
+            Ok(page.body)
+        }
+        _ => Ok(read_to_string(location)?),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A single validation finding.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub resource: Option<String>,
+    pub field: Option<String>,
+    pub message: String,
+    /// The 1-based record index when the finding is about a resource row.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<u64>,
+}
+
+impl Finding {
+    fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            resource: None,
+            field: None,
+            message,
+            row: None,
+        }
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.severity.to_string(),
+            self.resource.clone().unwrap_or_default(),
+            self.field.clone().unwrap_or_default(),
+            self.row.map(|row| row.to_string()).unwrap_or_default(),
+            self.message.clone(),
+        ]
+    }
+}
+
+/// The columns of a finding when rendered for humans.
+const COLUMNS: [&str; 5] = ["severity", "resource", "field", "row", "message"];
+
+/// The outcome of validating a package.
+#[derive(Debug)]
+pub struct Validation {
+    format: OutputMode,
+    /// The package name when the descriptor could be read.
+    package: Option<String>,
+    findings: Vec<Finding>,
+}
+
+impl Validation {
+    fn new(format: OutputMode) -> Self {
+        Self {
+            format,
+            package: None,
+            findings: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, finding: Finding) {
+        self.findings.push(finding);
+    }
+
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// Fails only when there is at least one error, warnings alone are acceptable.
+    pub fn exit_code(&self) -> i32 {
+        if self
+            .findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error)
+        {
+            1
+        } else {
+            0
+        }
+    }
+
+    pub fn render(&self, out: &mut dyn Write) -> Result<()> {
+        match self.format {
+            OutputMode::Jsonline => {
+                for finding in &self.findings {
+                    writeln!(out, "{}", serde_json::to_string(finding)?)?;
+                }
+            }
+            _ if self.findings.is_empty() => {
+                if let Some(name) = &self.package {
+                    writeln!(out, "Package `{}` is valid.", name)?;
+                }
+            }
+            OutputMode::Table => self.render_table(out)?,
+            OutputMode::Tabbed => self.render_tabbed(out)?,
+            OutputMode::Vertical => self.render_vertical(out)?,
+        }
+
+        Ok(())
+    }
+
+    fn render_table(&self, out: &mut dyn Write) -> Result<()> {
+        use comfy_table::presets::UTF8_FULL;
+        use comfy_table::*;
+
+        let mut table = Table::new();
+
+        table
+            .load_preset(UTF8_FULL)
+            .set_header(COLUMNS.to_vec())
+            .set_content_arrangement(ContentArrangement::DynamicFullWidth);
+
+        for finding in &self.findings {
+            table.add_row(finding.cells());
+        }
+
+        writeln!(out, "{table}")?;
+
+        Ok(())
+    }
+
+    fn render_tabbed(&self, out: &mut dyn Write) -> Result<()> {
+        let mut tw = TabWriter::new(out).padding(2);
+        writeln!(tw, "{}", COLUMNS.join("\t"))?;
+
+        for finding in &self.findings {
+            writeln!(tw, "{}", finding.cells().join("\t"))?;
+        }
+
+        tw.flush()?;
+
+        Ok(())
+    }
+
+    fn render_vertical(&self, out: &mut dyn Write) -> Result<()> {
+        for (idx, finding) in self.findings.iter().enumerate() {
+            if idx > 0 {
+                writeln!(out)?;
+            }
+
+            for (name, value) in COLUMNS.iter().zip(finding.cells()) {
+                writeln!(out, "{:>8} = {}", name, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs::{self, OpenOptions};
+
+    fn cmd(path: &Path, strict: bool) -> Cmd {
+        Cmd {
+            path: path.to_path_buf(),
+            strict,
+            schema: None,
+            encoding: None,
+            format: OutputMode::Jsonline,
+        }
+    }
+
+    /// Adds an unknown key to the descriptor.
+    fn add_unknown_key(path: &Path) -> Result<()> {
+        let location = path.join(package::DESCRIPTOR_PATH);
+        let mut descriptor: Value = serde_json::from_str(&read_to_string(&location)?)?;
+        descriptor["homepag"] = Value::String("https://example.org/".into());
+        fs::write(&location, serde_json::to_string_pretty(&descriptor)?)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn error_and_warning() -> Result<()> {
+        let dir = fixtures::package();
+        add_unknown_key(dir.path())?;
+
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(thing, "https://example.org/,Example")?;
+
+        let validation = cmd(dir.path(), false).run()?;
+        let mut out = Vec::new();
+        validation.render(&mut out)?;
+
+        let lines: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({
+                    "severity": "warning",
+                    "resource": null,
+                    "field": "homepag",
+                    "message": "Unknown field.",
+                }),
+                serde_json::json!({
+                    "severity": "error",
+                    "resource": "thing",
+                    "field": null,
+                    "message": "wrong number of fields (expected 4, found 2)",
+                    "row": 1,
+                }),
+            ]
+        );
+        assert_eq!(validation.exit_code(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn warnings_alone_succeed() -> Result<()> {
+        let dir = fixtures::package();
+        add_unknown_key(dir.path())?;
+
+        assert_eq!(cmd(dir.path(), false).run()?.exit_code(), 0);
+        assert_eq!(cmd(dir.path(), true).run()?.exit_code(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_resources_are_skipped() -> Result<()> {
+        let dir = fixtures::package();
+        let mut package = Package::from_path(dir.path())?;
+        package.resources[0].inline(Vec::new());
+
+        let mut validation = Validation::new(OutputMode::Jsonline);
+        check_resources(&package, dir.path(), encoding_rs::UTF_8, &mut validation);
+
+        assert!(validation.findings.is_empty());
+
+        Ok(())
+    }
+}
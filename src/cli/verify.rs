@@ -0,0 +1,242 @@
+use clap::Parser;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::Prompter;
+use crate::info;
+use crate::package::resource::Fingerprint;
+use crate::package::Package;
+use crate::{Report, Result};
+
+/// Verifies the resource files against the size and hash recorded in the package descriptor.
+///
+/// Any difference means a resource file was edited without going through a commit and makes the
+/// command fail unless fixed. Resources without a recorded hash are skipped.
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The location where to find the Some package to be verified.
+    #[clap(default_value = ".")]
+    path: PathBuf,
+    /// Record the current size and hash of the drifted resources in the descriptor.
+    #[clap(long = "fix")]
+    fix_flag: bool,
+    /// Fix without any user confirmation.
+    #[clap(long = "yes")]
+    yes_flag: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, dry_run: bool) -> Result<Verification> {
+        let root = self.path.canonicalize()?;
+        let mut package = Package::from_path(&root)?;
+        let drifts = check(&package, &root)?;
+        let skipped = package
+            .resources()
+            .iter()
+            .filter(|resource| resource.fingerprint().is_none())
+            .count();
+
+        if drifts.is_empty() {
+            let message = if skipped == package.resources().len() {
+                "No resource has a recorded hash to verify.".to_string()
+            } else {
+                "Every resource matches its recorded hash.".to_string()
+            };
+
+            return Ok(Verification::passed(message));
+        }
+
+        let lines: Vec<String> = drifts.iter().map(|drift| drift.to_string()).collect();
+
+        if !self.fix_flag {
+            return Ok(Verification::drifted(lines.join("\n")));
+        }
+
+        if dry_run {
+            for drift in &drifts {
+                info!("Would record the current hash of:", drift.resource);
+            }
+
+            return Ok(Verification::drifted("Dry run. Nothing changed."));
+        }
+
+        if !self.yes_flag {
+            let mut prompter = Prompter::new()?;
+            let answer = prompter.ask_once(&format!(
+                "{}\nrecord the current hashes? (y/N)",
+                lines.join("\n")
+            ))?;
+            prompter.flush()?;
+
+            if answer.as_deref() != Some("y") {
+                return Ok(Verification::drifted("Nothing changed."));
+            }
+        }
+
+        fix(&mut package, &drifts);
+        package.save(&root)?;
+
+        Ok(Verification::passed(format!(
+            "Recorded the current hash for {} resources.",
+            drifts.len()
+        )))
+    }
+}
+
+/// The outcome of a verification.
+#[derive(Debug)]
+pub struct Verification {
+    report: Report,
+    /// Whether any resource is left drifted.
+    drifted: bool,
+}
+
+impl Verification {
+    fn passed<S: Into<String>>(message: S) -> Self {
+        Self {
+            report: Report::new(message),
+            drifted: false,
+        }
+    }
+
+    fn drifted<S: Into<String>>(message: S) -> Self {
+        Self {
+            report: Report::new(message),
+            drifted: true,
+        }
+    }
+
+    /// Fails while any resource is left drifted.
+    pub fn exit_code(&self) -> i32 {
+        if self.drifted {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.report)
+    }
+}
+
+/// A resource file that no longer matches its recorded size and hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub resource: String,
+    pub expected: Fingerprint,
+    /// The current fingerprint, absent when the file is missing.
+    pub actual: Option<Fingerprint>,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "{}: expected {} bytes with {}, found {} bytes with {}",
+                self.resource, self.expected.bytes, self.expected.hash, actual.bytes, actual.hash
+            ),
+            None => write!(f, "{}: the file is missing", self.resource),
+        }
+    }
+}
+
+/// Recomputes the fingerprint of every resource with a recorded one, returning the differences.
+fn check(package: &Package, root: &Path) -> Result<Vec<Drift>> {
+    let mut drifts = Vec::new();
+
+    for resource in package.resources() {
+        let expected = match resource.fingerprint() {
+            Some(fingerprint) => fingerprint,
+            None => continue,
+        };
+        let path = resource.absolute_path(root);
+        let actual = if path.is_file() {
+            Some(Fingerprint::from_path(&path)?)
+        } else {
+            None
+        };
+
+        if actual.as_ref() != Some(&expected) {
+            drifts.push(Drift {
+                resource: resource.id().to_string(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(drifts)
+}
+
+/// Records the current fingerprint of the drifted resources that still exist.
+fn fix(package: &mut Package, drifts: &[Drift]) {
+    for drift in drifts {
+        if let Some(actual) = &drift.actual {
+            if let Some(resource) = package
+                .resources
+                .iter_mut()
+                .find(|resource| resource.id().to_string() == drift.resource)
+            {
+                resource.set_fingerprint(actual.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn edited_csv_drifts_until_fixed() -> Result<()> {
+        let dir = fixtures::package();
+        let root = dir.path();
+
+        assert!(check(&Package::from_path(root)?, root)?.is_empty());
+
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(root.join("data/thing.csv"))?;
+        writeln!(thing, "https://www.rust-lang.org/,Rust,,miscellaneous")?;
+
+        let mut package = Package::from_path(root)?;
+        let drifts = check(&package, root)?;
+        let verification = Cmd::parse_from(["verify", &root.display().to_string()]).run(false)?;
+
+        assert_eq!(verification.exit_code(), 1);
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].resource, "thing");
+
+        fix(&mut package, &drifts);
+        package.save(root)?;
+
+        assert!(check(&Package::from_path(root)?, root)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unrecorded_hashes_are_skipped() -> Result<()> {
+        let dir = fixtures::package();
+        let root = dir.path();
+        let mut package = Package::from_path(root)?;
+
+        for resource in package.resources.iter_mut() {
+            resource.bytes = None;
+            resource.hash = None;
+        }
+
+        std::fs::remove_file(root.join("data/tag.csv"))?;
+
+        assert!(check(&package, root)?.is_empty());
+
+        Ok(())
+    }
+}
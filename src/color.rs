@@ -0,0 +1,129 @@
+//! Colour policy for terminal output.
+//!
+//! All coloured output should go through a [`Palette`] so the `--color` flag and the `NO_COLOR`,
+//! `CLICOLOR` and `CLICOLOR_FORCE` conventions are honoured uniformly.
+
+use ansi_term::Style;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static POLICY: OnceLock<bool> = OnceLock::new();
+
+/// When to use colours.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorChoice {
+    /// Use colours when writing to a terminal unless the environment says otherwise.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolves the choice against the environment.
+    pub fn resolve(&self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                if env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty()) {
+                    return false;
+                }
+
+                if env::var("CLICOLOR_FORCE").map_or(false, |value| value != "0") {
+                    return true;
+                }
+
+                if env::var("CLICOLOR").map_or(false, |value| value == "0") {
+                    return false;
+                }
+
+                io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            value => Err(anyhow::anyhow!(
+                "{} is not a valid colour choice. Expected one of: auto, always, never",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Always => write!(f, "always"),
+            Self::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Sets the colour policy for the whole process. Only the first call has any effect.
+pub fn set(choice: ColorChoice) {
+    let _ = POLICY.set(choice.resolve());
+}
+
+/// Whether colours are enabled. Defaults to [`ColorChoice::Auto`] when no policy was set.
+pub fn enabled() -> bool {
+    *POLICY.get_or_init(|| ColorChoice::Auto.resolve())
+}
+
+/// Paints text only when colours are enabled.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// The palette following the process colour policy.
+    pub fn current() -> Self {
+        Self::new(enabled())
+    }
+
+    pub fn paint<S: Into<Style>>(&self, style: S, text: &str) -> String {
+        if self.enabled {
+            style.into().paint(text).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ansi_term::Colour::Red;
+
+    #[test]
+    fn disabled_palette_has_no_escapes() {
+        let actual = Palette::new(false).paint(Red.bold(), "boom");
+
+        assert_eq!(actual, "boom");
+    }
+
+    #[test]
+    fn enabled_palette_has_escapes() {
+        let actual = Palette::new(true).paint(Red, "boom");
+
+        assert!(actual.contains('\u{1b}'));
+    }
+}
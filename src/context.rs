@@ -2,12 +2,16 @@ use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use encoding_rs::{Encoding, UTF_8};
 use rusqlite::Transaction;
 
-use crate::package::core::Name;
-use crate::package::resource::Resource;
+use crate::entities::tag_set::TagSet;
+use crate::entities::thing_set::ThingSet;
+use crate::package::resource::{Fingerprint, Resource};
 use crate::package::Package;
-use crate::store::{Store, Strategy, DEFAULT_PATH};
+use crate::services::staging::Uniqueness;
+use crate::settings::{self, Settings};
+use crate::store::{Repository, Store, StoreError, Strategy, TagStore, ThingStore, DEFAULT_PATH};
 use crate::Result;
 
 /// The holder of all contextual information.
@@ -19,22 +23,68 @@ pub struct Context {
     package: Package,
     /// The cache store.
     store: Store,
+    /// The settings taken from the environment.
+    settings: Settings,
+    /// Whether writes are reported instead of made.
+    dry_run: bool,
 }
 
 impl Context {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_encoding(path, UTF_8)
+    }
+
+    /// Opens the package reading its resource CSV files with the given encoding, regardless of
+    /// the one declared in the descriptor.
+    pub fn with_encoding<P: AsRef<Path>>(path: P, encoding: &'static Encoding) -> Result<Self> {
         let path = path.as_ref().canonicalize()?;
-        let package = Package::from_path(&path)?;
         let strategy = Strategy::from_str(DEFAULT_PATH)?;
-        let store = Store::open(path.to_path_buf(), &strategy)?;
+        let store = Store::open_with_encoding(path.to_path_buf(), &strategy, encoding)?;
+
+        Self::with_store(path, store)
+    }
+
+    /// Builds a context over an already open store, skipping the cost of attaching and loading
+    /// the source and staging databases again.
+    ///
+    /// The store must have been opened for the same package location. Use
+    /// [`Context::into_store`] to reclaim it for the next context.
+    pub fn with_store<P: AsRef<Path>>(path: P, store: Store) -> Result<Self> {
+        let path = path.as_ref().canonicalize()?;
+
+        if store.path.canonicalize()? != path {
+            return Err(StoreError::PathMismatch(path.display().to_string()).into());
+        }
+
+        let package = Package::from_path(&path)?;
+
+        store.write_run_metadata(&package.id)?;
 
         Ok(Self {
             package,
             path,
             store,
+            settings: settings::current().clone(),
+            dry_run: false,
         })
     }
 
+    /// Reads the package descriptor and its resources again, picking up any change made to them
+    /// since the context was opened.
+    pub fn reload(&mut self) -> Result<()> {
+        let fresh = Self::with_encoding(&self.path, self.store.encoding)?;
+
+        self.package = fresh.package;
+        self.store = fresh.store;
+
+        Ok(())
+    }
+
+    /// Releases the store so it can be reused by another context.
+    pub fn into_store(self) -> Store {
+        self.store
+    }
+
     pub fn store(&mut self) -> &mut Store {
         &mut self.store
     }
@@ -43,38 +93,236 @@ impl Context {
         Ok(self.store.transaction()?)
     }
 
+    /// Runs `f` in a store transaction, see [`Store::in_transaction`].
+    ///
+    /// A dry run commits as well, into the in-memory staging database, so each step sees the
+    /// changes staged by the previous ones. The whole run is dropped with the store.
+    pub fn in_transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction) -> Result<T>,
+    {
+        self.store.in_transaction(f)
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Sets whether writes are reported instead of made.
+    ///
+    /// Fails for a store with its staging database on disk as a dry run must leave it untouched.
+    pub fn set_dry_run(&mut self, dry_run: bool) -> Result<()> {
+        if dry_run && self.store.strategy != Strategy::Memory {
+            return Err(StoreError::DryRunOnDisk(self.path.display().to_string()).into());
+        }
+
+        self.dry_run = dry_run;
+
+        Ok(())
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn uniqueness(&self) -> Uniqueness {
+        self.settings.uniqueness
+    }
+
+    pub fn set_uniqueness(&mut self, uniqueness: Uniqueness) {
+        self.settings.uniqueness = uniqueness;
+    }
+
+    /// Every thing in the collection, including the staged ones.
+    pub fn things(&mut self) -> Result<ThingSet> {
+        Ok(ThingSet::new(ThingStore::to_vec(&self.store.conn)?))
+    }
+
+    /// Every tag in the collection, including the staged ones.
+    pub fn tags(&mut self) -> Result<TagSet> {
+        Ok(TagSet::new(TagStore::list(&self.store.conn)?))
+    }
+
     pub fn package(&self) -> &Package {
         &self.package
     }
 
+    pub fn package_mut(&mut self) -> &mut Package {
+        &mut self.package
+    }
+
+    /// Writes the package descriptor back to disk, stamping it as modified now.
+    pub fn save_package(&mut self) -> Result<()> {
+        self.package.save(&self.path)?;
+
+        Ok(())
+    }
+
+    /// Updates the recorded size and hash of every resource file that changed, saving the
+    /// package when any did.
+    ///
+    /// Resources without a recorded fingerprint are left alone.
+    pub fn refresh_fingerprints(&mut self) -> Result<()> {
+        let mut changed = false;
+
+        for resource in self.package.resources.iter_mut() {
+            if let Some(recorded) = resource.fingerprint() {
+                let actual = Fingerprint::from_path(&resource.absolute_path(&self.path))?;
+
+                if actual != recorded {
+                    resource.set_fingerprint(actual);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.save_package()?;
+        }
+
+        Ok(())
+    }
+
     pub fn resources(&self) -> Vec<Resource> {
         self.package
             .resources()
             .iter()
             .map(|resource| {
                 let mut new = resource.clone();
-                new.path = self.path.join(resource.path());
+                new.path = resource.absolute_path(&self.path);
                 new
             })
             .collect()
     }
 
-    pub fn open_resource(&self, name: &str) -> Result<File> {
-        let name = Name::new(name);
-        let resource: Resource = self
-            .package
+    /// The resource descriptor with the given name.
+    pub fn resource(&self, name: &str) -> &Resource {
+        self.package
             .resources()
             .iter()
-            .find(|r| r.id() == &name)
+            .find(|r| r.id() == name)
             .expect("resource to exist")
-            .clone();
+    }
+
+    /// The location of the package.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The location of the given resource file.
+    pub fn resource_path(&self, name: &str) -> PathBuf {
+        self.resource(name).absolute_path(&self.path)
+    }
 
+    pub fn open_resource(&self, name: &str) -> Result<File> {
         let file = OpenOptions::new()
             .read(true)
             .append(true)
             .truncate(false)
-            .open(self.path.join(resource.path()))?;
+            .open(self.resource_path(name))?;
 
         Ok(file)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::store::STAGING_PATH;
+    use crate::SomeError;
+    use std::io::Write;
+
+    #[test]
+    fn save_package_sets_modified() -> Result<()> {
+        let dir = fixtures::package();
+        let mut context = Context::new(dir.path())?;
+
+        assert!(context.package().modified().is_none());
+
+        context.package_mut().title = "Some Renamed".into();
+        context.save_package()?;
+
+        let saved = Package::from_path(dir.path())?;
+        let roundtrip: Package = serde_json::from_str(&serde_json::to_string(&saved)?)?;
+
+        assert_eq!(saved.title(), "Some Renamed");
+        assert_eq!(saved.modified(), context.package().modified());
+        assert_eq!(roundtrip.modified(), saved.modified());
+        assert!(saved.modified().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn contexts_share_a_store() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        let count = |context: &mut Context| -> Result<Vec<i64>> {
+            context
+                .store()
+                .query("SELECT count(*) FROM tag", [], |row| row.get(0))
+        };
+
+        let mut first = Context::with_store(dir.path(), store)?;
+        let first_count = count(&mut first)?;
+
+        let mut second = Context::with_store(dir.path(), first.into_store())?;
+        let second_count = count(&mut second)?;
+
+        assert_eq!(first_count, second_count);
+
+        Ok(())
+    }
+
+    #[test]
+    fn store_for_another_package() -> Result<()> {
+        let dir = fixtures::package();
+        let other = fixtures::package();
+        let store = Store::open(other.path().to_path_buf(), &Strategy::Memory)?;
+
+        assert!(Context::with_store(dir.path(), store).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run_on_disk_staging() -> Result<()> {
+        let dir = fixtures::package();
+        let strategy = Strategy::Disk(dir.path().join(STAGING_PATH));
+        let store = Store::open(dir.path().to_path_buf(), &strategy)?;
+        let mut context = Context::with_store(dir.path(), store)?;
+
+        assert!(matches!(
+            context.set_dry_run(true),
+            Err(SomeError::StoreError(StoreError::DryRunOnDisk(_)))
+        ));
+        assert!(!context.dry_run());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sets_match_the_store() -> Result<()> {
+        let dir = fixtures::package();
+        let mut thing = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/thing.csv"))?;
+        writeln!(
+            thing,
+            "https://www.rust-lang.org/,Rust,A language.,miscellaneous"
+        )?;
+
+        let mut context = Context::new(dir.path())?;
+        let things = context.things()?;
+        let tags = context.tags()?;
+        let conn = &context.store().conn;
+
+        assert_eq!(things.as_slice(), ThingStore::to_vec(conn)?.as_slice());
+        assert_eq!(tags.as_slice(), TagStore::list(conn)?.as_slice());
+        assert_eq!(things.len(), 1);
+
+        Ok(())
+    }
+}
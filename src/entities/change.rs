@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use crate::entities::{tag, thing};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Event {
     pub timestamp: usize,
     pub change: Change,
@@ -16,7 +17,7 @@ pub struct EventId {
     pub id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "operation")]
 pub enum Change {
     Insert(Data),
@@ -24,7 +25,7 @@ pub enum Change {
     Delete(DataRef),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "kind")]
 pub enum Data {
     Thing {
@@ -42,13 +43,47 @@ pub enum Data {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "kind")]
 pub enum DataRef {
     Thing { id: thing::Id },
     Tag { id: tag::Id },
 }
 
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.timestamp, self.change)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insert(data) => write!(f, "insert {}", data),
+            Self::Replace(data) => write!(f, "replace {}", data),
+            Self::Delete(data_ref) => write!(f, "delete {}", data_ref),
+        }
+    }
+}
+
+impl fmt::Display for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Thing { url, .. } => write!(f, "thing {}", url),
+            Self::Tag { id, .. } => write!(f, "tag {}", id),
+        }
+    }
+}
+
+impl fmt::Display for DataRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Thing { id } => write!(f, "thing {}", id),
+            Self::Tag { id } => write!(f, "tag {}", id),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -68,4 +103,42 @@ mod test {
 
         assert_eq!(&ev, &actual);
     }
+
+    #[test]
+    fn display() {
+        let thing = Data::Thing {
+            url: "https://www.rust-lang.org/".to_string(),
+            name: "Rust".to_string(),
+            summary: None,
+            category: "language".to_string(),
+            tags: Vec::new(),
+        };
+        let tag = Data::Tag {
+            id: "rust".to_string(),
+            name: None,
+            summary: None,
+        };
+
+        assert_eq!(
+            Change::Insert(thing).to_string(),
+            "insert thing https://www.rust-lang.org/"
+        );
+        assert_eq!(Change::Replace(tag).to_string(), "replace tag rust");
+        assert_eq!(
+            Change::Delete(DataRef::Thing {
+                id: "https://www.rust-lang.org/".to_string()
+            })
+            .to_string(),
+            "delete thing https://www.rust-lang.org/"
+        );
+
+        let ev = Event {
+            timestamp: 1609236672,
+            change: Change::Delete(DataRef::Tag {
+                id: "rust".to_string(),
+            }),
+        };
+
+        assert_eq!(ev.to_string(), "1609236672 delete tag rust");
+    }
 }
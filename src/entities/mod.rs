@@ -1,5 +1,8 @@
 pub mod change;
+pub mod row;
 pub mod tag;
 pub mod tag_set;
 pub mod thing;
+pub mod thing_set;
 pub mod thingtag;
+pub mod thingtag_set;
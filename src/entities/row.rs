@@ -0,0 +1,83 @@
+use thiserror::Error;
+
+use crate::package::resource::Schema;
+
+/// A CSV parse failure located by resource and 1-based record index.
+#[derive(Error, Debug)]
+#[error("{resource}: row {row}: {reason}")]
+pub struct RowError {
+    pub resource: String,
+    pub row: u64,
+    pub reason: String,
+}
+
+impl RowError {
+    /// Locates the given CSV error. Returns `None` when the error carries no position, such as an
+    /// IO failure.
+    pub fn from_csv(resource: &str, err: &csv::Error) -> Option<Self> {
+        let (pos, reason) = match err.kind() {
+            csv::ErrorKind::UnequalLengths {
+                pos: Some(pos),
+                expected_len,
+                len,
+            } => (
+                pos,
+                format!(
+                    "wrong number of fields (expected {}, found {})",
+                    expected_len, len
+                ),
+            ),
+            csv::ErrorKind::Utf8 {
+                pos: Some(pos),
+                err,
+            } => (pos, err.to_string()),
+            csv::ErrorKind::Deserialize {
+                pos: Some(pos),
+                err,
+            } => (pos, err.kind().to_string()),
+            _ => return None,
+        };
+
+        Some(Self {
+            resource: resource.to_string(),
+            row: pos.record(),
+            reason,
+        })
+    }
+}
+
+/// Checks that the CSV `headers` have a column for every field the `schema` requires.
+///
+/// Columns are matched by name so their order doesn't matter. A missing column is reported as a
+/// failure of row 0, the header.
+pub fn check_header(
+    headers: &csv::StringRecord,
+    schema: &Schema,
+    resource: &str,
+) -> Result<(), RowError> {
+    let missing = schema
+        .fields
+        .iter()
+        .filter(|field| field.is_required())
+        .find(|field| !headers.iter().any(|header| field.name == *header));
+
+    match missing {
+        Some(field) => Err(RowError {
+            resource: resource.to_string(),
+            row: 0,
+            reason: format!("missing required column `{}`", field.name),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Converts a CSV error into `E`, preferring a located [`RowError`] when possible.
+pub fn locate<E>(resource: &str, err: csv::Error) -> E
+where
+    E: From<RowError> + From<csv::Error>,
+{
+    match RowError::from_csv(resource, &err) {
+        Some(row_error) => row_error.into(),
+        None => err.into(),
+    }
+}
@@ -3,9 +3,14 @@ use std::fmt;
 use std::io;
 use thiserror::Error;
 
+use crate::entities::row::RowError;
+
 pub type Id = String;
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// A tag record.
+///
+/// Records are ordered by `id` first, which is unique within a collection.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct Record {
     id: Id,
     name: Option<String>,
@@ -28,6 +33,14 @@ impl Record {
     pub fn summary(&self) -> Option<&String> {
         self.summary.as_ref()
     }
+
+    /// Whether both records carry the same meaningful content, the name.
+    ///
+    /// Unlike the derived `Eq`, the `id` identity and the `summary` prose are left out so a
+    /// caller pairing records by `id` can tell a renamed tag from a touched-up one.
+    pub fn content_eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
 }
 
 impl fmt::Display for Record {
@@ -42,8 +55,83 @@ pub enum TagError {
     Unknown,
     #[error("A tag '{0}' already exists.")]
     Duplicate(String),
+    #[error("The tag '{0}' does not exist.")]
+    NotFound(String),
+    #[error("The tag '{id}' does not exist. Did you mean '{suggestion}'?")]
+    Misspelled { id: String, suggestion: String },
+    #[error(transparent)]
+    Row(#[from] RowError),
     #[error(transparent)]
     Csv(#[from] csv::Error),
     #[error(transparent)]
     Io(#[from] io::Error),
 }
+
+/// The candidate closest to `id` by edit distance, if any is close enough to be a typo.
+pub fn closest<'a, I>(id: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let threshold = (id.chars().count() / 3).max(2);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(id, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let value = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+
+            current.push(value);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: Option<&str>, summary: Option<&str>) -> Record {
+        Record::new("rust".into(), name.map(Into::into), summary.map(Into::into))
+    }
+
+    #[test]
+    fn content_eq() {
+        let original = record(Some("Rust"), None);
+
+        assert!(original.content_eq(&record(Some("Rust"), None)));
+        assert!(original.content_eq(&record(Some("Rust"), Some("Things about Rust."))));
+        assert!(!original.content_eq(&record(Some("Rust Lang"), None)));
+        assert!(!original.content_eq(&record(None, None)));
+    }
+
+    #[test]
+    fn closest_candidate() {
+        let candidates = ["miscellaneous", "language", "database"];
+
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(
+            closest("miscelaneous", candidates.iter().copied()),
+            Some("miscellaneous")
+        );
+        assert_eq!(closest("graphics", candidates.iter().copied()), None);
+    }
+}
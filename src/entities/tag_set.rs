@@ -2,7 +2,9 @@ use std::fmt;
 use std::io::prelude::*;
 use std::iter::FromIterator;
 
+use crate::entities::row::{check_header, locate};
 use crate::entities::tag::{self, TagError};
+use crate::package::resource::Schema;
 
 #[derive(Debug, Clone)]
 pub struct TagSet(Vec<tag::Record>);
@@ -24,21 +26,57 @@ impl TagSet {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn first(&self) -> Option<&tag::Record> {
         self.0.first()
     }
 
+    /// Returns a new set with the records matching the predicate.
+    pub fn filter<F>(&self, predicate: F) -> Self
+    where
+        F: Fn(&tag::Record) -> bool,
+    {
+        self.0
+            .iter()
+            .filter(|record| predicate(*record))
+            .cloned()
+            .collect()
+    }
+
+    /// Keeps only the records matching the predicate.
+    pub fn retain<F>(&mut self, predicate: F)
+    where
+        F: Fn(&tag::Record) -> bool,
+    {
+        self.0.retain(|record| predicate(record));
+    }
+
     /// Loads a TagSet from a Reader. Must be a valid CSV.
-    pub fn from_reader<R: Read>(rdr: &mut R) -> Result<Self, TagError> {
+    ///
+    /// The `resource` name is used to locate parse errors, e.g. `data/tag.csv: row 3: ...`. The
+    /// resulting set is sorted by tag id regardless of the order found in the source. Columns are
+    /// mapped by header name, failing when one required by the `schema` is missing.
+    pub fn from_reader<R: Read>(
+        rdr: &mut R,
+        schema: &Schema,
+        resource: &str,
+    ) -> Result<Self, TagError> {
         let mut rdr = csv::Reader::from_reader(rdr);
         let mut set = Vec::new();
 
+        check_header(rdr.headers()?, schema, resource)?;
+
         for result in rdr.deserialize() {
-            let record: tag::Record = result?;
+            let record: tag::Record = result.map_err(|err| locate(resource, err))?;
 
             set.push(record);
         }
 
+        set.sort();
+
         Ok(Self(set))
     }
 }
@@ -52,6 +90,15 @@ impl IntoIterator for TagSet {
     }
 }
 
+impl<'a> IntoIterator for &'a TagSet {
+    type Item = &'a tag::Record;
+    type IntoIter = std::slice::Iter<'a, tag::Record>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
 impl FromIterator<tag::Record> for TagSet {
     fn from_iter<I: IntoIterator<Item = tag::Record>>(iter: I) -> Self {
         let mut v = Vec::new();
@@ -75,3 +122,66 @@ impl fmt::Display for TagSet {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lenses::package::tag_resource;
+
+    #[test]
+    fn from_reader_sorts_by_id() -> Result<(), TagError> {
+        let mut raw = "id,name,summary\nzig,Zig,\nc,C,\nrust,Rust,\n".as_bytes();
+        let set = TagSet::from_reader(&mut raw, &tag_resource().schema, "data/tag.csv")?;
+        let ids: Vec<&String> = set.as_slice().iter().map(|tag| tag.id()).collect();
+
+        assert_eq!(ids, vec!["c", "rust", "zig"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn iterates_by_reference() -> Result<(), TagError> {
+        let mut raw = "id,name,summary\nrust,Rust,\nc,C,\n".as_bytes();
+        let set = TagSet::from_reader(&mut raw, &tag_resource().schema, "data/tag.csv")?;
+        let mut ids = Vec::new();
+
+        for tag in &set {
+            ids.push(tag.id().as_str());
+        }
+
+        assert_eq!(ids, vec!["c", "rust"]);
+        assert_eq!(set.len(), 2);
+        assert!(!set.is_empty());
+        assert!(TagSet::new(Vec::new()).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn filter_and_retain_by_id() -> Result<(), TagError> {
+        let mut raw = "id,name,summary\nc,C,\nrust,Rust,\nzig,Zig,\n".as_bytes();
+        let mut set = TagSet::from_reader(&mut raw, &tag_resource().schema, "data/tag.csv")?;
+        let wanted = ["rust", "zig"];
+
+        let filtered = set.filter(|tag| wanted.contains(&tag.id().as_str()));
+        set.retain(|tag| !wanted.contains(&tag.id().as_str()));
+
+        assert_eq!(filtered.to_string(), "rust;zig");
+        assert_eq!(set.to_string(), "c");
+
+        Ok(())
+    }
+
+    #[test]
+    fn short_row_is_located() {
+        let mut raw = "id,name,summary\nc,C,\nrust,Rust\n".as_bytes();
+        let actual =
+            TagSet::from_reader(&mut raw, &tag_resource().schema, "data/tag.csv").unwrap_err();
+
+        assert!(matches!(actual, TagError::Row(_)));
+        assert_eq!(
+            actual.to_string(),
+            "data/tag.csv: row 2: wrong number of fields (expected 3, found 2)"
+        );
+    }
+}
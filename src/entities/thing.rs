@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::{fmt, io};
 use thiserror::Error;
 
+use crate::entities::row::RowError;
 use crate::entities::tag;
 
 pub type Id = String;
@@ -21,7 +22,7 @@ pub struct Thing {
 pub struct Record {
     url: Id,
     name: String,
-    #[serde(with = "empty_string")]
+    #[serde(default, with = "empty_string")]
     summary: Option<String>,
     category_id: tag::Id,
 }
@@ -51,6 +52,14 @@ impl Record {
     pub fn category_id(&self) -> &tag::Id {
         &self.category_id
     }
+
+    /// Whether both records carry the same meaningful content, the name and category.
+    ///
+    /// Unlike the derived `Eq`, the `url` identity and the `summary` prose are left out so a
+    /// caller pairing records by `url` can tell a modified thing from a touched-up one.
+    pub fn content_eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.category_id == other.category_id
+    }
 }
 
 impl fmt::Display for Record {
@@ -59,6 +68,19 @@ impl fmt::Display for Record {
     }
 }
 
+/// Collapses every run of whitespace, newlines included, into a single space and trims the ends.
+///
+/// Returns `None` when nothing is left so a blank summary is never stored.
+pub fn normalize_summary(raw: &str) -> Option<String> {
+    let summary = raw.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if summary.is_empty() {
+        None
+    } else {
+        Some(summary)
+    }
+}
+
 // impl Markdown for Thing {
 //     fn fmt_md(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
 //         write!(f, "")
@@ -98,14 +120,62 @@ mod empty_string {
 pub enum ThingError {
     #[error("A thing exists with the URL '{0}'")]
     Duplicate(String),
+    #[error("No thing exists with the URL '{0}'")]
+    NotFound(String),
     #[error("'url' is a required field")]
     MissingUrl,
     #[error("'name' is a required field")]
     MissingName,
     #[error("'category_id' is a required field")]
     MissingCategory,
+    #[error("The URL '{url}' has a scheme other than the allowed ones: {allowed}")]
+    DisallowedScheme { url: String, allowed: String },
+    #[error(transparent)]
+    Row(#[from] RowError),
     #[error(transparent)]
     Csv(#[from] csv::Error),
     #[error(transparent)]
     Io(#[from] io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, summary: Option<&str>) -> Record {
+        Record::new(
+            "https://www.rust-lang.org/".into(),
+            name.into(),
+            summary.map(Into::into),
+            "language".into(),
+        )
+    }
+
+    #[test]
+    fn content_eq() {
+        let original = record("Rust", Some("A language."));
+
+        assert!(original.content_eq(&record("Rust", Some("A language."))));
+        assert!(original.content_eq(&record("Rust", Some("A systems language."))));
+        assert_ne!(original, record("Rust", Some("A systems language.")));
+        assert!(!original.content_eq(&record("Rust Lang", Some("A language."))));
+    }
+
+    #[test]
+    fn normalize_multiline_summary() {
+        let actual =
+            normalize_summary("  A language\n  empowering everyone\r\n\tto build software.  ");
+
+        assert_eq!(
+            actual.as_deref(),
+            Some("A language empowering everyone to build software.")
+        );
+    }
+
+    #[test]
+    fn normalize_blank_summary() {
+        assert_eq!(normalize_summary(" \n\t  \n"), None);
+        assert_eq!(normalize_summary(""), None);
+        assert_eq!(normalize_summary("Rust").as_deref(), Some("Rust"));
+    }
+}
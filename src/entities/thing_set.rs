@@ -0,0 +1,173 @@
+use std::fmt;
+use std::io::Read;
+use std::iter::FromIterator;
+
+use crate::entities::row::{check_header, locate};
+use crate::entities::thing::{self, ThingError};
+use crate::package::resource::Schema;
+
+#[derive(Debug, Clone)]
+pub struct ThingSet(Vec<thing::Record>);
+
+impl ThingSet {
+    pub fn new(raw: Vec<thing::Record>) -> Self {
+        Self(raw)
+    }
+
+    pub fn as_slice(&self) -> &[thing::Record] {
+        self.0.as_slice()
+    }
+
+    pub fn to_vec(&self) -> Vec<thing::Record> {
+        self.0.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&thing::Record> {
+        self.0.first()
+    }
+
+    /// Loads a ThingSet from a CSV source with a header.
+    ///
+    /// Fields are mapped by header name so a reordered file still reads correctly. Fails when a
+    /// column required by the `schema` is missing.
+    pub fn from_reader<R: Read>(
+        rdr: &mut R,
+        schema: &Schema,
+        resource: &str,
+    ) -> Result<Self, ThingError> {
+        let mut rdr = csv::Reader::from_reader(rdr);
+        let mut set = Vec::new();
+
+        check_header(rdr.headers()?, schema, resource)?;
+
+        for result in rdr.deserialize() {
+            let record: thing::Record = result.map_err(|err| locate(resource, err))?;
+
+            set.push(record);
+        }
+
+        Ok(Self(set))
+    }
+
+    /// Returns a new set with the records matching the predicate.
+    pub fn filter<F>(&self, predicate: F) -> Self
+    where
+        F: Fn(&thing::Record) -> bool,
+    {
+        self.0
+            .iter()
+            .filter(|record| predicate(*record))
+            .cloned()
+            .collect()
+    }
+
+    /// Keeps only the records matching the predicate.
+    pub fn retain<F>(&mut self, predicate: F)
+    where
+        F: Fn(&thing::Record) -> bool,
+    {
+        self.0.retain(|record| predicate(record));
+    }
+}
+
+impl IntoIterator for ThingSet {
+    type Item = thing::Record;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ThingSet {
+    type Item = &'a thing::Record;
+    type IntoIter = std::slice::Iter<'a, thing::Record>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<thing::Record> for ThingSet {
+    fn from_iter<I: IntoIterator<Item = thing::Record>>(iter: I) -> Self {
+        let mut v = Vec::new();
+
+        for item in iter {
+            v.push(item);
+        }
+
+        ThingSet::new(v)
+    }
+}
+
+impl fmt::Display for ThingSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list: Vec<String> = self.0.iter().map(|thing| format!("{}", thing)).collect();
+
+        if f.alternate() {
+            write!(f, "{}", list.join("\n"))
+        } else {
+            write!(f, "{}", list.join(";"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::row::RowError;
+    use crate::lenses::package::thing_resource;
+
+    fn record(url: &str, name: &str, category_id: &str) -> thing::Record {
+        thing::Record::new(url.into(), name.into(), None, category_id.into())
+    }
+
+    #[test]
+    fn filter_and_retain_by_category() {
+        let mut set = ThingSet::new(vec![
+            record("https://www.rust-lang.org/", "Rust", "language"),
+            record("https://www.sqlite.org/", "SQLite", "database"),
+            record("https://ziglang.org/", "Zig", "language"),
+        ]);
+
+        let languages = set.filter(|thing| thing.category_id() == "language");
+        set.retain(|thing| thing.category_id() == "database");
+
+        assert_eq!(languages.to_string(), "Rust;Zig");
+        assert_eq!(set.to_string(), "SQLite");
+        assert!(set.filter(|thing| thing.name() == "Go").is_empty());
+    }
+
+    #[test]
+    fn from_reader_maps_by_header() -> Result<(), ThingError> {
+        let mut raw = "category_id,name,url\nlanguage,Rust,https://www.rust-lang.org/\n".as_bytes();
+        let set = ThingSet::from_reader(&mut raw, &thing_resource().schema, "data/thing.csv")?;
+
+        assert_eq!(
+            set.as_slice(),
+            &[record("https://www.rust-lang.org/", "Rust", "language")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_without_required_column() {
+        let mut raw = "url,name,summary\nhttps://www.rust-lang.org/,Rust,\n".as_bytes();
+        let actual = ThingSet::from_reader(&mut raw, &thing_resource().schema, "data/thing.csv");
+
+        assert!(matches!(
+            actual,
+            Err(ThingError::Row(RowError { row: 0, reason, .. }))
+                if reason == "missing required column `category_id`"
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::io;
 use thiserror::Error;
 
+use crate::entities::row::RowError;
 use crate::entities::tag;
 
 /// A relation between a [`Thing`] and a [`Tag`].
@@ -32,6 +33,12 @@ impl Thingtag {
 pub enum ThingtagError {
     #[error("Unknown thingtag error")]
     Unknown,
+    #[error("The thing '{0}' does not exist.")]
+    MissingThing(String),
+    #[error("The tag '{0}' does not exist.")]
+    MissingTag(String),
+    #[error(transparent)]
+    Row(#[from] RowError),
     #[error(transparent)]
     Csv(#[from] csv::Error),
     #[error(transparent)]
@@ -0,0 +1,108 @@
+use std::io::Read;
+use std::iter::FromIterator;
+
+use crate::entities::row::{check_header, locate};
+use crate::entities::thingtag::{Thingtag, ThingtagError};
+use crate::package::resource::Schema;
+
+#[derive(Debug, Clone)]
+pub struct ThingtagSet(Vec<Thingtag>);
+
+impl ThingtagSet {
+    pub fn new(raw: Vec<Thingtag>) -> Self {
+        Self(raw)
+    }
+
+    pub fn as_slice(&self) -> &[Thingtag] {
+        self.0.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reads the relations from a CSV source with a `thing_id,tag_id` header.
+    ///
+    /// Fields are mapped by header name so a reordered file still reads correctly. Fails when a
+    /// column required by the `schema` is missing.
+    pub fn from_reader<R: Read>(
+        rdr: &mut R,
+        schema: &Schema,
+        resource: &str,
+    ) -> Result<Self, ThingtagError> {
+        let mut rdr = csv::Reader::from_reader(rdr);
+        let mut set = Vec::new();
+
+        check_header(rdr.headers()?, schema, resource)?;
+
+        for result in rdr.deserialize() {
+            let record: Thingtag = result.map_err(|err| locate(resource, err))?;
+
+            set.push(record);
+        }
+
+        Ok(Self(set))
+    }
+}
+
+impl IntoIterator for ThingtagSet {
+    type Item = Thingtag;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ThingtagSet {
+    type Item = &'a Thingtag;
+    type IntoIter = std::slice::Iter<'a, Thingtag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Thingtag> for ThingtagSet {
+    fn from_iter<I: IntoIterator<Item = Thingtag>>(iter: I) -> Self {
+        ThingtagSet::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lenses::package::thing_tag_resource;
+
+    #[test]
+    fn from_reader_maps_by_header() -> Result<(), ThingtagError> {
+        let schema = thing_tag_resource().schema;
+        let mut raw = "tag_id,thing_id\nrust,https://www.rust-lang.org/\n".as_bytes();
+        let set = ThingtagSet::from_reader(&mut raw, &schema, "data/thing_tag.csv")?;
+
+        assert_eq!(
+            set.as_slice(),
+            &[Thingtag::new(
+                "https://www.rust-lang.org/".into(),
+                "rust".into()
+            )]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_locates_bad_rows() {
+        let schema = thing_tag_resource().schema;
+        let mut raw = "thing_id,tag_id\nhttps://www.rust-lang.org/\n".as_bytes();
+
+        match ThingtagSet::from_reader(&mut raw, &schema, "data/thing_tag.csv") {
+            Err(ThingtagError::Row(err)) => assert_eq!(err.row, 1),
+            other => panic!("expected a row error, got {:?}", other),
+        }
+    }
+}
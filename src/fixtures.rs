@@ -0,0 +1,195 @@
+//! Helpers to scaffold packages on disk for tests.
+
+use tempfile::TempDir;
+
+use crate::cli::init::scaffold;
+use crate::lenses;
+use crate::package::PackageBuilder;
+
+/// Scaffolds an empty Some package in a temporary directory.
+pub fn package() -> TempDir {
+    let dir = tempfile::tempdir().expect("a temporary directory");
+    let package = PackageBuilder::new("some-test")
+        .expect("a valid name")
+        .title("Some Test")
+        .description("A test collection.")
+        .resources(lenses::package::resources())
+        .build()
+        .expect("a valid package");
+
+    scaffold(dir.path(), &package, false).expect("a scaffolded package");
+
+    dir
+}
+
+/// A complete package descriptor.
+pub fn canonical() -> String {
+    r#"{
+      "profile": "tabular-data-package",
+      "id": "00000000-0000-0000-0000-000000000000",
+      "name": "some-nothingness",
+      "title": "Some Nothingness",
+      "description": "Nothing",
+      "licenses": [{
+        "name": "ODC-PDDL",
+        "path": "http://opendatacommons.org/licenses/pddl/",
+        "title": "Open Data Commons Public Domain Dedication and License"
+      }],
+      "homepage": "https://github.com/arnau/some-nothingness",
+      "created": "2020-12-29T10:11:12Z",
+      "contributors": [{
+        "title": "Arnau Siches",
+        "path": "https://www.seachess.net/",
+        "role": "author"
+      }],
+      "keywords": [
+        "sqlite",
+        "some-thing"
+      ],
+      "resources": [
+        {
+          "profile": "tabular-data-resource",
+          "name": "thing",
+          "title": "Thing",
+          "description": "The set of things for the collection.",
+          "path": "data/thing.csv",
+          "encoding": "UTF-8",
+          "schema": {
+            "fields": [
+              {
+                "name": "url",
+                "description": "The URL of the thing.",
+                "type": "string",
+                "format": "uri",
+                "constraints": [{
+                  "required": true,
+                  "unique": true
+                }]
+              },
+              {
+                "name": "name",
+                "description": "The name of the thing.",
+                "type": "string",
+                "constraints": [{
+                  "required": true,
+                  "unique": true
+                }]
+              },
+              {
+                "name": "summary",
+                "description": "The description of the thing.",
+                "type": "string",
+                "constraints": [{
+                  "required": false,
+                  "unique": false
+                }]
+              },
+              {
+                "name": "category_id",
+                "description": "The category of the thing.",
+                "type": "string",
+                "constraints": [{
+                  "required": true,
+                  "unique": false
+                }]
+              }
+            ],
+            "primaryKey": ["url"],
+            "foreignKeys": [{
+              "fields": ["category_id"],
+              "reference": {
+                "resource": "tag",
+                "fields": ["id"]
+              }
+            }]
+          }
+        },
+        {
+          "profile": "tabular-data-resource",
+          "name": "tag",
+          "title": "Tag",
+          "description": "The set of tags to classify the collection of things.",
+          "path": "data/tag.csv",
+          "encoding": "UTF-8",
+          "schema": {
+            "fields": [
+              {
+                "name": "id",
+                "description": "The tag identifier.",
+                "type": "string",
+                "constraints": [{
+                  "required": true,
+                  "unique": true
+                }]
+              },
+              {
+                "name": "name",
+                "description": "The tag name.",
+                "type": "string",
+                "constraints": [{
+                  "required": true,
+                  "unique": true
+                }]
+              },
+              {
+                "name": "summary",
+                "description": "The tag description.",
+                "type": "string",
+                "constraints": [{
+                  "required": false,
+                  "unique": false
+                }]
+              }
+            ],
+            "primaryKey": ["id"]
+          }
+        },
+        {
+          "profile": "tabular-data-resource",
+          "name": "thing_tag",
+          "title": "Thing tags",
+          "description": "The set of tags to further classify the collection of things.",
+          "path": "data/thing_tag.csv",
+          "encoding": "UTF-8",
+          "schema": {
+            "fields": [
+              {
+                "name": "thing_id",
+                "description": "The reference to a thing.",
+                "type": "string",
+                "format": "uri",
+                "constraints": [{
+                  "required": true,
+                  "unique": false
+                }]
+              },
+              {
+                "name": "tag_id",
+                "description": "The reference to a tag.",
+                "type": "string",
+                "constraints": [{
+                  "required": true,
+                  "unique": false
+                }]
+              }
+            ],
+            "primaryKey": ["thing_id", "tag_id"],
+            "foreignKeys": [{
+              "fields": ["thing_id"],
+              "reference": {
+                "resource": "thing",
+                "fields": ["url"]
+              }
+            }, {
+            "fields": ["tag_id"],
+            "reference": {
+              "resource": "tag",
+              "fields": ["id"]
+            }
+            }]
+          }
+        }
+      ]
+    }"#
+    .to_string()
+}
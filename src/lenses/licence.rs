@@ -20,6 +20,37 @@ pub fn fetch_licenses() -> Result<Vec<Licence>> {
     Ok(set)
 }
 
+/// A curated subset of common licences, available offline.
+const BUNDLED_LICENCES: &str = include_str!("licences.json");
+
+/// The licences to choose from, and whether they come from the offline subset.
+#[derive(Debug, Clone)]
+pub struct LicenceChoices {
+    pub items: Vec<Licence>,
+    pub offline: bool,
+}
+
+/// Fetches the licences, falling back to the bundled subset when the fetch fails.
+pub fn licences_or_bundled<F>(fetch: F) -> LicenceChoices
+where
+    F: FnOnce() -> Result<Vec<Licence>>,
+{
+    match fetch() {
+        Ok(items) => LicenceChoices {
+            items,
+            offline: false,
+        },
+        Err(_) => LicenceChoices {
+            items: bundled_licenses(),
+            offline: true,
+        },
+    }
+}
+
+pub fn bundled_licenses() -> Vec<Licence> {
+    serde_json::from_str(BUNDLED_LICENCES).expect("bundled licences to be valid JSON")
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FullLicence {
     pub id: String,
@@ -77,6 +108,10 @@ impl LicenceSet {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn first(&self) -> Option<&Licence> {
         self.0.first()
     }
@@ -91,6 +126,15 @@ impl IntoIterator for LicenceSet {
     }
 }
 
+impl<'a> IntoIterator for &'a LicenceSet {
+    type Item = &'a Licence;
+    type IntoIter = std::slice::Iter<'a, Licence>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
 impl FromIterator<Licence> for LicenceSet {
     fn from_iter<I: IntoIterator<Item = Licence>>(iter: I) -> Self {
         let mut v = Vec::new();
@@ -142,3 +186,26 @@ impl From<LicenceSet> for SkimItemReceiver {
         rx_item
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SomeError;
+
+    #[test]
+    fn network_failure_offers_bundled() {
+        let actual = licences_or_bundled(|| Err(SomeError::Unknown("offline".into())));
+        let names: Vec<&str> = actual.items.iter().map(|licence| licence.name()).collect();
+
+        assert!(actual.offline);
+        assert_eq!(names, vec!["CC0-1.0", "CC-BY-4.0", "ODC-PDDL-1.0", "MIT"]);
+    }
+
+    #[test]
+    fn network_success_is_used() {
+        let actual = licences_or_bundled(|| Ok(Vec::new()));
+
+        assert!(!actual.offline);
+        assert!(actual.items.is_empty());
+    }
+}
@@ -1,7 +1,5 @@
 use crate::package::core::Name;
-use crate::package::resource::{
-    Constraint, Field, ForeignKey, Reference, Resource, ResourceBuilder, Schema,
-};
+use crate::package::resource::{FieldBuilder, Resource, ResourceBuilder, SchemaBuilder};
 
 /// Builds the resources for a Some package.
 pub fn resources() -> Vec<Resource> {
@@ -9,174 +7,91 @@ pub fn resources() -> Vec<Resource> {
 }
 
 pub fn tag_resource() -> Resource {
-    let schema = Schema {
-        fields: vec![
-            Field {
-                name: Name::new("id"),
-                description: "The tag identifier.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: true,
-                }],
-            },
-            Field {
-                name: Name::new("name"),
-                description: "The tag human readable name.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: true,
-                }],
-            },
-            Field {
-                name: Name::new("summary"),
-                description: "The tag description.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: false,
-                    unique: false,
-                }],
-            },
-        ],
-        primary_key: vec![Name::new("id")],
-        foreign_keys: vec![],
-    };
+    let schema = SchemaBuilder::new()
+        .field(
+            FieldBuilder::new("id")
+                .description("The tag identifier.")
+                .required()
+                .unique(),
+        )
+        .field(
+            FieldBuilder::new("name")
+                .description("The tag human readable name.")
+                .required()
+                .unique(),
+        )
+        .field(FieldBuilder::new("summary").description("The tag description."))
+        .primary_key(&["id"])
+        .build();
 
-    let mut builder = ResourceBuilder::new();
-    builder.with_name(Name::new("tag"));
-    builder.with_title("Tag");
-    builder.with_description("The set of tags to classify the collection of things.");
-    builder.with_path("data/tag.csv");
-    builder.with_schema(schema);
-
-    let resource = builder.build();
-
-    resource
+    ResourceBuilder::new()
+        .with_name(Name::new("tag"))
+        .with_title("Tag")
+        .with_description("The set of tags to classify the collection of things.")
+        .with_path("data/tag.csv")
+        .with_schema(schema)
+        .build()
 }
 
 /// The thing resouce.
 pub fn thing_resource() -> Resource {
-    let schema = Schema {
-        fields: vec![
-            Field {
-                name: Name::new("url"),
-                description: "The URL of the thing.".into(),
-                datatype: "string".into(),
-                format: Some("uri".into()),
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: true,
-                }],
-            },
-            Field {
-                name: Name::new("name"),
-                description: "The name of the thing.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: true,
-                }],
-            },
-            Field {
-                name: Name::new("summary"),
-                description: "The description of the thing.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: false,
-                    unique: false,
-                }],
-            },
-            Field {
-                name: Name::new("category_id"),
-                description: "The category of the thing.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: false,
-                }],
-            },
-        ],
-        primary_key: vec![Name::new("url")],
-        foreign_keys: vec![ForeignKey {
-            fields: vec![Name::new("category_id")],
-            reference: Reference {
-                resource: Name::new("tag"),
-                fields: vec![Name::new("id")],
-            },
-        }],
-    };
-
-    let mut builder = ResourceBuilder::new();
-    builder.with_name(Name::new("thing"));
-    builder.with_title("Thing");
-    builder.with_description("The set of things for the collection.");
-    builder.with_path("data/thing.csv");
-    builder.with_schema(schema);
+    let schema = SchemaBuilder::new()
+        .field(
+            FieldBuilder::new("url")
+                .description("The URL of the thing.")
+                .format("uri")
+                .required()
+                .unique(),
+        )
+        .field(
+            FieldBuilder::new("name")
+                .description("The name of the thing.")
+                .required()
+                .unique(),
+        )
+        .field(FieldBuilder::new("summary").description("The description of the thing."))
+        .field(
+            FieldBuilder::new("category_id")
+                .description("The category of the thing.")
+                .required(),
+        )
+        .primary_key(&["url"])
+        .foreign_key(&["category_id"], "tag", &["id"])
+        .build();
 
-    let resource = builder.build();
-
-    resource
+    ResourceBuilder::new()
+        .with_name(Name::new("thing"))
+        .with_title("Thing")
+        .with_description("The set of things for the collection.")
+        .with_path("data/thing.csv")
+        .with_schema(schema)
+        .build()
 }
 
 /// The thing_tag resouce.
 pub fn thing_tag_resource() -> Resource {
-    let schema = Schema {
-        fields: vec![
-            Field {
-                name: Name::new("thing_id"),
-                description: "The reference to a thing.".into(),
-                datatype: "string".into(),
-                format: Some("uri".into()),
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: false,
-                }],
-            },
-            Field {
-                name: Name::new("tag_id"),
-                description: "The reference to a tag.".into(),
-                datatype: "string".into(),
-                format: None,
-                constraints: vec![Constraint {
-                    required: true,
-                    unique: false,
-                }],
-            },
-        ],
-        primary_key: vec![Name::new("thing_id"), Name::new("tag_id")],
-        foreign_keys: vec![
-            ForeignKey {
-                fields: vec![Name::new("thing_id")],
-                reference: Reference {
-                    resource: Name::new("thing"),
-                    fields: vec![Name::new("url")],
-                },
-            },
-            ForeignKey {
-                fields: vec![Name::new("tag_id")],
-                reference: Reference {
-                    resource: Name::new("tag"),
-                    fields: vec![Name::new("id")],
-                },
-            },
-        ],
-    };
-
-    let mut builder = ResourceBuilder::new();
-    builder.with_name(Name::new("thing_tag"));
-    builder.with_title("Thing tags");
-    builder.with_description("The set of tags to further classify the collection of things.");
-    builder.with_path("data/thing_tag.csv");
-    builder.with_schema(schema);
-
-    let resource = builder.build();
+    let schema = SchemaBuilder::new()
+        .field(
+            FieldBuilder::new("thing_id")
+                .description("The reference to a thing.")
+                .format("uri")
+                .required(),
+        )
+        .field(
+            FieldBuilder::new("tag_id")
+                .description("The reference to a tag.")
+                .required(),
+        )
+        .primary_key(&["thing_id", "tag_id"])
+        .foreign_key(&["thing_id"], "thing", &["url"])
+        .foreign_key(&["tag_id"], "tag", &["id"])
+        .build();
 
-    resource
+    ResourceBuilder::new()
+        .with_name(Name::new("thing_tag"))
+        .with_title("Thing tags")
+        .with_description("The set of tags to further classify the collection of things.")
+        .with_path("data/thing_tag.csv")
+        .with_schema(schema)
+        .build()
 }
@@ -2,40 +2,244 @@ use scraper::{Html, Selector};
 use url::Url;
 
 use crate::{Result, SomeError};
+use crate::entities::thing::{normalize_summary, ThingError};
 use crate::info;
+use crate::services::fetcher::Fetcher;
 
+/// The URL schemes a thing can have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeAllowlist(Vec<String>);
 
-pub fn validate_url<S: Into<String>>(input: S) -> Result<()> {
-    Url::parse(&input.into())?;
+impl SchemeAllowlist {
+    pub fn new<I, S>(schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self(
+            schemes
+                .into_iter()
+                .map(|scheme| scheme.as_ref().trim().to_ascii_lowercase())
+                .filter(|scheme| !scheme.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn allows(&self, scheme: &str) -> bool {
+        self.0.iter().any(|allowed| allowed == scheme)
+    }
+}
+
+impl Default for SchemeAllowlist {
+    fn default() -> Self {
+        Self::new(["http", "https"])
+    }
+}
+
+/// Checks the input is a URL with one of the schemes in the allowlist.
+pub fn validate_url_with<S: Into<String>>(input: S, allowlist: &SchemeAllowlist) -> Result<()> {
+    let input = input.into();
+    let url = Url::parse(&input)?;
+
+    if !allowlist.allows(url.scheme()) {
+        return Err(ThingError::DisallowedScheme {
+            url: input,
+            allowed: allowlist.0.join(", "),
+        }
+        .into());
+    }
 
     Ok(())
 }
 
-pub fn fetch_thing(input: &str) -> Result<()> {
+/// The metadata scraped from a thing's web page.
+///
+/// The rich fields are only looked for when asked to and stay `None` when missing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FetchedThing {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub author: Option<String>,
+    pub published: Option<String>,
+    pub favicon: Option<String>,
+}
+
+/// Same as [`scrape_thing`] reporting the progress and what was found.
+pub fn fetch_thing(fetcher: &mut Fetcher, input: &str, rich: bool) -> Result<FetchedThing> {
     info!("Fetching information about:", input);
 
+    let fetched = scrape_thing(fetcher, input, rich)?;
+
+    if let Some(title) = &fetched.title {
+        info!("Found a title:", title);
+    }
+
+    if let Some(summary) = &fetched.summary {
+        info!("Found a summary:", summary);
+    }
+
+    if let Some(author) = &fetched.author {
+        info!("Found an author:", author);
+    }
+
+    if let Some(published) = &fetched.published {
+        info!("Found a publication date:", published);
+    }
+
+    if let Some(favicon) = &fetched.favicon {
+        info!("Found a favicon:", favicon);
+    }
+
+    Ok(fetched)
+}
+
+/// Fetches the page at `input` and extracts its metadata without printing anything, for callers
+/// whose output must stay clean such as `build` writing to stdout.
+pub fn scrape_thing(fetcher: &mut Fetcher, input: &str, rich: bool) -> Result<FetchedThing> {
     let url = Url::parse(input)?;
-    let req = reqwest::blocking::get(url)?;
-    let status = req.status();
-    let body = req.text()?;
+    let page = fetcher.get(&url)?;
 
-    if status != 200 {
+    if page.status != 200 {
         return Err(SomeError::BadUrl(input.to_string()));
     }
 
-    let document = Html::parse_document(&body);
-    let title_selector = Selector::parse("title").expect("valid css selector");
-    let desc_selector = Selector::parse(r#"meta[name="description"]"#).expect("valid css selector");
+    Ok(parse_thing(&page.body, &url, rich))
+}
 
-    for element in document.select(&title_selector) {
-        info!("Found a title:", &element.text().collect::<String>());
+/// Extracts the metadata from an HTML document found at `base`.
+fn parse_thing(body: &str, base: &Url, rich: bool) -> FetchedThing {
+    let document = Html::parse_document(body);
+    let title = select_text(&document, "title");
+    let summary = select_attr(&document, r#"meta[name="description"]"#, "content")
+        .and_then(|summary| normalize_summary(&summary));
+
+    if !rich {
+        return FetchedThing {
+            title,
+            summary,
+            ..Default::default()
+        };
     }
 
-    for element in document.select(&desc_selector) {
-        if let Some(summary) = &element.value().attr("content") {
-            info!("Found a summary:", summary);
-        }
+    let author = select_attr(&document, r#"meta[name="author"]"#, "content");
+    let published = select_attr(
+        &document,
+        r#"meta[property="article:published_time"]"#,
+        "content",
+    );
+    // Relative links are resolved against the page URL.
+    let favicon = select_attr(&document, r#"link[rel~="icon"]"#, "href")
+        .and_then(|href| base.join(&href).ok())
+        .map(|url| url.to_string());
+
+    FetchedThing {
+        title,
+        summary,
+        author,
+        published,
+        favicon,
     }
+}
 
-    Ok(())
+fn select_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).expect("valid css selector");
+
+    document
+        .select(&selector)
+        .next()
+        .map(|element| element.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    let selector = Selector::parse(selector).expect("valid css selector");
+
+    document
+        .select(&selector)
+        .find_map(|element| element.value().attr(attr))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_schemes_allow_https() -> Result<()> {
+        validate_url_with("https://www.rust-lang.org/", &SchemeAllowlist::default())
+    }
+
+    #[test]
+    fn default_schemes_reject_javascript() {
+        let result = validate_url_with("javascript:alert(1)", &SchemeAllowlist::default());
+
+        assert!(matches!(
+            result,
+            Err(SomeError::Thing(ThingError::DisallowedScheme { url, allowed }))
+                if url == "javascript:alert(1)" && allowed == "http, https"
+        ));
+    }
+
+    #[test]
+    fn configured_schemes() -> Result<()> {
+        let allowlist = SchemeAllowlist::new(" FTP ,https,".split(','));
+
+        validate_url_with("ftp://files.example.org/", &allowlist)?;
+        assert!(validate_url_with("http://example.org/", &allowlist).is_err());
+
+        Ok(())
+    }
+
+    const RICH_PAGE: &str = r#"
+        <html>
+          <head>
+            <title>Rust</title>
+            <meta name="description" content="A language empowering everyone.">
+            <meta name="author" content="The Rust Team">
+            <meta property="article:published_time" content="2015-05-15T00:00:00Z">
+            <link rel="shortcut icon" href="/static/favicon.ico">
+          </head>
+        </html>
+    "#;
+
+    const BARE_PAGE: &str = "<html><head></head><body>Nothing here.</body></html>";
+
+    fn base() -> Url {
+        Url::parse("https://www.rust-lang.org/learn").expect("valid url")
+    }
+
+    #[test]
+    fn rich_fields() {
+        let actual = parse_thing(RICH_PAGE, &base(), true);
+
+        assert_eq!(actual.title.as_deref(), Some("Rust"));
+        assert_eq!(
+            actual.summary.as_deref(),
+            Some("A language empowering everyone.")
+        );
+        assert_eq!(actual.author.as_deref(), Some("The Rust Team"));
+        assert_eq!(actual.published.as_deref(), Some("2015-05-15T00:00:00Z"));
+        assert_eq!(
+            actual.favicon.as_deref(),
+            Some("https://www.rust-lang.org/static/favicon.ico")
+        );
+    }
+
+    #[test]
+    fn rich_fields_are_opt_in() {
+        let actual = parse_thing(RICH_PAGE, &base(), false);
+
+        assert_eq!(actual.title.as_deref(), Some("Rust"));
+        assert_eq!(actual.author, None);
+        assert_eq!(actual.published, None);
+        assert_eq!(actual.favicon, None);
+    }
+
+    #[test]
+    fn missing_fields() {
+        let actual = parse_thing(BARE_PAGE, &base(), true);
+
+        assert_eq!(actual, FetchedThing::default());
+    }
 }
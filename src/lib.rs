@@ -10,6 +10,7 @@ use toml;
 use url;
 
 pub mod cli;
+pub mod color;
 pub mod context;
 pub mod lenses;
 pub mod markdown;
@@ -19,11 +20,16 @@ pub mod shell;
 
 pub mod entities;
 pub mod services;
+pub mod settings;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
 
 pub use markdown::Markdown;
 
 use package::core::PackageError;
 use store::StoreError;
+use entities::row::RowError;
 use entities::tag::TagError;
 use entities::thing::ThingError;
 use entities::thingtag::ThingtagError;
@@ -74,6 +80,8 @@ pub enum SomeError {
     Package(#[from] PackageError),
     #[error(transparent)]
     StoreError(#[from] StoreError),
+    #[error(transparent)]
+    Row(#[from] RowError),
 
     #[error("unknown {0}")]
     Unknown(String),
@@ -85,15 +93,42 @@ pub enum SomeError {
     FieldRequired(String),
     #[error("couldn't find the project directory")]
     ProjectDir,
-    #[error("{0}")]
-    SealError(String),
+    #[error("The given name `{got}` does not match the data package `{expected}`.")]
+    ConfirmationMismatch { expected: String, got: String },
     #[error("`{0}` is not a Some package.")]
     MissingPackageDescriptor(String),
+    #[error("`{0}` already exists. Use `--force` to overwrite it.")]
+    Conflict(String),
+    #[error("`{path}` has files unknown to Some: {}. Use `--force` to remove them too.", .files.join(", "))]
+    UnknownFiles { path: String, files: Vec<String> },
+    #[error("The resource file `{0}` is missing.")]
+    MissingResource(String),
+    #[error("The resource `{0}` has its data inline in the descriptor and can't be changed.")]
+    InlineResource(String),
+    #[error("The commit stopped after replacing {}, the staged changes were kept: {source}", .replaced.join(", "))]
+    PartialCommit {
+        replaced: Vec<String>,
+        source: io::Error,
+    },
+    #[error("Unknown encoding `{0}`. Use a label such as `utf-8` or `windows-1252`.")]
+    UnknownEncoding(String),
+    #[error("No editor found. Set `$EDITOR` to the command to edit the descriptor with.")]
+    NoEditor,
+    #[error("The edited descriptor is invalid so the original was restored, the edit is kept in `{rejected}`: {}", .findings.join("; "))]
+    InvalidDescriptor {
+        rejected: String,
+        findings: Vec<String>,
+    },
+    #[error("The archive `{archive}` does not hold a valid package: {}", .findings.join("; "))]
+    InvalidArchive {
+        archive: String,
+        findings: Vec<String>,
+    },
 
     // External
     #[error("{0}")]
     Lazy(#[from] anyhow::Error),
-    #[error("date error")]
+    #[error("date error: {0}")]
     Date(#[from] ChronoError),
     #[error("url error")]
     Url(#[from] url::ParseError),
@@ -117,6 +152,76 @@ pub enum SomeError {
     Fetch(#[from] reqwest::Error),
     #[error("json")]
     Json(#[from] serde_json::Error),
+    #[error("signal handler")]
+    Signal(#[from] ctrlc::Error),
+    #[error("file watcher: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+impl SomeError {
+    /// Whether the error is about something that couldn't be found, e.g. a tag, a resource file
+    /// or a package descriptor.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            SomeError::Unknown(_)
+            | SomeError::MissingPackageDescriptor(_)
+            | SomeError::MissingResource(_)
+            | SomeError::Thing(ThingError::NotFound(_))
+            | SomeError::Tag(TagError::NotFound(_) | TagError::Misspelled { .. })
+            | SomeError::Thingtag(ThingtagError::MissingThing(_) | ThingtagError::MissingTag(_))
+            | SomeError::StoreError(StoreError::MissingCsv { .. })
+            | SomeError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => true,
+            SomeError::Io(err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+
+    /// Whether the error comes from reading or writing files.
+    pub fn is_io(&self) -> bool {
+        match self {
+            SomeError::Io(_)
+            | SomeError::Thing(ThingError::Io(_))
+            | SomeError::Tag(TagError::Io(_))
+            | SomeError::Thingtag(ThingtagError::Io(_))
+            | SomeError::PartialCommit { .. }
+            | SomeError::Watch(_) => true,
+            SomeError::Csv(err) => err.is_io_error(),
+            _ => false,
+        }
+    }
+
+    /// Whether the error comes from fetching a remote resource.
+    pub fn is_network(&self) -> bool {
+        matches!(self, SomeError::Fetch(_))
+    }
+
+    /// Whether the error is about data that doesn't hold to the package rules, e.g. a malformed
+    /// URL, a missing required field or a duplicate.
+    pub fn is_validation(&self) -> bool {
+        matches!(
+            self,
+            SomeError::Package(_)
+                | SomeError::Row(_)
+                | SomeError::Thing(
+                    ThingError::Duplicate(_)
+                        | ThingError::MissingUrl
+                        | ThingError::MissingName
+                        | ThingError::MissingCategory
+                        | ThingError::DisallowedScheme { .. }
+                        | ThingError::Row(_)
+                )
+                | SomeError::Tag(TagError::Duplicate(_) | TagError::Row(_))
+                | SomeError::Thingtag(ThingtagError::Row(_))
+                | SomeError::UrlExists(_)
+                | SomeError::BadUrl(_)
+                | SomeError::FieldRequired(_)
+                | SomeError::InvalidDescriptor { .. }
+                | SomeError::InvalidArchive { .. }
+                | SomeError::ConfirmationMismatch { .. }
+                | SomeError::UnknownEncoding(_)
+                | SomeError::Url(_)
+        )
+    }
 }
 
 impl ser::Error for SomeError {
@@ -135,6 +240,80 @@ impl de::Error for SomeError {
 macro_rules! info {
     ($a:expr, $b:expr) => {{
         use ansi_term::Colour::Cyan;
-        println!("{} {}", Cyan.bold().paint($a), $b);
+        println!(
+            "{} {}",
+            $crate::color::Palette::current().paint(Cyan.bold(), $a),
+            $b
+        );
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails to compile if an entity error loses its `SomeError` variant.
+    fn lift<E: Into<SomeError>>(err: E) -> Result<()> {
+        Err(err.into())
+    }
+
+    #[test]
+    fn entity_errors_convert() {
+        assert!(matches!(
+            lift(ThingError::MissingUrl),
+            Err(SomeError::Thing(_))
+        ));
+        assert!(matches!(lift(TagError::Unknown), Err(SomeError::Tag(_))));
+        assert!(matches!(
+            lift(ThingtagError::Unknown),
+            Err(SomeError::Thingtag(_))
+        ));
+        assert!(matches!(
+            lift(PackageError::RequiredField("name".into())),
+            Err(SomeError::Package(_))
+        ));
+        assert!(matches!(
+            lift(StoreError::EmptyError("SELECT 1".into())),
+            Err(SomeError::StoreError(_))
+        ));
+        assert!(matches!(
+            lift(RowError {
+                resource: "data/tag.csv".into(),
+                row: 1,
+                reason: "wrong number of fields".into(),
+            }),
+            Err(SomeError::Row(_))
+        ));
+    }
+
+    #[test]
+    fn classify_not_found() {
+        assert!(SomeError::Tag(TagError::NotFound("rust".into())).is_not_found());
+        assert!(SomeError::MissingResource("data/thing.csv".into()).is_not_found());
+        assert!(SomeError::Io(io::Error::from(io::ErrorKind::NotFound)).is_not_found());
+        assert!(!SomeError::Io(io::Error::from(io::ErrorKind::PermissionDenied)).is_not_found());
+        assert!(!SomeError::BadUrl("rust".into()).is_not_found());
+    }
+
+    #[test]
+    fn classify_io() {
+        assert!(SomeError::Io(io::Error::from(io::ErrorKind::PermissionDenied)).is_io());
+        assert!(SomeError::Tag(TagError::Io(io::Error::from(io::ErrorKind::Other))).is_io());
+        assert!(!SomeError::MissingResource("data/thing.csv".into()).is_io());
+    }
+
+    #[test]
+    fn classify_network() {
+        assert!(!SomeError::BadUrl("rust".into()).is_network());
+        assert!(!SomeError::Io(io::Error::from(io::ErrorKind::Other)).is_network());
+    }
+
+    #[test]
+    fn classify_validation() {
+        assert!(SomeError::FieldRequired("category".into()).is_validation());
+        assert!(SomeError::Thing(ThingError::MissingUrl).is_validation());
+        assert!(SomeError::Package(PackageError::MalformedName("Some".into())).is_validation());
+        assert!(!SomeError::Tag(TagError::NotFound("rust".into())).is_validation());
+        assert!(!SomeError::NoEditor.is_validation());
+    }
+}
@@ -1,5 +1,8 @@
 use clap::{AppSettings, Parser};
 use some::cli;
+use some::color::{self, ColorChoice};
+use std::io;
+use std::process;
 
 const CLI_NAME: &str = "some";
 
@@ -11,11 +14,29 @@ enum Subcommand {
     Build(cli::build::Cmd),
     Destroy(cli::destroy::Cmd),
     Shell(cli::shell::Cmd),
+    Maintenance(cli::maintenance::Cmd),
+    Validate(cli::validate::Cmd),
+    Tag(cli::tag::Cmd),
+    Verify(cli::verify::Cmd),
+    List(cli::list::Cmd),
+    Import(cli::import::Cmd),
+    Fmt(cli::fmt::Cmd),
+    EditDescriptor(cli::edit_descriptor::Cmd),
+    OpenDb(cli::open_db::Cmd),
+    Export(cli::export::Cmd),
+    Backup(cli::backup::Cmd),
+    Restore(cli::restore::Cmd),
 }
 
 #[derive(Debug, Parser)]
 #[clap(name = CLI_NAME, version, global_setting(AppSettings::ColoredHelp))]
 struct Cli {
+    /// When to use colours: auto, always or never.
+    #[clap(long, global = true, value_name = "when", default_value = "auto")]
+    color: ColorChoice,
+    /// Report what would be written instead of writing it.
+    #[clap(long = "dry-run", global = true)]
+    dry_run_flag: bool,
     #[clap(subcommand)]
     subcommand: Subcommand,
 }
@@ -23,8 +44,10 @@ struct Cli {
 fn main() {
     let cli: Cli = Cli::parse();
 
+    color::set(cli.color);
+
     match cli.subcommand {
-        Subcommand::Init(cmd) => match cmd.run() {
+        Subcommand::Init(cmd) => match cmd.run(cli.dry_run_flag) {
             Ok(msg) => {
                 println!("{}", msg);
             }
@@ -32,7 +55,7 @@ fn main() {
                 eprintln!("{:?}", err);
             }
         },
-        Subcommand::Destroy(cmd) => match cmd.run() {
+        Subcommand::Destroy(cmd) => match cmd.run(cli.dry_run_flag) {
             Ok(msg) => {
                 println!("{}", msg);
             }
@@ -40,7 +63,7 @@ fn main() {
                 eprintln!("{:?}", err);
             }
         },
-        Subcommand::Add(cmd) => match cmd.run() {
+        Subcommand::Add(cmd) => match cmd.run(cli.dry_run_flag) {
             Ok(msg) => {
                 println!("{}", msg);
             }
@@ -56,6 +79,107 @@ fn main() {
                 eprintln!("{:?}", err);
             }
         },
+        Subcommand::Maintenance(cmd) => match cmd.run() {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Validate(cmd) => match cmd.run() {
+            Ok(validation) => {
+                if let Err(err) = validation.render(&mut io::stdout()) {
+                    eprintln!("{:?}", err);
+                }
+                process::exit(validation.exit_code());
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+                process::exit(1);
+            }
+        },
+        Subcommand::Tag(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Verify(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(verification) => {
+                println!("{}", verification);
+                process::exit(verification.exit_code());
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+                process::exit(1);
+            }
+        },
+        Subcommand::Fmt(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::EditDescriptor(cmd) => match cmd.run() {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                // The message tells how to recover, e.g. which `$EDITOR` to set.
+                eprintln!("{}", err);
+            }
+        },
+        Subcommand::OpenDb(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Export(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Backup(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Restore(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::Import(cmd) => match cmd.run(cli.dry_run_flag) {
+            Ok(msg) => {
+                println!("{}", msg);
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
+        Subcommand::List(cmd) => match cmd.run() {
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("{:?}", err);
+            }
+        },
         Subcommand::Shell(cmd) => match cmd.run() {
             Ok(_) => {}
             Err(err) => {
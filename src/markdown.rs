@@ -6,3 +6,63 @@ use std::fmt;
 pub trait Markdown {
     fn fmt_md(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error>;
 }
+
+/// Escapes the characters Markdown reads as formatting, links or inline HTML so `text` renders
+/// as written. Pipes are escaped as well to keep the text within a table cell.
+pub fn escape_md(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '&'
+        ) {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Escapes `text` for use as HTML text or as a quoted attribute value.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_md_metacharacters() {
+        assert_eq!(
+            escape_md(r#"<b>Fish & "chips"</b> | *new*"#),
+            r#"\<b\>Fish \& "chips"\</b\> \| \*new\*"#
+        );
+        assert_eq!(escape_md("snake_case [link]"), "snake\\_case \\[link\\]");
+    }
+
+    #[test]
+    fn escape_html_markup() {
+        assert_eq!(
+            escape_html(r#"<b>Fish & "chips"</b>"#),
+            "&lt;b&gt;Fish &amp; &quot;chips&quot;&lt;/b&gt;"
+        );
+        assert_eq!(escape_html("it's"), "it&#39;s");
+    }
+}
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
 use std::fmt;
 use std::str::FromStr;
 use thiserror::Error;
 
+use super::schema::Violation;
+
 /// Represents a [Profile](https://specs.frictionlessdata.io/profiles/).
 ///
 /// See the [registry](https://specs.frictionlessdata.io/schemas/registry.json) for more details.
@@ -46,7 +49,9 @@ pub type ResourceProfile = Profile;
 pub type Identifier = String;
 
 /// An identifier string. Lower case characters with `.`, `_`, `-` and `/` are allowed.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// Names hash and compare like their string so a map keyed by `Name` can be queried with a `&str`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Name(String);
 
 impl Name {
@@ -75,6 +80,19 @@ impl AsRef<[u8]> for Name {
     }
 }
 
+impl Borrow<str> for Name {
+    #[inline]
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
 /// Checks whether a string is a valid `Name`.
 fn assert_name(name: &str) -> Result<(), PackageError> {
     for character in name.chars() {
@@ -169,6 +187,48 @@ pub struct Contributor {
     pub role: Role,
 }
 
+impl Contributor {
+    /// Checks that the optional `path` is a URL and the optional `email` looks like an address.
+    pub fn validate(&self) -> Result<(), PackageError> {
+        if let Some(path) = &self.path {
+            if url::Url::parse(path).is_err() {
+                return Err(PackageError::MalformedUrl(path.clone()));
+            }
+        }
+
+        if let Some(email) = &self.email {
+            assert_email(email)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks whether a string looks like an email address such as `someone@example.org`.
+///
+/// This is a loose check: a single `@` separating a non-empty local part from a dotted domain,
+/// with no whitespace.
+fn assert_email(email: &str) -> Result<(), PackageError> {
+    let malformed = || PackageError::MalformedEmail(email.into());
+
+    if email.chars().any(char::is_whitespace) {
+        return Err(malformed());
+    }
+
+    let (local, domain) = email.split_once('@').ok_or_else(malformed)?;
+    let labels: Vec<&str> = domain.split('.').collect();
+
+    if local.is_empty() || labels.len() < 2 || labels.iter().any(|label| label.is_empty()) {
+        return Err(malformed());
+    }
+
+    if domain.contains('@') {
+        return Err(malformed());
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
@@ -185,14 +245,52 @@ impl Default for Role {
     }
 }
 
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Author => write!(f, "author"),
+            Self::Contributor => write!(f, "contributor"),
+            Self::Maintainer => write!(f, "maintainer"),
+            Self::Publisher => write!(f, "publisher"),
+            Self::Wrangler => write!(f, "wrangler"),
+        }
+    }
+}
+
+fn list_violations(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("  {}", violation))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[derive(Debug, Error)]
 pub enum PackageError {
     #[error("Name `{0}` is invalid. A name must only contain lowercase, `.`, `_`, `-`.")]
     MalformedName(String),
     #[error("Profile `{0}` is unknown.")]
     UnknownProfile(String),
+    #[error("Id `{0}` is not a valid UUID.")]
+    MalformedId(String),
     #[error("Field `{0}` is required.")]
     RequiredField(String),
+    #[error("Resource `{0}` is defined more than once.")]
+    DuplicateResource(String),
+    #[error("Resource `{0}` must have either a `path` or inline `data`.")]
+    AmbiguousSource(String),
+    #[error("Resource `{resource}` path `{path}` must be relative and stay within the package.")]
+    UnsafePath { resource: String, path: String },
+    #[error("Unknown fields: {}.", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    #[error("Contributor path `{0}` is not a valid URL.")]
+    MalformedUrl(String),
+    #[error("Contributor email `{0}` is invalid.")]
+    MalformedEmail(String),
+    #[error("Schema is invalid: {0}")]
+    InvalidSchema(String),
+    #[error("Descriptor does not conform to the schema:\n{}", list_violations(.0))]
+    SchemaViolations(Vec<Violation>),
     #[error(transparent)]
     JsonError(#[from] serde_json::Error),
     #[error(transparent)]
@@ -203,12 +301,73 @@ pub enum PackageError {
 mod tests {
     use super::*;
 
+    mod contributor {
+        use super::*;
+
+        fn contributor(path: Option<&str>, email: Option<&str>) -> Contributor {
+            Contributor {
+                title: "Arnau Siches".into(),
+                path: path.map(Into::into),
+                email: email.map(Into::into),
+                organization: None,
+                role: Role::Author,
+            }
+        }
+
+        #[test]
+        fn valid_contributors() {
+            assert!(contributor(None, None).validate().is_ok());
+            assert!(
+                contributor(Some("https://www.seachess.net/"), Some("a@seachess.net"))
+                    .validate()
+                    .is_ok()
+            );
+        }
+
+        #[test]
+        fn invalid_path() {
+            let actual = contributor(Some("www.seachess.net"), None).validate();
+
+            assert!(matches!(actual, Err(PackageError::MalformedUrl(_))));
+        }
+
+        #[test]
+        fn invalid_email() {
+            for email in [
+                "seachess.net",
+                "a@seachess",
+                "@seachess.net",
+                "a b@seachess.net",
+            ] {
+                let actual = contributor(None, Some(email)).validate();
+
+                assert!(
+                    matches!(actual, Err(PackageError::MalformedEmail(_))),
+                    "Expect `{}` to be rejected",
+                    email
+                );
+            }
+        }
+    }
+
     mod name {
         use super::*;
+        use std::collections::HashMap;
 
         #[test]
         fn assert_valid_name() {
             assert!(assert_name("foo-bar").is_ok(), "Expect name to be valid");
         }
+
+        #[test]
+        fn lookup_by_str() {
+            let mut resources = HashMap::new();
+            resources.insert(Name::new("thing"), 1);
+            resources.insert(Name::new("tag"), 2);
+
+            assert_eq!(resources.get("thing"), Some(&1));
+            assert_eq!(resources.get("thing_tag"), None);
+            assert!(Name::new("tag") == *"tag");
+        }
     }
 }
@@ -51,6 +51,7 @@
 
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
@@ -60,6 +61,7 @@ use uuid::Uuid;
 
 pub mod core;
 pub mod resource;
+pub mod schema;
 
 use self::core::*;
 use self::resource::Resource;
@@ -70,7 +72,7 @@ pub const DATA_PATH: &str = "data/";
 /// Represents a Tabular Data Package.
 ///
 /// Use either the [`PackageBuilder`] or `serde_json::from_str` to create a new `Package`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
     pub profile: PackageProfile,
     pub id: Identifier,
@@ -78,6 +80,9 @@ pub struct Package {
     pub title: String,
     pub description: String,
     pub created: DateTime<Utc>,
+    /// When the descriptor was last saved after its creation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
     pub resources: Vec<Resource>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub licenses: Vec<Licence>,
@@ -110,8 +115,74 @@ impl Package {
         &self.licenses
     }
 
+    pub fn homepage(&self) -> Option<&Url> {
+        self.homepage.as_ref()
+    }
+
+    pub fn contributors(&self) -> &[Contributor] {
+        &self.contributors
+    }
+
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    pub fn modified(&self) -> Option<&DateTime<Utc>> {
+        self.modified.as_ref()
+    }
+
     pub fn from_reader<R: Read>(rdr: R) -> Result<Self, PackageError> {
-        Ok(serde_json::from_reader(rdr)?)
+        let package: Self = serde_json::from_reader(rdr)?;
+        package.validate()?;
+
+        Ok(package)
+    }
+
+    /// Parses a descriptor rejecting any key unknown to Some, such as a misspelled `titel`.
+    pub fn from_str_strict(s: &str) -> Result<Self, PackageError> {
+        let value: Value = serde_json::from_str(s)?;
+        let unknown = unknown_keys(&value);
+
+        if !unknown.is_empty() {
+            return Err(PackageError::UnknownFields(unknown));
+        }
+
+        let package: Self = serde_json::from_value(value)?;
+        package.validate()?;
+
+        Ok(package)
+    }
+
+    /// Checks the values serde can't, such as contributor URLs and emails.
+    fn validate(&self) -> Result<(), PackageError> {
+        assert_valid_resources(&self.resources)?;
+
+        for contributor in &self.contributors {
+            contributor.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the descriptor into the package `root`, stamping it as modified now.
+    pub fn save<P: AsRef<Path>>(&mut self, root: P) -> Result<(), PackageError> {
+        self.modified = Some(Utc::now());
+
+        std::fs::write(
+            root.as_ref().join(DESCRIPTOR_PATH),
+            self.to_canonical_string()?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Serialises the descriptor in its canonical form: keys in declaration order, two-space
+    /// indentation and a trailing newline.
+    pub fn to_canonical_string(&self) -> Result<String, PackageError> {
+        let mut descriptor = serde_json::to_string_pretty(self)?;
+        descriptor.push('\n');
+
+        Ok(descriptor)
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, PackageError> {
@@ -123,6 +194,114 @@ impl Package {
     }
 }
 
+const PACKAGE_KEYS: &[&str] = &[
+    "profile",
+    "id",
+    "name",
+    "title",
+    "description",
+    "created",
+    "modified",
+    "resources",
+    "licenses",
+    "homepage",
+    "contributors",
+    "keywords",
+];
+const LICENCE_KEYS: &[&str] = &["name", "path", "title"];
+const CONTRIBUTOR_KEYS: &[&str] = &["title", "path", "email", "organization", "role"];
+const RESOURCE_KEYS: &[&str] = &[
+    "profile",
+    "name",
+    "title",
+    "description",
+    "path",
+    "data",
+    "encoding",
+    "bytes",
+    "hash",
+    "schema",
+];
+const SCHEMA_KEYS: &[&str] = &["fields", "primaryKey", "foreignKeys"];
+const FIELD_KEYS: &[&str] = &["name", "description", "type", "format", "constraints"];
+const CONSTRAINT_KEYS: &[&str] = &["required", "unique"];
+const FOREIGN_KEY_KEYS: &[&str] = &["fields", "reference"];
+const REFERENCE_KEYS: &[&str] = &["resource", "fields"];
+
+/// Lists the keys in a descriptor that are not part of the Some flavour of the specification.
+///
+/// Nested keys are reported with their location, e.g. `resources[0].titel`.
+fn unknown_keys(value: &Value) -> Vec<String> {
+    let mut found = Vec::new();
+
+    check_keys(value, "", PACKAGE_KEYS, &mut found);
+
+    for (idx, licence) in entries(value, "licenses") {
+        let prefix = format!("licenses[{}]", idx);
+        check_keys(licence, &prefix, LICENCE_KEYS, &mut found);
+    }
+
+    for (idx, contributor) in entries(value, "contributors") {
+        let prefix = format!("contributors[{}]", idx);
+        check_keys(contributor, &prefix, CONTRIBUTOR_KEYS, &mut found);
+    }
+
+    for (idx, resource) in entries(value, "resources") {
+        let prefix = format!("resources[{}]", idx);
+        check_keys(resource, &prefix, RESOURCE_KEYS, &mut found);
+
+        if let Some(schema) = resource.get("schema") {
+            let prefix = format!("{}.schema", prefix);
+            check_keys(schema, &prefix, SCHEMA_KEYS, &mut found);
+
+            for (idx, field) in entries(schema, "fields") {
+                let prefix = format!("{}.fields[{}]", prefix, idx);
+                check_keys(field, &prefix, FIELD_KEYS, &mut found);
+
+                for (idx, constraint) in entries(field, "constraints") {
+                    let prefix = format!("{}.constraints[{}]", prefix, idx);
+                    check_keys(constraint, &prefix, CONSTRAINT_KEYS, &mut found);
+                }
+            }
+
+            for (idx, foreign_key) in entries(schema, "foreignKeys") {
+                let prefix = format!("{}.foreignKeys[{}]", prefix, idx);
+                check_keys(foreign_key, &prefix, FOREIGN_KEY_KEYS, &mut found);
+
+                if let Some(reference) = foreign_key.get("reference") {
+                    let prefix = format!("{}.reference", prefix);
+                    check_keys(reference, &prefix, REFERENCE_KEYS, &mut found);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+fn check_keys(value: &Value, prefix: &str, known: &[&str], found: &mut Vec<String>) {
+    if let Some(object) = value.as_object() {
+        for key in object.keys() {
+            if !known.contains(&key.as_str()) {
+                if prefix.is_empty() {
+                    found.push(key.clone());
+                } else {
+                    found.push(format!("{}.{}", prefix, key));
+                }
+            }
+        }
+    }
+}
+
+fn entries<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = (usize, &'a Value)> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+}
+
 /// The main way to build a `Package`.
 ///
 /// ## Examples
@@ -267,9 +446,15 @@ impl PackageBuilder {
             return Err(PackageError::RequiredField("resources".into()));
         };
 
+        assert_valid_resources(&self.resources)?;
+
+        for contributor in &self.contributors {
+            contributor.validate()?;
+        }
+
         Ok(Package {
             profile: Profile::new("tabular-data-package"),
-            id: self.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            id: self.id.unwrap_or_else(generate_id),
             name,
             title,
             description,
@@ -277,194 +462,168 @@ impl PackageBuilder {
             homepage: self.homepage,
             resources: self.resources,
             created: self.created.unwrap_or_else(|| Utc::now()),
+            modified: None,
             contributors: self.contributors,
             keywords: self.keywords,
         })
     }
 }
 
+/// Rejects resources sharing a name, which would collide as store tables, and resources whose
+/// path points outside the package, which would have commands read, overwrite or delete files
+/// elsewhere.
+fn assert_valid_resources(resources: &[Resource]) -> Result<(), PackageError> {
+    let mut seen: Vec<&Name> = Vec::new();
+
+    for resource in resources {
+        if !resource.is_contained() {
+            return Err(PackageError::UnsafePath {
+                resource: resource.id().to_string(),
+                path: resource.path().display().to_string(),
+            });
+        }
+
+        if seen.contains(&resource.id()) {
+            return Err(PackageError::DuplicateResource(resource.id().to_string()));
+        }
+
+        if resource.is_inline() == resource.has_path() {
+            return Err(PackageError::AmbiguousSource(resource.id().to_string()));
+        }
+
+        seen.push(resource.id());
+    }
+
+    Ok(())
+}
+
+/// Generates a random package id.
+fn generate_id() -> Identifier {
+    Uuid::new_v4().to_string()
+}
+
+/// A name-based (v5) UUID derived from the given seed, the same seed always yielding the same id.
+pub fn seeded_id(seed: &str) -> Identifier {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, seed.as_bytes()).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::canonical;
 
-    fn canonical() -> String {
-        r#"{
-          "profile": "tabular-data-package",
-          "id": "00000000-0000-0000-0000-000000000000",
-          "name": "some-nothingness",
-          "title": "Some Nothingness",
-          "description": "Nothing",
-          "licenses": [{
-            "name": "ODC-PDDL",
-            "path": "http://opendatacommons.org/licenses/pddl/",
-            "title": "Open Data Commons Public Domain Dedication and License"
-          }],
-          "homepage": "https://github.com/arnau/some-nothingness",
-          "created": "2020-12-29T10:11:12Z",
-          "contributors": [{
-            "title": "Arnau Siches",
-            "path": "https://www.seachess.net/",
-            "role": "author"
-          }],
-          "keywords": [
-            "sqlite",
-            "some-thing"
-          ],
-          "resources": [
-            {
-              "profile": "tabular-data-resource",
-              "name": "thing",
-              "title": "Thing",
-              "description": "The set of things for the collection.",
-              "path": "data/thing.csv",
-              "encoding": "UTF-8",
-              "schema": {
-                "fields": [
-                  {
-                    "name": "url",
-                    "description": "The URL of the thing.",
-                    "type": "string",
-                    "format": "uri",
-                    "constraints": [{
-                      "required": true,
-                      "unique": true
-                    }]
-                  },
-                  {
-                    "name": "name",
-                    "description": "The name of the thing.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": true,
-                      "unique": true
-                    }]
-                  },
-                  {
-                    "name": "summary",
-                    "description": "The description of the thing.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": false,
-                      "unique": false
-                    }]
-                  },
-                  {
-                    "name": "category_id",
-                    "description": "The category of the thing.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": true,
-                      "unique": false
-                    }]
-                  }
-                ],
-                "primaryKey": ["url"],
-                "foreignKeys": [{
-                  "fields": ["category_id"],
-                  "reference": {
-                    "resource": "tag",
-                    "fields": ["id"]
-                  }
-                }]
-              }
-            },
-            {
-              "profile": "tabular-data-resource",
-              "name": "tag",
-              "title": "Tag",
-              "description": "The set of tags to classify the collection of things.",
-              "path": "data/tag.csv",
-              "encoding": "UTF-8",
-              "schema": {
-                "fields": [
-                  {
-                    "name": "id",
-                    "description": "The tag identifier.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": true,
-                      "unique": true
-                    }]
-                  },
-                  {
-                    "name": "name",
-                    "description": "The tag name.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": true,
-                      "unique": true
-                    }]
-                  },
-                  {
-                    "name": "summary",
-                    "description": "The tag description.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": false,
-                      "unique": false
-                    }]
-                  }
-                ],
-                "primaryKey": ["id"]
-              }
-            },
-            {
-              "profile": "tabular-data-resource",
-              "name": "thing_tag",
-              "title": "Thing tags",
-              "description": "The set of tags to further classify the collection of things.",
-              "path": "data/thing_tag.csv",
-              "encoding": "UTF-8",
-              "schema": {
-                "fields": [
-                  {
-                    "name": "thing_id",
-                    "description": "The reference to a thing.",
-                    "type": "string",
-                    "format": "uri",
-                    "constraints": [{
-                      "required": true,
-                      "unique": false
-                    }]
-                  },
-                  {
-                    "name": "tag_id",
-                    "description": "The reference to a tag.",
-                    "type": "string",
-                    "constraints": [{
-                      "required": true,
-                      "unique": false
-                    }]
-                  }
-                ],
-                "primaryKey": ["thing_id", "tag_id"],
-                "foreignKeys": [{
-                  "fields": ["thing_id"],
-                  "reference": {
-                    "resource": "thing",
-                    "fields": ["url"]
-                  }
-                }, {
-                "fields": ["tag_id"],
-                "reference": {
-                  "resource": "tag",
-                  "fields": ["id"]
-                }
-                }]
-              }
+    #[test]
+    fn deserialise() {
+        let raw = canonical();
+        let actual: Result<Package, _> = serde_json::from_str(&raw);
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn strict_rejects_unknown_keys() {
+        let raw = canonical()
+            .replace(r#""homepage""#, r#""homepag""#)
+            .replacen(r#""encoding""#, r#""encodign""#, 1);
+        let actual = Package::from_str_strict(&raw);
+
+        match actual {
+            Err(PackageError::UnknownFields(keys)) => {
+                assert_eq!(keys, vec!["homepag", "resources[0].encodign"]);
             }
-          ]
-        }"#
-        .to_string()
+            _ => panic!("expected unknown fields"),
+        }
     }
 
     #[test]
-    fn deserialise() {
-        let raw = canonical();
+    fn lenient_ignores_unknown_keys() {
+        let raw = canonical().replace(r#""homepage""#, r#""homepag""#);
         let actual: Result<Package, _> = serde_json::from_str(&raw);
+
         assert!(actual.is_ok());
     }
 
+    #[test]
+    fn rejects_invalid_contributor() {
+        let valid = canonical().replace(
+            r#""role": "author""#,
+            r#""email": "arnau@seachess.net", "role": "author""#,
+        );
+        let invalid = canonical().replace(
+            r#""role": "author""#,
+            r#""email": "arnau at seachess", "role": "author""#,
+        );
+
+        assert!(Package::from_reader(valid.as_bytes()).is_ok());
+        assert!(matches!(
+            Package::from_reader(invalid.as_bytes()),
+            Err(PackageError::MalformedEmail(_))
+        ));
+        assert!(matches!(
+            Package::from_str_strict(&invalid),
+            Err(PackageError::MalformedEmail(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicate_resources() {
+        let mut resources = crate::lenses::package::resources();
+        resources.push(resources[0].clone());
+
+        let actual = PackageBuilder::new("some-test")
+            .expect("a valid name")
+            .title("Some Test")
+            .description("A test collection.")
+            .resources(resources)
+            .build();
+
+        match actual {
+            Err(PackageError::DuplicateResource(name)) => assert_eq!(name, "thing"),
+            _ => panic!("expected a duplicate resource"),
+        }
+
+        let raw = canonical().replacen(r#""name": "thing""#, r#""name": "tag""#, 1);
+
+        assert!(matches!(
+            Package::from_reader(raw.as_bytes()),
+            Err(PackageError::DuplicateResource(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_package() {
+        for path in ["/etc/passwd", "../secret.csv", "data/../../secret.csv"] {
+            let raw = canonical().replacen(
+                r#""path": "data/thing.csv""#,
+                &format!(r#""path": "{}""#, path),
+                1,
+            );
+
+            assert!(
+                matches!(
+                    Package::from_reader(raw.as_bytes()),
+                    Err(PackageError::UnsafePath { .. })
+                ),
+                "{} should be rejected",
+                path
+            );
+        }
+
+        let raw = canonical().replacen(
+            r#""path": "data/thing.csv""#,
+            r#""path": "./data/../data/thing.csv""#,
+            1,
+        );
+
+        assert!(Package::from_reader(raw.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn seeded_ids_are_stable() {
+        assert_eq!(seeded_id("golden"), seeded_id("golden"));
+        assert_ne!(seeded_id("golden"), seeded_id("silver"));
+        assert!(Uuid::parse_str(&seeded_id("golden")).is_ok());
+    }
+
     #[test]
     fn fullround() -> Result<(), Box<dyn std::error::Error>> {
         let raw = canonical();
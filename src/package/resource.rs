@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
 use super::core::{Name, Profile, ResourceProfile};
 
@@ -10,14 +14,28 @@ pub struct Resource {
     pub name: Name,
     pub title: String,
     pub description: String,
+    /// The resource file. Empty when the rows are inline.
+    #[serde(default, skip_serializing_if = "is_unset")]
     pub path: PathBuf,
+    /// The rows embedded in the descriptor instead of a file, keyed by field name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<InlineRow>>,
     pub encoding: Encoding,
-    // pub bytes: u64,
-    // pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
     pub schema: Schema,
     // dialect: Dialect,
 }
 
+/// A row of inline data.
+pub type InlineRow = Map<String, Value>;
+
+fn is_unset(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+}
+
 impl Resource {
     pub fn id(&self) -> &Name {
         &self.name
@@ -27,6 +45,66 @@ impl Resource {
         &self.path
     }
 
+    pub fn has_path(&self) -> bool {
+        !is_unset(&self.path)
+    }
+
+    /// Whether the rows are embedded in the descriptor instead of a file.
+    pub fn is_inline(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// Embeds the given rows in the descriptor, leaving the resource file behind.
+    ///
+    /// The fingerprint is dropped as there is no file left to describe.
+    pub fn inline(&mut self, rows: Vec<InlineRow>) {
+        self.path = PathBuf::new();
+        self.data = Some(rows);
+        self.bytes = None;
+        self.hash = None;
+    }
+
+    /// The location of the resource file resolved against the package `root`.
+    ///
+    /// Descriptors are only loaded when every path stays within the package, see
+    /// [`Resource::is_contained`].
+    pub fn absolute_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.path)
+    }
+
+    /// Whether the path is relative and never steps outside the package, e.g. `data/thing.csv`
+    /// but not `/etc/passwd` nor `data/../../secret`.
+    pub fn is_contained(&self) -> bool {
+        let mut depth: usize = 0;
+
+        for component in self.path.components() {
+            match component {
+                Component::Normal(_) => depth += 1,
+                Component::CurDir => {}
+                Component::ParentDir => match depth.checked_sub(1) {
+                    Some(value) => depth = value,
+                    None => return false,
+                },
+                Component::RootDir | Component::Prefix(_) => return false,
+            }
+        }
+
+        true
+    }
+
+    /// The recorded size and hash of the resource file, when both are present.
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        Some(Fingerprint {
+            bytes: self.bytes?,
+            hash: self.hash.clone()?,
+        })
+    }
+
+    pub fn set_fingerprint(&mut self, value: Fingerprint) {
+        self.bytes = Some(value.bytes);
+        self.hash = Some(value.hash);
+    }
+
     pub fn field_names(&self) -> Vec<Name> {
         self.schema
             .fields
@@ -36,7 +114,30 @@ impl Resource {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The size and SHA-256 hash of a resource file.
+///
+/// The hash is prefixed with its algorithm as the [Data Resource] specification requires for
+/// anything but MD5.
+///
+/// [Data Resource]: https://specs.frictionlessdata.io/data-resource/#optional-properties
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    pub bytes: u64,
+    pub hash: String,
+}
+
+impl Fingerprint {
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+
+        Ok(Self {
+            bytes: content.len() as u64,
+            hash: format!("sha256:{:x}", Sha256::digest(&content)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Schema {
     pub fields: Vec<Field>,
     #[serde(rename = "primaryKey")]
@@ -45,7 +146,7 @@ pub struct Schema {
     pub foreign_keys: Vec<ForeignKey>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Field {
     pub name: Name,
     pub description: String,
@@ -56,24 +157,165 @@ pub struct Field {
     pub constraints: Vec<Constraint>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Field {
+    /// Whether any of the field constraints requires a value.
+    pub fn is_required(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|constraint| constraint.required)
+    }
+
+    /// Whether any of the field constraints requires unique values.
+    pub fn is_unique(&self) -> bool {
+        self.constraints.iter().any(|constraint| constraint.unique)
+    }
+}
+
+/// The constraints for a field.
+///
+/// Descriptors store them as a list but Some always produces a single entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Constraint {
     pub required: bool,
     pub unique: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Constraint {
+    pub fn new(required: bool, unique: bool) -> Self {
+        Self { required, unique }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ForeignKey {
     pub fields: Vec<Name>,
     pub reference: Reference,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Reference {
     pub resource: Name,
     pub fields: Vec<Name>,
 }
 
+/// Builds a [`Schema`] field by field.
+///
+/// ```
+/// use some::package::resource::{FieldBuilder, SchemaBuilder};
+///
+/// let schema = SchemaBuilder::new()
+///     .field(FieldBuilder::new("url").format("uri").required().unique())
+///     .field(FieldBuilder::new("category_id").required())
+///     .primary_key(&["url"])
+///     .foreign_key(&["category_id"], "tag", &["id"])
+///     .build();
+///
+/// assert_eq!(schema.fields.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct SchemaBuilder {
+    fields: Vec<Field>,
+    primary_key: Vec<Name>,
+    foreign_keys: Vec<ForeignKey>,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field.build());
+        self
+    }
+
+    pub fn primary_key(mut self, fields: &[&str]) -> Self {
+        self.primary_key = names(fields);
+        self
+    }
+
+    /// Adds a foreign key from the given `fields` to the `fields` of the `resource`.
+    pub fn foreign_key(mut self, fields: &[&str], resource: &str, reference: &[&str]) -> Self {
+        self.foreign_keys.push(ForeignKey {
+            fields: names(fields),
+            reference: Reference {
+                resource: Name::new(resource),
+                fields: names(reference),
+            },
+        });
+        self
+    }
+
+    pub fn build(self) -> Schema {
+        Schema {
+            fields: self.fields,
+            primary_key: self.primary_key,
+            foreign_keys: self.foreign_keys,
+        }
+    }
+}
+
+/// Builds a [`Field`]. Fields are optional, non-unique strings unless told otherwise.
+#[derive(Debug)]
+pub struct FieldBuilder {
+    name: Name,
+    description: String,
+    datatype: String,
+    format: Option<String>,
+    constraint: Constraint,
+}
+
+impl FieldBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: Name::new(name),
+            description: String::new(),
+            datatype: "string".into(),
+            format: None,
+            constraint: Constraint::new(false, false),
+        }
+    }
+
+    pub fn description<S: Into<String>>(mut self, value: S) -> Self {
+        self.description = value.into();
+        self
+    }
+
+    pub fn datatype<S: Into<String>>(mut self, value: S) -> Self {
+        self.datatype = value.into();
+        self
+    }
+
+    pub fn format<S: Into<String>>(mut self, value: S) -> Self {
+        self.format = Some(value.into());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.constraint.required = true;
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.constraint.unique = true;
+        self
+    }
+
+    pub fn build(self) -> Field {
+        Field {
+            name: self.name,
+            description: self.description,
+            datatype: self.datatype,
+            format: self.format,
+            constraints: vec![self.constraint],
+        }
+    }
+}
+
+fn names(values: &[&str]) -> Vec<Name> {
+    values.iter().map(|value| Name::new(*value)).collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Encoding {
     #[serde(rename = "UTF-8")]
@@ -104,35 +346,36 @@ impl ResourceBuilder {
             title: self.title.expect("to be present."),
             description: self.description.expect("to be present."),
             path: self.path.expect("to be present.").into(),
+            data: None,
             encoding: Encoding::Utf8,
-            // bytes: u64,
-            // hash: String,
+            bytes: None,
+            hash: None,
             schema: self.schema.expect("to be present."),
             // dialect: Dialect,
         }
     }
 
-    pub fn with_name(&mut self, value: Name) -> &Self {
+    pub fn with_name(mut self, value: Name) -> Self {
         self.name = Some(value);
         self
     }
 
-    pub fn with_title<S: Into<String>>(&mut self, value: S) -> &Self {
+    pub fn with_title<S: Into<String>>(mut self, value: S) -> Self {
         self.title = Some(value.into());
         self
     }
 
-    pub fn with_description<S: Into<String>>(&mut self, value: S) -> &Self {
+    pub fn with_description<S: Into<String>>(mut self, value: S) -> Self {
         self.description = Some(value.into());
         self
     }
 
-    pub fn with_path<S: Into<String>>(&mut self, value: S) -> &Self {
+    pub fn with_path<S: Into<String>>(mut self, value: S) -> Self {
         self.path = Some(value.into());
         self
     }
 
-    pub fn with_schema(&mut self, value: Schema) -> &Self {
+    pub fn with_schema(mut self, value: Schema) -> Self {
         self.schema = Some(value);
         self
     }
@@ -153,3 +396,146 @@ impl ResourceBuilder {
 //         "quoteChar": "\"",
 //         "skipInitialSpace": false
 //       },
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(constraints: Vec<Constraint>) -> Field {
+        Field {
+            name: Name::new("url"),
+            description: "The URL of the thing.".into(),
+            datatype: "string".into(),
+            format: None,
+            constraints,
+        }
+    }
+
+    #[test]
+    fn single_constraint() {
+        let field = field(vec![Constraint::new(true, false)]);
+
+        assert!(field.is_required());
+        assert!(!field.is_unique());
+    }
+
+    #[test]
+    fn many_constraints() {
+        let field = field(vec![
+            Constraint::new(false, false),
+            Constraint::new(true, false),
+            Constraint::new(false, true),
+        ]);
+
+        assert!(field.is_required());
+        assert!(field.is_unique());
+    }
+
+    #[test]
+    fn schema_builder_matches_literal() {
+        let expected = Schema {
+            fields: vec![
+                Field {
+                    name: Name::new("url"),
+                    description: "The URL of the thing.".into(),
+                    datatype: "string".into(),
+                    format: Some("uri".into()),
+                    constraints: vec![Constraint {
+                        required: true,
+                        unique: true,
+                    }],
+                },
+                Field {
+                    name: Name::new("summary"),
+                    description: "The description of the thing.".into(),
+                    datatype: "string".into(),
+                    format: None,
+                    constraints: vec![Constraint {
+                        required: false,
+                        unique: false,
+                    }],
+                },
+                Field {
+                    name: Name::new("category_id"),
+                    description: "The category of the thing.".into(),
+                    datatype: "string".into(),
+                    format: None,
+                    constraints: vec![Constraint {
+                        required: true,
+                        unique: false,
+                    }],
+                },
+            ],
+            primary_key: vec![Name::new("url")],
+            foreign_keys: vec![ForeignKey {
+                fields: vec![Name::new("category_id")],
+                reference: Reference {
+                    resource: Name::new("tag"),
+                    fields: vec![Name::new("id")],
+                },
+            }],
+        };
+
+        let actual = SchemaBuilder::new()
+            .field(
+                FieldBuilder::new("url")
+                    .description("The URL of the thing.")
+                    .format("uri")
+                    .required()
+                    .unique(),
+            )
+            .field(FieldBuilder::new("summary").description("The description of the thing."))
+            .field(
+                FieldBuilder::new("category_id")
+                    .description("The category of the thing.")
+                    .required(),
+            )
+            .primary_key(&["url"])
+            .foreign_key(&["category_id"], "tag", &["id"])
+            .build();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn relative_path_within_root() {
+        let resource = crate::lenses::package::tag_resource();
+        let actual = resource.absolute_path(Path::new("/tmp/collection"));
+
+        assert_eq!(actual, PathBuf::from("/tmp/collection/data/tag.csv"));
+    }
+
+    #[test]
+    fn absolute_path_not_contained() {
+        let mut resource = crate::lenses::package::tag_resource();
+        resource.path = PathBuf::from("/srv/shared/tag.csv");
+
+        assert!(!resource.is_contained());
+    }
+
+    #[test]
+    fn resource_builder_chains() -> Result<(), serde_json::Error> {
+        let schema = SchemaBuilder::new()
+            .field(FieldBuilder::new("id").required())
+            .primary_key(&["id"])
+            .build();
+        let resource = ResourceBuilder::new()
+            .with_name(Name::new("tag"))
+            .with_title("Tag")
+            .with_description("The set of tags.")
+            .with_path("data/tag.csv")
+            .with_schema(schema.clone())
+            .build();
+
+        let json = serde_json::to_string(&resource)?;
+        let actual: Resource = serde_json::from_str(&json)?;
+
+        assert_eq!(actual.id(), "tag");
+        assert_eq!(actual.title, "Tag");
+        assert_eq!(actual.description, "The set of tags.");
+        assert_eq!(actual.path, PathBuf::from("data/tag.csv"));
+        assert_eq!(actual.schema, schema);
+
+        Ok(())
+    }
+}
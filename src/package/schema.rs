@@ -0,0 +1,103 @@
+//! Validation of a package descriptor against an external JSON Schema such as the official
+//! [Frictionless] one.
+//!
+//! This is complementary to the checks done when reading a [`Package`][super::Package]: a
+//! descriptor can be a valid Some package and still fail to conform to a stricter profile.
+//!
+//! [Frictionless]: https://specs.frictionlessdata.io/schemas/data-package.json
+
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fmt;
+
+use super::core::PackageError;
+
+/// A single schema violation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The JSON pointer to the offending value in the descriptor.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// Validates the given descriptor against the given JSON Schema, collecting every violation.
+pub fn validate(descriptor: &Value, schema: &Value) -> Result<(), PackageError> {
+    let compiled =
+        JSONSchema::compile(schema).map_err(|err| PackageError::InvalidSchema(err.to_string()))?;
+
+    if let Err(errors) = compiled.validate(descriptor) {
+        let violations = errors
+            .map(|err| Violation {
+                pointer: err.instance_path.to_string(),
+                message: err.to_string(),
+            })
+            .collect();
+
+        return Err(PackageError::SchemaViolations(violations));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use serde_json::json;
+
+    fn minimal_schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "required": ["name", "resources"],
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z0-9._-]+$" },
+                "resources": { "type": "array", "minItems": 1 }
+            }
+        })
+    }
+
+    #[test]
+    fn canonical_conforms() {
+        let descriptor: Value = serde_json::from_str(&fixtures::canonical()).unwrap();
+
+        assert!(validate(&descriptor, &minimal_schema()).is_ok());
+    }
+
+    #[test]
+    fn violations_point_at_the_value() {
+        let descriptor = json!({ "name": "Not Valid", "resources": [] });
+
+        match validate(&descriptor, &minimal_schema()) {
+            Err(PackageError::SchemaViolations(violations)) => {
+                let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+
+                assert_eq!(pointers, vec!["/name", "/resources"]);
+            }
+            other => panic!("expected schema violations, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_schema() {
+        let descriptor = json!({});
+        let schema = json!({ "type": 42 });
+
+        assert!(matches!(
+            validate(&descriptor, &schema),
+            Err(PackageError::InvalidSchema(_))
+        ));
+    }
+}
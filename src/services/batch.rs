@@ -0,0 +1,121 @@
+//! Support for batch operations that can be interrupted with `CTRL-C`.
+//!
+//! An interrupted batch always finishes the item in progress so callers can rely on each item
+//! being either fully processed or not processed at all.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::Result;
+
+/// A flag signalling that the user asked to stop.
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a flag raised when the process receives `CTRL-C`.
+    ///
+    /// Only one handler can be installed per process.
+    pub fn install() -> Result<Self> {
+        let interrupt = Self::new();
+        let flag = interrupt.clone();
+
+        ctrlc::set_handler(move || flag.raise())?;
+
+        Ok(interrupt)
+    }
+
+    pub fn raise(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_raised(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// The outcome of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn is_complete(&self) -> bool {
+        self.processed == self.total
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_complete() {
+            write!(f, "{} processed", self.processed)
+        } else {
+            write!(
+                f,
+                "interrupted; {} of {} processed",
+                self.processed, self.total
+            )
+        }
+    }
+}
+
+/// Applies `f` to every item until all are processed or the interrupt is raised.
+///
+/// Stops at the first error returned by `f`.
+pub fn run<I, T, F>(items: I, interrupt: &Interrupt, mut f: F) -> Result<Progress>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: ExactSizeIterator,
+    F: FnMut(T) -> Result<()>,
+{
+    let items = items.into_iter();
+    let mut progress = Progress {
+        processed: 0,
+        total: items.len(),
+    };
+
+    for item in items {
+        if interrupt.is_raised() {
+            break;
+        }
+
+        f(item)?;
+        progress.processed += 1;
+    }
+
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupt_stops_early() -> Result<()> {
+        let interrupt = Interrupt::new();
+        let mut seen = Vec::new();
+
+        let progress = run(vec![1, 2, 3, 4, 5], &interrupt, |item| {
+            seen.push(item);
+
+            if item == 2 {
+                interrupt.raise();
+            }
+
+            Ok(())
+        })?;
+
+        assert_eq!(seen, vec![1, 2]);
+        assert!(!progress.is_complete());
+        assert_eq!(progress.to_string(), "interrupted; 2 of 5 processed");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! Favicon lookups for the things in a collection.
+
+use crate::lenses;
+use crate::services::fetcher::{FetchConfig, Fetcher};
+use crate::services::probe::Probe;
+
+/// Finds the favicon of a page.
+pub trait FaviconFinder {
+    /// Returns the absolute URL of the favicon, if any could be found.
+    fn find(&mut self, url: &str) -> Option<String>;
+}
+
+/// A finder that never finds anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoFavicon;
+
+impl FaviconFinder for NoFavicon {
+    fn find(&mut self, _url: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Finds favicons by scraping each page, as a [`Probe`] remembering the outcome for the rest of
+/// the run.
+#[derive(Debug)]
+pub struct HttpFavicon {
+    fetcher: Fetcher,
+    probe: Probe<String>,
+}
+
+impl HttpFavicon {
+    pub fn new(config: FetchConfig) -> Self {
+        Self {
+            fetcher: Fetcher::new(config),
+            probe: Probe::new(None),
+        }
+    }
+}
+
+impl Default for HttpFavicon {
+    fn default() -> Self {
+        Self::new(FetchConfig::default())
+    }
+}
+
+impl FaviconFinder for HttpFavicon {
+    fn find(&mut self, url: &str) -> Option<String> {
+        let fetcher = &mut self.fetcher;
+
+        self.probe.find(url, |url| {
+            Ok(lenses::thing::scrape_thing(fetcher, url, true)?.favicon)
+        })
+    }
+}
@@ -0,0 +1,331 @@
+//! A polite HTTP fetcher.
+//!
+//! Responses are cached by URL for a while, across runs when given a cache directory, and
+//! consecutive requests to the same host are spaced out by a minimum delay.
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use crate::Result;
+
+/// The minimum time between two requests to the same host unless configured otherwise.
+pub const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+
+/// How long a request can take before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The file in the cache directory keeping the fetched pages.
+const CACHE_FILE: &str = "pages.json";
+
+/// The fetcher settings.
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    /// How long a cached response is reused for.
+    pub ttl: Duration,
+    /// The minimum time between two requests to the same host.
+    pub delay: Duration,
+    /// Whether to cache responses at all.
+    pub cache: bool,
+    /// Where to keep the cached pages across runs. Only kept in memory when absent.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            delay: DEFAULT_DELAY,
+            cache: true,
+            cache_dir: None,
+        }
+    }
+}
+
+/// A fetched web page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page {
+    pub status: u16,
+    pub body: String,
+}
+
+/// A cached page with the time it was fetched, in seconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cached {
+    fetched_at: u64,
+    page: Page,
+}
+
+#[derive(Debug)]
+pub struct Fetcher {
+    config: FetchConfig,
+    client: Client,
+    cache: HashMap<String, Cached>,
+    /// The status of each `HEAD` request, cached apart from the pages as they have no body.
+    statuses: HashMap<Url, (Instant, u16)>,
+    last_request: HashMap<String, Instant>,
+}
+
+impl Fetcher {
+    pub fn new(config: FetchConfig) -> Self {
+        let client = Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        let cache = if config.cache {
+            load_cache(&config)
+        } else {
+            HashMap::new()
+        };
+
+        Self {
+            config,
+            client,
+            cache,
+            statuses: HashMap::new(),
+            last_request: HashMap::new(),
+        }
+    }
+
+    /// Fetches the given URL, reusing a cached response when it is fresh enough.
+    pub fn get(&mut self, url: &Url) -> Result<Page> {
+        if let Some(page) = self.cached(url) {
+            return Ok(page.clone());
+        }
+
+        self.wait_for(url);
+
+        let res = self.client.get(url.clone()).send()?;
+        let page = Page {
+            status: res.status().as_u16(),
+            body: res.text()?,
+        };
+
+        if self.config.cache {
+            self.cache.insert(
+                url.to_string(),
+                Cached {
+                    fetched_at: now(),
+                    page: page.clone(),
+                },
+            );
+            self.persist();
+        }
+
+        Ok(page)
+    }
+
+    /// Sends a `HEAD` request to the given URL, returning the status.
+    ///
+    /// A fresh cached response for the URL, from either method, is reused.
+    pub fn head(&mut self, url: &Url) -> Result<u16> {
+        if let Some(page) = self.cached(url) {
+            return Ok(page.status);
+        }
+
+        if self.config.cache {
+            if let Some((fetched_at, status)) = self.statuses.get(url) {
+                if fetched_at.elapsed() < self.config.ttl {
+                    return Ok(*status);
+                }
+            }
+        }
+
+        self.wait_for(url);
+
+        let status = self.client.head(url.clone()).send()?.status().as_u16();
+
+        if self.config.cache {
+            self.statuses.insert(url.clone(), (Instant::now(), status));
+        }
+
+        Ok(status)
+    }
+
+    /// The cached page for the given URL, when caching and still fresh.
+    fn cached(&self, url: &Url) -> Option<&Page> {
+        if !self.config.cache {
+            return None;
+        }
+
+        self.cache
+            .get(url.as_str())
+            .filter(|cached| is_fresh(cached, self.config.ttl))
+            .map(|cached| &cached.page)
+    }
+
+    /// Writes the fresh pages to the cache directory, if any.
+    ///
+    /// The cache is a convenience so failing to write it is not an error.
+    fn persist(&mut self) {
+        let dir = match &self.config.cache_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let ttl = self.config.ttl;
+
+        self.cache.retain(|_, cached| is_fresh(cached, ttl));
+
+        if let Ok(raw) = serde_json::to_string(&self.cache) {
+            let _ = fs::create_dir_all(dir).and_then(|_| fs::write(dir.join(CACHE_FILE), raw));
+        }
+    }
+
+    /// Sleeps until the host of the given URL can be requested again.
+    fn wait_for(&mut self, url: &Url) {
+        let host = url.host_str().unwrap_or_default().to_string();
+
+        if let Some(last) = self.last_request.get(&host) {
+            let elapsed = last.elapsed();
+
+            if elapsed < self.config.delay {
+                thread::sleep(self.config.delay - elapsed);
+            }
+        }
+
+        self.last_request.insert(host, Instant::now());
+    }
+}
+
+impl Default for Fetcher {
+    fn default() -> Self {
+        Self::new(FetchConfig::default())
+    }
+}
+
+/// Reads the pages cached by previous runs, none when there is no cache directory or it can't be
+/// read.
+fn load_cache(config: &FetchConfig) -> HashMap<String, Cached> {
+    config
+        .cache_dir
+        .as_ref()
+        .and_then(|dir| fs::read_to_string(dir.join(CACHE_FILE)).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn is_fresh(cached: &Cached, ttl: Duration) -> bool {
+    now().saturating_sub(cached.fetched_at) < ttl.as_secs()
+}
+
+/// The current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serves a fixed page, counting the requests received.
+    fn serve() -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("a free port");
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("a connection");
+                let mut request = Vec::new();
+                let mut buffer = [0; 512];
+
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).expect("a request");
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = "<title>Mock</title>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).expect("a response");
+            }
+        });
+
+        (url, hits)
+    }
+
+    fn config(cache: bool) -> FetchConfig {
+        FetchConfig {
+            delay: Duration::ZERO,
+            cache,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cached_within_ttl() -> Result<()> {
+        let (url, hits) = serve();
+        let mut fetcher = Fetcher::new(config(true));
+
+        let first = fetcher.get(&url)?;
+        let second = fetcher.get(&url)?;
+
+        assert_eq!(first, second);
+        assert_eq!(first.body, "<title>Mock</title>");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn head_cached_within_ttl() -> Result<()> {
+        let (url, hits) = serve();
+        let mut fetcher = Fetcher::new(config(true));
+
+        assert_eq!(fetcher.head(&url)?, 200);
+        assert_eq!(fetcher.head(&url)?, 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cached_across_runs() -> Result<()> {
+        let (url, hits) = serve();
+        let dir = tempfile::tempdir()?;
+        let config = FetchConfig {
+            cache_dir: Some(dir.path().to_path_buf()),
+            ..config(true)
+        };
+
+        Fetcher::new(config.clone()).get(&url)?;
+        let page = Fetcher::new(config).get(&url)?;
+
+        assert_eq!(page.body, "<title>Mock</title>");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bypassed_cache() -> Result<()> {
+        let (url, hits) = serve();
+        let mut fetcher = Fetcher::new(config(false));
+
+        fetcher.get(&url)?;
+        fetcher.get(&url)?;
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! The log of every change committed to a package.
+//!
+//! The changelog is emptied on each commit, so the committed events are appended to a JSON Lines
+//! file within the package to keep track of what changed and when across runs.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::entities::change::Event;
+use crate::Result;
+
+/// The location of the history, relative to the package.
+pub const HISTORY_PATH: &str = ".some/history.jsonl";
+
+/// Appends the given events to the history of the package in `root`.
+pub fn append(root: &Path, events: &[Event]) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let path = root.join(HISTORY_PATH);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    for event in events {
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+    }
+
+    Ok(())
+}
+
+/// The committed events recorded after `since` and up to `until`, both as Unix timestamps, in
+/// the order they were committed.
+pub fn between(root: &Path, since: i64, until: i64) -> Result<Vec<Event>> {
+    let path = root.join(HISTORY_PATH);
+
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let mut events = Vec::new();
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: Event = serde_json::from_str(&line)?;
+        let timestamp = event.timestamp as i64;
+
+        if timestamp > since && timestamp <= until {
+            events.push(event);
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::change::{Change, DataRef};
+
+    fn event(timestamp: usize, id: &str) -> Event {
+        Event {
+            timestamp,
+            change: Change::Delete(DataRef::Tag { id: id.into() }),
+        }
+    }
+
+    #[test]
+    fn between_bounds() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        append(dir.path(), &[event(10, "a"), event(20, "b")])?;
+        append(dir.path(), &[event(30, "c")])?;
+
+        let ids: Vec<String> = between(dir.path(), 10, 30)?
+            .iter()
+            .map(|event| event.change.to_string())
+            .collect();
+
+        assert_eq!(ids, vec!["delete tag b", "delete tag c"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Reachability checks for the links in a collection.
+
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use url::Url;
+
+use crate::services::batch::{self, Interrupt, Progress};
+use crate::services::fetcher::{FetchConfig, Fetcher};
+use crate::services::probe::Probe;
+use crate::Result;
+
+/// Tells whether a link is reachable.
+pub trait LinkChecker {
+    /// Returns `None` when reachability can't be told, for example when offline.
+    fn check(&mut self, url: &str) -> Option<bool>;
+}
+
+/// A checker that never checks anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoCheck;
+
+impl LinkChecker for NoCheck {
+    fn check(&mut self, _url: &str) -> Option<bool> {
+        None
+    }
+}
+
+/// The links checked upfront, telling nothing about any other.
+#[derive(Debug, Clone, Default)]
+pub struct Checked(HashMap<String, bool>);
+
+impl LinkChecker for Checked {
+    fn check(&mut self, url: &str) -> Option<bool> {
+        self.0.get(url).copied()
+    }
+}
+
+/// Checks every URL with `checker` until all are checked or the interrupt is raised.
+pub fn check_all<'a, I>(
+    checker: &mut dyn LinkChecker,
+    urls: I,
+    interrupt: &Interrupt,
+) -> Result<(Checked, Progress)>
+where
+    I: IntoIterator<Item = &'a str>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let mut checked = HashMap::new();
+    let progress = batch::run(urls, interrupt, |url| {
+        if let Some(reachable) = checker.check(url) {
+            checked.insert(url.to_string(), reachable);
+        }
+
+        Ok(())
+    })?;
+
+    Ok((Checked(checked), progress))
+}
+
+/// Checks links with a `HEAD` request through a [`Fetcher`], as a [`Probe`] remembering the
+/// outcome for the rest of the run.
+#[derive(Debug)]
+pub struct HttpChecker {
+    fetcher: Fetcher,
+    probe: Probe<bool>,
+}
+
+impl HttpChecker {
+    pub fn new(config: FetchConfig) -> Self {
+        Self {
+            fetcher: Fetcher::new(config),
+            probe: Probe::new(Some(false)),
+        }
+    }
+}
+
+impl Default for HttpChecker {
+    fn default() -> Self {
+        Self::new(FetchConfig::default())
+    }
+}
+
+impl LinkChecker for HttpChecker {
+    fn check(&mut self, url: &str) -> Option<bool> {
+        let fetcher = &mut self.fetcher;
+
+        self.probe.find(url, |url| {
+            let status = fetcher.head(&Url::parse(url)?)?;
+
+            // Some servers refuse `HEAD` even though the page is there.
+            Ok(Some(
+                (200..300).contains(&status) || status == StatusCode::METHOD_NOT_ALLOWED.as_u16(),
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupted_check() -> Result<()> {
+        struct Raising<'a>(&'a Interrupt);
+
+        impl LinkChecker for Raising<'_> {
+            fn check(&mut self, url: &str) -> Option<bool> {
+                if url == "https://b.test/" {
+                    self.0.raise();
+                }
+
+                Some(true)
+            }
+        }
+
+        let interrupt = Interrupt::new();
+        let urls = ["https://a.test/", "https://b.test/", "https://c.test/"];
+        let (mut checked, progress) =
+            check_all(&mut Raising(&interrupt), urls.into_iter(), &interrupt)?;
+
+        assert_eq!(progress.to_string(), "interrupted; 2 of 3 processed");
+        assert_eq!(checked.check("https://b.test/"), Some(true));
+        assert_eq!(checked.check("https://c.test/"), None);
+
+        Ok(())
+    }
+}
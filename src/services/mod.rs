@@ -1 +1,8 @@
+pub mod batch;
+pub mod favicon;
+pub mod fetcher;
+pub mod history;
+pub mod link_check;
+pub mod probe;
 pub mod staging;
+pub mod watch;
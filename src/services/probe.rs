@@ -0,0 +1,88 @@
+//! The bookkeeping shared by the lookups `build` makes for each thing over the network.
+
+use std::collections::HashMap;
+
+use crate::{Result, SomeError};
+
+/// Remembers the outcome of a lookup per URL for the rest of the run.
+///
+/// When the very first lookup can't connect the probe assumes it is offline and stops looking
+/// altogether.
+#[derive(Debug)]
+pub struct Probe<T> {
+    found: HashMap<String, Option<T>>,
+    online: Option<bool>,
+    /// The outcome of a lookup that fails for any other reason.
+    failed: Option<T>,
+}
+
+impl<T: Clone> Probe<T> {
+    pub fn new(failed: Option<T>) -> Self {
+        Self {
+            found: HashMap::new(),
+            online: None,
+            failed,
+        }
+    }
+
+    /// The remembered outcome for `url`, running `lookup` the first time it is asked for.
+    ///
+    /// Always `None` once offline.
+    pub fn find<F>(&mut self, url: &str, lookup: F) -> Option<T>
+    where
+        F: FnOnce(&str) -> Result<Option<T>>,
+    {
+        if self.online == Some(false) {
+            return None;
+        }
+
+        if let Some(outcome) = self.found.get(url) {
+            return outcome.clone();
+        }
+
+        let outcome = match lookup(url) {
+            Ok(outcome) => {
+                self.online = Some(true);
+
+                outcome
+            }
+            Err(SomeError::Fetch(err)) if err.is_connect() && self.online.is_none() => {
+                self.online = Some(false);
+
+                return None;
+            }
+            Err(_) => self.failed.clone(),
+        };
+
+        self.found.insert(url.to_string(), outcome.clone());
+
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_each_url() {
+        let mut probe = Probe::new(Some(false));
+        let mut lookups = 0;
+
+        for _ in 0..2 {
+            let outcome = probe.find("https://up.test/", |_| {
+                lookups += 1;
+                Ok(Some(true))
+            });
+
+            assert_eq!(outcome, Some(true));
+        }
+
+        let outcome = probe.find("https://down.test/", |url| {
+            Err(SomeError::BadUrl(url.to_string()))
+        });
+
+        assert_eq!(outcome, Some(false));
+        assert_eq!(lookups, 1);
+    }
+}
@@ -1,44 +1,302 @@
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use crate::{
     context::Context,
-    entities::change::{Change, Data},
-    store::{ChangeStore, Connection, Repository, TagStore, ThingStore},
+    entities::change::{Change, Data, DataRef},
+    entities::row::{check_header, locate},
     entities::tag::{self, TagError},
     entities::thing::{self, ThingError},
+    entities::thingtag::{Thingtag, ThingtagError},
+    info,
+    package::core::Name,
+    services::history,
+    store::{ChangeStore, Connection, Repository, TagStore, ThingStore, ThingtagStore},
     Result, SomeError,
 };
-use std::{io::Write, ops::Deref};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// How new ids are compared against the existing ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uniqueness {
+    /// Ids must differ byte for byte, the primary keys behaviour.
+    Exact,
+    /// Ids must differ ignoring ASCII case, so `JavaScript` clashes with `javascript`.
+    CaseInsensitive,
+}
+
+impl Default for Uniqueness {
+    fn default() -> Self {
+        Self::Exact
+    }
+}
 
 /// Attempts to insert a new change to the store.
 pub fn add(ctx: &mut Context, data: Data) -> Result<()> {
-    let tx = ctx.tx()?;
+    let uniqueness = ctx.uniqueness();
+    let dry_run = ctx.dry_run();
+
+    ctx.in_transaction(|tx| {
+        assert_data_exists(tx, &data, uniqueness)?;
+
+        // Relations are staged as well so the overlay views see them before a commit.
+        let relations: Vec<Thingtag> = match &data {
+            Data::Thing { url, tags, .. } => tags
+                .iter()
+                .map(|tag_id| Thingtag::new(url.clone(), tag_id.clone()))
+                .collect(),
+            Data::Tag { .. } => Vec::new(),
+        };
+
+        let change = Change::Insert(data);
 
-    assert_data_exists(&tx, &data)?;
+        ChangeStore::add(tx, &change)?;
+
+        for relation in &relations {
+            stage_relation(tx, relation)?;
+        }
+
+        if dry_run {
+            info!("Would stage:", change);
+        }
+
+        Ok(())
+    })
+}
+
+/// Stages a relation between an existing thing and an existing tag.
+pub fn add_relation(ctx: &mut Context, relation: &Thingtag) -> Result<()> {
+    ctx.in_transaction(|tx| stage_relation(tx, relation))
+}
 
-    let change = Change::Insert(data);
+/// Stages a relation, failing when either end is missing.
+///
+/// SQLite can't enforce foreign keys across the overlay views so the check happens here.
+fn stage_relation<C>(conn: &C, relation: &Thingtag) -> Result<()>
+where
+    C: Deref<Target = Connection>,
+{
+    if !assert_thing_exists(conn, &relation.thing_id().to_string())? {
+        return Err(ThingtagError::MissingThing(relation.thing_id().to_string()).into());
+    }
 
-    ChangeStore::add(&tx, &change)?;
+    if !assert_tag_exists(conn, relation.tag_id())? {
+        return Err(ThingtagError::MissingTag(relation.tag_id().clone()).into());
+    }
 
-    tx.commit()?;
+    ThingtagStore::add(&**conn, relation)?;
 
     Ok(())
 }
 
-/// Attempts to consume and commit all events in the changelog.
+/// Records the replacement of an existing thing or tag with the given data.
+pub fn replace(ctx: &mut Context, data: Data) -> Result<()> {
+    let dry_run = ctx.dry_run();
+
+    ctx.in_transaction(|tx| {
+        match &data {
+            Data::Thing { url, .. } => {
+                if !assert_thing_exists(tx, url)? {
+                    return Err(ThingError::NotFound(url.to_string()).into());
+                }
+            }
+            Data::Tag { id, .. } => {
+                if !assert_tag_exists(tx, id)? {
+                    return Err(TagError::NotFound(id.to_string()).into());
+                }
+            }
+        }
+
+        let change = Change::Replace(data);
+
+        ChangeStore::add(tx, &change)?;
+
+        if dry_run {
+            info!("Would stage:", change);
+        }
+
+        Ok(())
+    })
+}
+
+/// Records the replacement of an existing tag, such as a new name or summary.
+pub fn replace_tag(ctx: &mut Context, record: tag::Record) -> Result<()> {
+    let dry_run = ctx.dry_run();
+
+    ctx.in_transaction(|tx| {
+        if !assert_tag_exists(tx, record.id())? {
+            return Err(SomeError::Tag(TagError::NotFound(record.id().to_string())));
+        }
+
+        let change = Change::Replace(Data::Tag {
+            id: record.id().clone(),
+            name: record.name().cloned(),
+            summary: record.summary().cloned(),
+        });
+
+        ChangeStore::add(tx, &change)?;
+
+        if dry_run {
+            info!("Would stage:", change);
+        }
+
+        Ok(())
+    })
+}
+
+/// Records the merge of the `from` tag into the `into` tag.
+///
+/// Every thing referencing `from`, either as category or as tag, is replaced to reference `into`
+/// and `from` is deleted. Returns the number of things affected.
+pub fn merge_tags(ctx: &mut Context, from: &tag::Id, into: &tag::Id) -> Result<usize> {
+    ctx.in_transaction(|tx| {
+        for id in [from, into] {
+            if !assert_tag_exists(tx, id)? {
+                return Err(SomeError::Tag(TagError::NotFound(id.to_string())));
+            }
+        }
+
+        if from == into {
+            return Ok(0);
+        }
+
+        TagStore::merge(tx, from, into)
+    })
+}
+
+/// How the resource files are written on commit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommitMode {
+    /// One resource after the other.
+    Sequential,
+    /// Each resource on its own thread.
+    Concurrent,
+}
+
+/// Attempts to consume and commit all events in the changelog, recording them in the package
+/// history.
+///
+/// Every affected resource is rewritten to a temporary sibling and only moved into place once
+/// every write succeeded. Any failure removes the temporary files and keeps the changelog intact,
+/// the changelog being flushed only after every file is in place. A failure while moving them
+/// reports the resources already replaced.
 pub fn commit(ctx: &mut Context) -> Result<()> {
-    let mut thing_file = ctx.open_resource("thing")?;
-    let mut tag_file = ctx.open_resource("tag")?;
-    let mut thing_tags_file = ctx.open_resource("thing_tag")?;
-    let tx = ctx.tx()?;
-    let changes = ChangeStore::to_vec(&tx)?;
-
-    for change in changes {
-        match change.change {
-            Change::Insert(data) => match data {
+    commit_with(ctx, CommitMode::Sequential)
+}
+
+/// Same as [`commit`] writing the resource files as the given mode says.
+///
+/// On a dry run the pending changes are only reported.
+pub fn commit_with(ctx: &mut Context, mode: CommitMode) -> Result<()> {
+    if ctx.dry_run() {
+        for event in ChangeStore::to_vec(&ctx.tx()?)? {
+            info!("Would commit:", event.change);
+        }
+
+        return Ok(());
+    }
+
+    if let Some(resource) = ctx.package().resources().iter().find(|r| r.is_inline()) {
+        return Err(SomeError::InlineResource(resource.id().to_string()));
+    }
+
+    let mut tables = Tables {
+        thing: Table::load(ctx, "thing")?,
+        tag: Table::load(ctx, "tag")?,
+        thing_tag: Table::load(ctx, "thing_tag")?,
+    };
+
+    let events = ctx.store().in_transaction(|tx| {
+        let events = ChangeStore::to_vec(tx)?;
+
+        for event in &events {
+            tables.apply(event.change.clone());
+        }
+
+        tables.thing_tag.dedup();
+
+        let mut pending = Vec::new();
+        let result = tables.stage(mode, &mut pending);
+
+        if let Err(err) = result {
+            discard(&pending);
+
+            return Err(err);
+        }
+
+        move_into_place(&pending)?;
+
+        ChangeStore::flush(tx)?;
+        ThingtagStore::flush(tx)?;
+
+        Ok(events)
+    })?;
+
+    history::append(ctx.path(), &events)?;
+    ctx.refresh_fingerprints()?;
+
+    Ok(())
+}
+
+/// The resource tables affected by a commit.
+struct Tables {
+    thing: Table<thing::Record>,
+    tag: Table<tag::Record>,
+    thing_tag: Table<Thingtag>,
+}
+
+impl Tables {
+    /// Writes every changed table to a temporary sibling, registering each in `pending`.
+    ///
+    /// The concurrent mode waits for every writer before reporting the first failure, so
+    /// `pending` lists all temporary files written either way.
+    fn stage(&self, mode: CommitMode, pending: &mut Vec<(PathBuf, PathBuf)>) -> Result<()> {
+        match mode {
+            CommitMode::Sequential => self
+                .thing
+                .stage(pending)
+                .and_then(|_| self.tag.stage(pending))
+                .and_then(|_| self.thing_tag.stage(pending)),
+            CommitMode::Concurrent => {
+                let outcomes = thread::scope(|scope| {
+                    [
+                        scope.spawn(|| self.thing.stage_alone()),
+                        scope.spawn(|| self.tag.stage_alone()),
+                        scope.spawn(|| self.thing_tag.stage_alone()),
+                    ]
+                    .map(|handle| handle.join())
+                });
+                let mut result = Ok(());
+
+                for outcome in outcomes {
+                    let outcome = match outcome {
+                        Ok((outcome, staged)) => {
+                            pending.extend(staged);
+                            outcome
+                        }
+                        Err(_) => Err(SomeError::Unknown("resource writer panicked".into())),
+                    };
+
+                    if result.is_ok() {
+                        result = outcome;
+                    }
+                }
+
+                result
+            }
+        }
+    }
+
+    fn apply(&mut self, change: Change) {
+        match change {
+            Change::Insert(data) | Change::Replace(data) => match data {
                 Data::Tag { id, name, summary } => {
-                    let tag = tag::Record::new(id, name, summary);
-                    write_once(&tag, &mut tag_file)?;
+                    let record = tag::Record::new(id, name, summary);
+                    self.tag.upsert(record, |a, b| a.id() == b.id());
                 }
                 Data::Thing {
                     url,
@@ -47,72 +305,226 @@ pub fn commit(ctx: &mut Context) -> Result<()> {
                     category,
                     tags,
                 } => {
-                    let thing_tags: Vec<(String, String)> = tags
-                        .into_iter()
-                        .map(|tag_id| (url.clone(), tag_id))
-                        .collect();
-                    let thing = thing::Record::new(url, name, summary, category);
-                    write_once(&thing, &mut thing_file)?;
-                    write_many(&thing_tags, &mut thing_tags_file)?;
+                    self.thing_tag.remove(|record| record.thing_id() == url);
+
+                    for tag_id in tags {
+                        self.thing_tag.push(Thingtag::new(url.clone(), tag_id));
+                    }
+
+                    let record = thing::Record::new(url, name, summary, category);
+                    self.thing.upsert(record, |a, b| a.url() == b.url());
                 }
             },
-            Change::Replace(_) => unimplemented!(),
-            Change::Delete(_) => unimplemented!(),
+            Change::Delete(DataRef::Tag { id }) => {
+                self.tag.remove(|record| record.id() == &id);
+                self.thing_tag.remove(|record| record.tag_id() == &id);
+            }
+            Change::Delete(DataRef::Thing { id }) => {
+                self.thing.remove(|record| record.url() == id);
+                self.thing_tag.remove(|record| record.thing_id() == id);
+            }
         }
     }
+}
 
-    ChangeStore::flush(&tx)?;
-
-    tx.commit()?;
-
-    Ok(())
+/// A resource file loaded in memory.
+struct Table<R> {
+    path: PathBuf,
+    header: Vec<Name>,
+    records: Vec<R>,
+    /// Whether the records changed since loaded.
+    dirty: bool,
 }
 
-fn write_once<W, R>(record: &R, wtr: &mut W) -> Result<()>
+impl<R> Table<R>
 where
-    W: Write,
-    R: Serialize,
+    R: Serialize + DeserializeOwned,
 {
-    let mut wtr = csv::WriterBuilder::new()
-        .has_headers(false)
-        .from_writer(wtr);
+    fn load(ctx: &Context, name: &str) -> Result<Self> {
+        let path = ctx.resource_path(name);
+        let resource = ctx.resource(name);
+        let header = resource.field_names();
+        let location = resource.path().display().to_string();
 
-    wtr.serialize(&record)?;
-    wtr.flush()?;
+        if !path.is_file() {
+            return Err(SomeError::MissingResource(location));
+        }
 
-    Ok(())
+        let mut rdr = csv::Reader::from_path(&path)?;
+        let mut records: Vec<R> = Vec::new();
+
+        check_header(rdr.headers()?, &resource.schema, &location)?;
+
+        for result in rdr.deserialize() {
+            records.push(result.map_err(|err| locate::<SomeError>(&location, err))?);
+        }
+
+        Ok(Self {
+            path,
+            header,
+            records,
+            dirty: false,
+        })
+    }
+
+    fn push(&mut self, record: R) {
+        self.records.push(record);
+        self.dirty = true;
+    }
+
+    /// Replaces the first record deemed the same or appends it when there is none.
+    fn upsert<F>(&mut self, record: R, same: F)
+    where
+        F: Fn(&R, &R) -> bool,
+    {
+        match self
+            .records
+            .iter_mut()
+            .find(|existing| same(&**existing, &record))
+        {
+            Some(existing) => *existing = record,
+            None => self.records.push(record),
+        }
+
+        self.dirty = true;
+    }
+
+    fn remove<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&R) -> bool,
+    {
+        let len = self.records.len();
+        self.records.retain(|record| !predicate(record));
+
+        if self.records.len() != len {
+            self.dirty = true;
+        }
+    }
+
+    /// Writes the records to a temporary sibling when they changed.
+    ///
+    /// The temporary location is registered in `pending` alongside its final location.
+    fn stage(&self, pending: &mut Vec<(PathBuf, PathBuf)>) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let temp_path = temp_path(&self.path);
+        let file = File::create(&temp_path)?;
+        pending.push((temp_path, self.path.clone()));
+
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(file);
+
+        wtr.write_record(&self.header)?;
+
+        for record in &self.records {
+            wtr.serialize(record)?;
+        }
+
+        wtr.flush()?;
+
+        Ok(())
+    }
+
+    /// Same as [`Table::stage`] returning its own list of temporary files.
+    fn stage_alone(&self) -> (Result<()>, Vec<(PathBuf, PathBuf)>) {
+        let mut pending = Vec::new();
+        let result = self.stage(&mut pending);
+
+        (result, pending)
+    }
 }
 
-fn write_many<W, R>(records: &[R], wtr: &mut W) -> Result<()>
-where
-    W: Write,
-    R: Serialize,
-{
-    let mut wtr = csv::WriterBuilder::new()
-        .has_headers(false)
-        .from_writer(wtr);
+impl Table<Thingtag> {
+    /// Collapses duplicate relations keeping the first occurrence.
+    fn dedup(&mut self) {
+        let mut seen = HashSet::new();
 
-    for record in records {
-        wtr.serialize(&record)?;
+        self.remove(|record| {
+            !seen.insert((record.thing_id().to_string(), record.tag_id().clone()))
+        });
     }
+}
+
+/// Moves each temporary file over its resource, in order.
+///
+/// When a move fails the temporary files left are removed and the resources already replaced
+/// are reported.
+fn move_into_place(pending: &[(PathBuf, PathBuf)]) -> Result<()> {
+    for (idx, (temp_path, path)) in pending.iter().enumerate() {
+        if let Err(err) = fs::rename(temp_path, path) {
+            discard(&pending[idx..]);
+
+            if idx == 0 {
+                return Err(err.into());
+            }
 
-    wtr.flush()?;
+            return Err(SomeError::PartialCommit {
+                replaced: pending[..idx]
+                    .iter()
+                    .map(|(_, path)| path.display().to_string())
+                    .collect(),
+                source: err,
+            });
+        }
+    }
 
     Ok(())
 }
 
-fn assert_data_exists<C>(conn: &C, data: &Data) -> Result<()>
+/// Removes any temporary file left by an unsuccessful commit.
+fn discard(pending: &[(PathBuf, PathBuf)]) {
+    for (temp_path, _) in pending {
+        let _ = fs::remove_file(temp_path);
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .expect("resource path to point to a file")
+        .to_os_string();
+    name.push(".tmp");
+
+    path.with_file_name(name)
+}
+
+/// Fails when the data to insert clashes with an existing or pending id.
+///
+/// The pending inserts are checked explicitly so a batch never stages the same id twice,
+/// regardless of what the overlay views expose.
+fn assert_data_exists<C>(conn: &C, data: &Data, uniqueness: Uniqueness) -> Result<()>
 where
     C: Deref<Target = Connection>,
 {
+    let ignore_case = uniqueness == Uniqueness::CaseInsensitive;
+
     match &data {
         Data::Thing { url, .. } => {
-            if assert_thing_exists(conn, url)? {
+            let exists = if ChangeStore::is_pending_insert(conn, "thing", url, ignore_case)? {
+                true
+            } else if ignore_case {
+                ThingStore::exists_ignore_case(&**conn, url)?
+            } else {
+                assert_thing_exists(conn, url)?
+            };
+
+            if exists {
                 return Err(SomeError::Thing(ThingError::Duplicate(url.to_string())));
             }
         }
         Data::Tag { id, .. } => {
-            if assert_tag_exists(conn, id)? {
+            let exists = if ChangeStore::is_pending_insert(conn, "tag", id, ignore_case)? {
+                true
+            } else if ignore_case {
+                TagStore::exists_ignore_case(&**conn, id)?
+            } else {
+                assert_tag_exists(conn, id)?
+            };
+
+            if exists {
                 return Err(SomeError::Tag(TagError::Duplicate(id.to_string())));
             }
         }
@@ -126,7 +538,7 @@ fn assert_thing_exists<C>(conn: &C, id: &thing::Id) -> Result<bool>
 where
     C: Deref<Target = Connection>,
 {
-    Ok(ThingStore::get(conn, id)?.is_some())
+    ThingStore::exists(&**conn, id)
 }
 
 /// Validate the given tag does exist in the repository.
@@ -134,5 +546,436 @@ fn assert_tag_exists<C>(conn: &C, id: &tag::Id) -> Result<bool>
 where
     C: Deref<Target = Connection>,
 {
-    Ok(TagStore::get(conn, id)?.is_some())
+    TagStore::exists(&**conn, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::thingtag_set::ThingtagSet;
+    use crate::fixtures;
+
+    #[test]
+    fn failed_commit_leaves_files_and_changelog_untouched() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        add(
+            &mut ctx,
+            Data::Tag {
+                id: "rust".into(),
+                name: Some("Rust".into()),
+                summary: None,
+            },
+        )?;
+        add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "rust".into(),
+                tags: vec![],
+            },
+        )?;
+
+        let thing_path = ctx.resource_path("thing");
+        let before = fs::read(&thing_path)?;
+
+        // A directory in place of the temporary file makes the second resource write fail.
+        fs::create_dir(temp_path(&ctx.resource_path("tag")))?;
+
+        assert!(commit(&mut ctx).is_err());
+        assert_eq!(fs::read(&thing_path)?, before);
+        assert!(!temp_path(&thing_path).exists());
+
+        let tx = ctx.tx()?;
+        assert_eq!(ChangeStore::len(&tx)?, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_move_discards_the_rest() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pending: Vec<(PathBuf, PathBuf)> = ["thing", "tag", "thing_tag"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(format!("{}.csv", name));
+                (temp_path(&path), path)
+            })
+            .collect();
+
+        for (temp_path, _) in &pending {
+            fs::write(temp_path, "id\n")?;
+        }
+
+        // A directory with content can't be replaced by a file.
+        fs::create_dir(&pending[1].1)?;
+        fs::write(pending[1].1.join("keep"), "")?;
+
+        match move_into_place(&pending) {
+            Err(SomeError::PartialCommit { replaced, .. }) => {
+                assert_eq!(replaced, vec![pending[0].1.display().to_string()]);
+            }
+            other => panic!("expected a partial commit error, got {:?}", other),
+        }
+
+        assert!(pending[0].1.is_file());
+        assert!(!pending[2].1.exists());
+
+        for (temp_path, _) in &pending {
+            assert!(!temp_path.exists());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn commit_without_resource_file() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        add(
+            &mut ctx,
+            Data::Tag {
+                id: "rust".into(),
+                name: None,
+                summary: None,
+            },
+        )?;
+
+        let tag_path = ctx.resource_path("tag");
+        let before = fs::read(&tag_path)?;
+
+        fs::remove_file(ctx.resource_path("thing"))?;
+
+        match commit(&mut ctx) {
+            Err(SomeError::MissingResource(location)) => {
+                assert_eq!(location, "data/thing.csv");
+            }
+            other => panic!("expected a missing resource error, got {:?}", other),
+        }
+
+        assert_eq!(fs::read(&tag_path)?, before);
+        assert!(!temp_path(&tag_path).exists());
+
+        let tx = ctx.tx()?;
+        assert_eq!(ChangeStore::len(&tx)?, 1);
+
+        Ok(())
+    }
+
+    /// Stages a changelog mixing inserts, replacements and deletions across all resources.
+    fn stage_mixed(ctx: &mut Context) -> Result<()> {
+        for idx in 0..20 {
+            add(
+                ctx,
+                Data::Tag {
+                    id: format!("tag-{}", idx),
+                    name: Some(format!("Tag {}", idx)),
+                    summary: None,
+                },
+            )?;
+        }
+
+        for idx in 0..300 {
+            add(
+                ctx,
+                Data::Thing {
+                    url: format!("https://{}.test/", idx),
+                    name: format!("Thing {}", idx),
+                    summary: Some(format!("Summary {}", idx)),
+                    category: format!("tag-{}", idx % 20),
+                    tags: vec![format!("tag-{}", idx % 7), format!("tag-{}", idx % 11)],
+                },
+            )?;
+        }
+
+        for idx in 0..5 {
+            replace_tag(
+                ctx,
+                tag::Record::new(format!("tag-{}", idx), None, Some("Renamed".into())),
+            )?;
+        }
+
+        merge_tags(ctx, &"tag-3".into(), &"tag-4".into())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_commit_matches_sequential() -> Result<()> {
+        let sequential = fixtures::package();
+        let concurrent = fixtures::package();
+        let mut sequential_ctx = Context::new(sequential.path())?;
+        let mut concurrent_ctx = Context::new(concurrent.path())?;
+
+        stage_mixed(&mut sequential_ctx)?;
+        stage_mixed(&mut concurrent_ctx)?;
+
+        commit_with(&mut sequential_ctx, CommitMode::Sequential)?;
+        commit_with(&mut concurrent_ctx, CommitMode::Concurrent)?;
+
+        for name in ["thing", "tag", "thing_tag"] {
+            let expected = fs::read_to_string(sequential_ctx.resource_path(name))?;
+            let actual = fs::read_to_string(concurrent_ctx.resource_path(name))?;
+
+            assert!(expected.lines().count() > 1);
+            assert_eq!(actual, expected, "{} differs", name);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_stages_thing_tags() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        for id in ["language", "systems"] {
+            add(
+                &mut ctx,
+                Data::Tag {
+                    id: id.into(),
+                    name: None,
+                    summary: None,
+                },
+            )?;
+        }
+        add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["language".into(), "systems".into()],
+            },
+        )?;
+
+        let tx = ctx.tx()?;
+        assert_eq!(ThingtagStore::len(&tx)?, 2);
+        drop(tx);
+
+        commit(&mut ctx)?;
+
+        let mut file = File::open(ctx.resource_path("thing_tag"))?;
+        let set = ThingtagSet::from_reader(
+            &mut file,
+            &ctx.resource("thing_tag").schema,
+            "data/thing_tag.csv",
+        )?;
+
+        assert_eq!(
+            set.as_slice(),
+            &[
+                Thingtag::new("https://www.rust-lang.org/".into(), "language".into()),
+                Thingtag::new("https://www.rust-lang.org/".into(), "systems".into()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_tags_rewrites_things() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        for id in ["js", "javascript"] {
+            add(
+                &mut ctx,
+                Data::Tag {
+                    id: id.into(),
+                    name: None,
+                    summary: None,
+                },
+            )?;
+        }
+        add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://a.test/".into(),
+                name: "A".into(),
+                summary: None,
+                category: "js".into(),
+                tags: vec!["js".into(), "javascript".into()],
+            },
+        )?;
+        add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://b.test/".into(),
+                name: "B".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["js".into()],
+            },
+        )?;
+        commit(&mut ctx)?;
+
+        let count = merge_tags(&mut ctx, &"js".into(), &"javascript".into())?;
+        commit(&mut ctx)?;
+
+        let thing = fs::read_to_string(ctx.resource_path("thing"))?;
+        let tag = fs::read_to_string(ctx.resource_path("tag"))?;
+        let thing_tag = fs::read_to_string(ctx.resource_path("thing_tag"))?;
+
+        assert_eq!(count, 2);
+        assert!(thing.contains("https://a.test/,A,,javascript\n"));
+        assert!(!tag.lines().any(|line| line.starts_with("js,")));
+        assert_eq!(
+            thing_tag,
+            "thing_id,tag_id\nhttps://a.test/,javascript\nhttps://b.test/,javascript\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn relation_requires_both_ends() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        let missing_thing = add_relation(
+            &mut ctx,
+            &Thingtag::new("https://nowhere.test/".into(), "miscellaneous".into()),
+        );
+        let missing_tag = add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["nope".into()],
+            },
+        );
+
+        assert!(matches!(
+            missing_thing,
+            Err(SomeError::Thingtag(ThingtagError::MissingThing(_)))
+        ));
+        assert!(matches!(
+            missing_tag,
+            Err(SomeError::Thingtag(ThingtagError::MissingTag(_)))
+        ));
+
+        let tx = ctx.tx()?;
+        assert_eq!(ThingtagStore::len(&tx)?, 0);
+        assert_eq!(ChangeStore::len(&tx)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_unknown_tag() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        let result = merge_tags(&mut ctx, &"nope".into(), &"miscellaneous".into());
+
+        assert!(matches!(result, Err(SomeError::Tag(TagError::NotFound(_)))));
+
+        Ok(())
+    }
+
+    fn add_tag(ctx: &mut Context, id: &str) -> Result<()> {
+        add(
+            ctx,
+            Data::Tag {
+                id: id.into(),
+                name: None,
+                summary: None,
+            },
+        )
+    }
+
+    #[test]
+    fn case_variant_tag_when_case_insensitive() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+        ctx.set_uniqueness(Uniqueness::CaseInsensitive);
+
+        assert!(matches!(
+            add_tag(&mut ctx, "Miscellaneous"),
+            Err(SomeError::Tag(TagError::Duplicate(id))) if id == "Miscellaneous"
+        ));
+        add_tag(&mut ctx, "JavaScript")?;
+        assert!(add_tag(&mut ctx, "javascript").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn case_variant_tag_when_exact() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+        ctx.set_uniqueness(Uniqueness::Exact);
+
+        add_tag(&mut ctx, "Miscellaneous")?;
+        assert!(matches!(
+            add_tag(&mut ctx, "miscellaneous"),
+            Err(SomeError::Tag(TagError::Duplicate(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn same_new_tag_twice() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+
+        add_tag(&mut ctx, "rust")?;
+
+        assert!(matches!(
+            add_tag(&mut ctx, "rust"),
+            Err(SomeError::Tag(TagError::Duplicate(id))) if id == "rust"
+        ));
+
+        let tx = ctx.tx()?;
+        assert!(ChangeStore::is_pending_insert(&tx, "tag", "rust", false)?);
+        assert_eq!(ChangeStore::len(&tx)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run_sees_its_own_changes_and_leaves_resources() -> Result<()> {
+        let dir = fixtures::package();
+        let mut ctx = Context::new(dir.path())?;
+        ctx.set_dry_run(true)?;
+
+        let before: Vec<Vec<u8>> = ["thing", "tag", "thing_tag"]
+            .iter()
+            .map(|name| fs::read(ctx.resource_path(name)))
+            .collect::<std::io::Result<_>>()?;
+
+        add_tag(&mut ctx, "rust")?;
+        add(
+            &mut ctx,
+            Data::Thing {
+                url: "https://www.rust-lang.org/".into(),
+                name: "Rust".into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec!["rust".into()],
+            },
+        )?;
+        commit_with(&mut ctx, CommitMode::Sequential)?;
+
+        let after: Vec<Vec<u8>> = ["thing", "tag", "thing_tag"]
+            .iter()
+            .map(|name| fs::read(ctx.resource_path(name)))
+            .collect::<std::io::Result<_>>()?;
+
+        assert_eq!(after, before);
+
+        let tx = ctx.tx()?;
+        assert!(ChangeStore::is_pending_insert(&tx, "tag", "rust", false)?);
+
+        Ok(())
+    }
 }
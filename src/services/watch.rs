@@ -0,0 +1,216 @@
+//! Support for rerunning an action whenever the files of a package change.
+//!
+//! Editors tend to touch a file several times per save so changes are debounced: the action runs
+//! once the files have been quiet for a while. A failing action is reported and the watch goes
+//! on, so a mistake mid-edit doesn't need a restart once fixed.
+
+use chrono::Local;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::services::batch::Interrupt;
+use crate::{Result, SomeError};
+
+/// How long the files must be quiet before the action runs.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often the interrupt is checked while waiting for changes.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Tracks the last change seen to decide when it has settled.
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+        }
+    }
+
+    /// Records a change, postponing any pending one.
+    pub fn touch(&mut self, now: Instant) {
+        self.pending = Some(now);
+    }
+
+    /// Whether a change is pending and has been quiet for the whole delay. Clears it when so.
+    pub fn settle(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some(last) if now.duration_since(last) >= self.delay => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// Watches the `data/` CSVs and the `datapackage.json` of the package in `root`, running `f`
+/// after each debounced change until the interrupt is raised.
+pub fn watch<F>(root: &Path, delay: Duration, interrupt: &Interrupt, f: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| {
+            if let Ok(event) = result {
+                if is_relevant(&event) {
+                    let _ = tx.send(());
+                }
+            }
+        })?;
+
+    watcher.watch(&root.join("data"), RecursiveMode::NonRecursive)?;
+    watcher.watch(root, RecursiveMode::NonRecursive)?;
+
+    run(rx, delay, interrupt, f)
+}
+
+/// Whether the event changes a resource CSV or the package descriptor.
+///
+/// Anything else, e.g. the README being written, is ignored so a rebuild doesn't trigger another.
+fn is_relevant(event: &Event) -> bool {
+    let kind = &event.kind;
+
+    (kind.is_create() || kind.is_modify() || kind.is_remove())
+        && event.paths.iter().any(|path| {
+            let is_csv = path.extension().map_or(false, |ext| ext == "csv");
+            let is_descriptor = path
+                .file_name()
+                .map_or(false, |name| name == "datapackage.json");
+
+            is_csv || is_descriptor
+        })
+}
+
+/// Runs `f` once per burst of changes received, after `delay` without new ones.
+///
+/// Stops when the interrupt is raised or when no more changes can be received, running `f` for
+/// any change still pending. Errors from `f` are reported without stopping.
+fn run<F>(changes: Receiver<()>, delay: Duration, interrupt: &Interrupt, mut f: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let mut debouncer = Debouncer::new(delay);
+
+    while !interrupt.is_raised() {
+        match changes.recv_timeout(TICK.min(delay)) {
+            Ok(()) => debouncer.touch(Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                if debouncer.is_pending() {
+                    attempt(&mut f);
+                }
+
+                break;
+            }
+        }
+
+        if debouncer.settle(Instant::now()) {
+            attempt(&mut f);
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `f`, printing its error, if any, with the time it happened.
+fn attempt<F>(f: &mut F)
+where
+    F: FnMut() -> Result<()>,
+{
+    if let Err(err) = f() {
+        report(&err);
+    }
+}
+
+fn report(err: &SomeError) {
+    let stamp = Local::now().format("[%H:%M:%S]");
+
+    eprintln!("{} Rebuild failed: {:?}", stamp, err);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debouncer_waits_for_quiet() {
+        let start = Instant::now();
+        let delay = Duration::from_millis(300);
+        let mut debouncer = Debouncer::new(delay);
+
+        assert!(!debouncer.settle(start));
+
+        debouncer.touch(start);
+        debouncer.touch(start + Duration::from_millis(200));
+
+        assert!(!debouncer.settle(start + Duration::from_millis(400)));
+        assert!(debouncer.settle(start + Duration::from_millis(500)));
+        assert!(!debouncer.settle(start + Duration::from_millis(900)));
+    }
+
+    #[test]
+    fn burst_of_changes_rebuilds_once() -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let mut rebuilds = 0;
+
+        for _ in 0..3 {
+            tx.send(()).expect("receiver alive");
+        }
+
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            drop(tx);
+        });
+
+        run(rx, Duration::from_millis(50), &Interrupt::new(), || {
+            rebuilds += 1;
+            Ok(())
+        })?;
+        sender.join().expect("sender thread");
+
+        assert_eq!(rebuilds, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_rebuild_keeps_watching() -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let mut rebuilds = 0;
+
+        let sender = std::thread::spawn(move || {
+            for _ in 0..2 {
+                tx.send(()).expect("receiver alive");
+                std::thread::sleep(Duration::from_millis(150));
+            }
+        });
+
+        run(rx, Duration::from_millis(50), &Interrupt::new(), || {
+            rebuilds += 1;
+
+            if rebuilds == 1 {
+                Err(SomeError::BadUrl("not a url".into()))
+            } else {
+                Ok(())
+            }
+        })?;
+        sender.join().expect("sender thread");
+
+        assert_eq!(rebuilds, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! The settings taken from the `SOME_*` environment variables.
+//!
+//! The environment is read once per process, see [`current`]. Contexts carry a copy so the rest
+//! of the code asks the context instead of the environment.
+
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::cli::ChoiceLayout;
+use crate::lenses::thing::SchemeAllowlist;
+use crate::services::fetcher::DEFAULT_DELAY;
+use crate::services::staging::Uniqueness;
+
+/// The environment variable that, when set to anything but `0`, behaves like `--require-category`.
+pub const REQUIRE_CATEGORY_VAR: &str = "SOME_REQUIRE_CATEGORY";
+/// The environment variable that, when set to anything but `0`, rejects ids differing only in
+/// case from an existing one.
+pub const CASE_INSENSITIVE_VAR: &str = "SOME_CASE_INSENSITIVE_IDS";
+/// The environment variable setting the height of the choice lists, e.g. `40%` or `20`.
+pub const CHOICE_HEIGHT_VAR: &str = "SOME_CHOICE_HEIGHT";
+/// The environment variable setting the preview window of the choice lists, e.g. `right:50%`.
+pub const CHOICE_PREVIEW_VAR: &str = "SOME_CHOICE_PREVIEW_WINDOW";
+/// The environment variable with the comma separated URL schemes a thing can have, e.g.
+/// `https,ftp`.
+pub const URL_SCHEMES_VAR: &str = "SOME_URL_SCHEMES";
+/// The environment variable that, when set, makes generated package ids deterministic.
+pub const UUID_SEED_VAR: &str = "SOME_UUID_SEED";
+/// The environment variable setting the minimum milliseconds between two requests to the same
+/// host.
+pub const FETCH_DELAY_VAR: &str = "SOME_FETCH_DELAY";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// The settings of the process, read from the environment on first use.
+pub fn current() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::from_env)
+}
+
+/// Everything that can be tuned through the environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Whether a category must be explicitly chosen when adding a thing.
+    pub require_category: bool,
+    /// How new ids are checked against the existing ones.
+    pub uniqueness: Uniqueness,
+    /// How the choice lists are laid out.
+    pub choice_layout: ChoiceLayout,
+    /// The URL schemes a thing can have.
+    pub url_schemes: SchemeAllowlist,
+    /// The seed to derive new package ids from, random when unset.
+    pub uuid_seed: Option<String>,
+    /// The minimum time between two requests to the same host.
+    pub fetch_delay: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            require_category: false,
+            uniqueness: Uniqueness::default(),
+            choice_layout: ChoiceLayout::default(),
+            url_schemes: SchemeAllowlist::default(),
+            uuid_seed: None,
+            fetch_delay: DEFAULT_DELAY,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the process environment.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Reads the settings looking up each variable with `var`, falling back to the default for
+    /// anything unset.
+    pub fn from_vars<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let enabled = |name| var(name).map_or(false, |value| value != "0");
+        let default = Self::default();
+
+        Self {
+            require_category: enabled(REQUIRE_CATEGORY_VAR),
+            uniqueness: if enabled(CASE_INSENSITIVE_VAR) {
+                Uniqueness::CaseInsensitive
+            } else {
+                Uniqueness::Exact
+            },
+            choice_layout: ChoiceLayout {
+                height: var(CHOICE_HEIGHT_VAR).unwrap_or(default.choice_layout.height),
+                preview_window: var(CHOICE_PREVIEW_VAR)
+                    .unwrap_or(default.choice_layout.preview_window),
+            },
+            url_schemes: match var(URL_SCHEMES_VAR) {
+                Some(value) if !value.trim().is_empty() => SchemeAllowlist::new(value.split(',')),
+                _ => default.url_schemes,
+            },
+            uuid_seed: var(UUID_SEED_VAR),
+            fetch_delay: var(FETCH_DELAY_VAR)
+                .and_then(|value| value.trim().parse().ok())
+                .map_or(default.fetch_delay, Duration::from_millis),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reads_each_variable() {
+        let vars: HashMap<&str, &str> = [
+            (REQUIRE_CATEGORY_VAR, "1"),
+            (CASE_INSENSITIVE_VAR, "0"),
+            (CHOICE_HEIGHT_VAR, "40%"),
+            (URL_SCHEMES_VAR, "https, FTP"),
+            (UUID_SEED_VAR, "golden"),
+            (FETCH_DELAY_VAR, "250"),
+        ]
+        .into_iter()
+        .collect();
+        let settings = Settings::from_vars(|name| vars.get(name).map(|value| value.to_string()));
+
+        assert!(settings.require_category);
+        assert_eq!(settings.uniqueness, Uniqueness::Exact);
+        assert_eq!(settings.choice_layout.height, "40%");
+        assert_eq!(
+            settings.choice_layout.preview_window,
+            ChoiceLayout::default().preview_window
+        );
+        assert_eq!(settings.url_schemes, SchemeAllowlist::new(["https", "ftp"]));
+        assert_eq!(settings.uuid_seed.as_deref(), Some("golden"));
+        assert_eq!(settings.fetch_delay, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn defaults_when_unset() {
+        assert_eq!(Settings::from_vars(|_| None), Settings::default());
+    }
+}
@@ -1,21 +1,176 @@
 use std::str::FromStr;
 use std::fmt;
+use std::path::PathBuf;
 use anyhow::anyhow;
 
+pub mod render;
+
+/// The number of rows a query renders unless told otherwise.
+pub const DEFAULT_MAX_ROWS: usize = 1000;
+
 /// Composes the possible Shell configuration options.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct Config {
     pub output_mode: OutputMode,
+    /// The maximum number of rows to render for a query. `0` means unlimited.
+    pub max_rows: usize,
+    /// Whether to suppress notices such as `(0 rows)`.
+    pub quiet: bool,
+    /// A file to write the next query result to, set by `.once`.
+    pub once: Option<PathBuf>,
+    /// A fixed width for tables. Defaults to the terminal width.
+    pub width: Option<u16>,
+    /// How tables fit their columns to the width. Defaults to filling the terminal width, or to
+    /// [`Arrangement::Dynamic`] when the width is fixed.
+    pub arrangement: Option<Arrangement>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            output_mode: OutputMode::default(),
+            max_rows: DEFAULT_MAX_ROWS,
+            quiet: false,
+            once: None,
+            width: None,
+            arrangement: None,
+        }
+    }
 }
 
 
 
 
+/// How the columns of a table are fitted to its width.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arrangement {
+    /// Columns take the room their content needs, wrapping when the width is exceeded.
+    Dynamic,
+    /// Columns are stretched to fill the whole width.
+    FullWidth,
+    /// Columns are never wrapped, regardless of the width.
+    Disabled,
+}
+
+impl Arrangement {
+    const NAMES: &'static [(Arrangement, &'static str)] = &[
+        (Arrangement::Dynamic, "dynamic"),
+        (Arrangement::FullWidth, "full-width"),
+        (Arrangement::Disabled, "disabled"),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(arrangement, _)| arrangement == self)
+            .map(|(_, name)| *name)
+            .expect("every arrangement to be named")
+    }
+
+    /// A comma separated list of arrangement names, suitable for help messages.
+    pub fn help() -> String {
+        Self::NAMES
+            .iter()
+            .map(|(_, name)| *name)
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for Arrangement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Arrangement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|(arrangement, _)| *arrangement)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} is not a valid arrangement. Expected one of: {}",
+                    s,
+                    Arrangement::help()
+                )
+            })
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum OutputMode {
     Tabbed,
     Table,
     Jsonline,
+    Vertical,
+}
+
+/// The metadata for an output mode.
+struct ModeInfo {
+    mode: OutputMode,
+    /// The canonical name, used for display.
+    name: &'static str,
+    /// Alternative names accepted when parsing.
+    aliases: &'static [&'static str],
+}
+
+/// The single source of truth for the available output modes.
+///
+/// Adding a mode requires a new entry here and a renderer in [`render`].
+const MODES: &[ModeInfo] = &[
+    ModeInfo {
+        mode: OutputMode::Tabbed,
+        name: "tabbed",
+        aliases: &[],
+    },
+    ModeInfo {
+        mode: OutputMode::Table,
+        name: "table",
+        aliases: &[],
+    },
+    ModeInfo {
+        mode: OutputMode::Jsonline,
+        name: "jsonline",
+        aliases: &[],
+    },
+    ModeInfo {
+        mode: OutputMode::Vertical,
+        name: "vertical",
+        aliases: &["line"],
+    },
+];
+
+impl OutputMode {
+    /// All the available output modes.
+    pub fn all() -> impl Iterator<Item = OutputMode> {
+        MODES.iter().map(|info| info.mode)
+    }
+
+    /// The canonical name for the mode.
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// A comma separated list of mode names, suitable for help messages.
+    pub fn help() -> String {
+        MODES
+            .iter()
+            .map(|info| info.name)
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    fn info(&self) -> &'static ModeInfo {
+        MODES
+            .iter()
+            .find(|info| info.mode == *self)
+            .expect("every mode to be listed in MODES")
+    }
 }
 
 impl Default for OutputMode {
@@ -26,11 +181,7 @@ impl Default for OutputMode {
 
 impl fmt::Display for OutputMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Tabbed => write!(f, "tabbed"),
-            Self::Table => write!(f, "table"),
-            Self::Jsonline => write!(f, "jsonline"),
-        }
+        write!(f, "{}", self.name())
     }
 }
 
@@ -38,15 +189,16 @@ impl FromStr for OutputMode {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let mode = match s {
-            "tabbed" => OutputMode::Tabbed,
-            "table" => OutputMode::Table,
-            "jsonline" => OutputMode::Jsonline,
-            value => {
-                return Err(anyhow!("{} is not a valid mode", value));
-            }
-        };
-
-        Ok(mode)
+        MODES
+            .iter()
+            .find(|info| info.name == s || info.aliases.contains(&s))
+            .map(|info| info.mode)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} is not a valid mode. Expected one of: {}",
+                    s,
+                    OutputMode::help()
+                )
+            })
     }
 }
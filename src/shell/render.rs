@@ -0,0 +1,351 @@
+//! Renders query results in any of the [`OutputMode`]s.
+//!
+//! The renderers know nothing about SQLite, they take rows of optional strings so any command can
+//! render its results consistently with the shell. [`Values`] adapts a query result to them.
+
+use rusqlite::Rows;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+use super::{Arrangement, Config, OutputMode};
+use crate::Result;
+
+/// A result row, one optional value per column.
+pub type Row = Vec<Option<String>>;
+
+/// Adapts the rows of a SQLite query to the [`Row`]s the renderers take.
+pub struct Values<'stmt> {
+    rows: Rows<'stmt>,
+    width: usize,
+}
+
+impl<'stmt> Values<'stmt> {
+    pub fn new(rows: Rows<'stmt>, width: usize) -> Self {
+        Self { rows, width }
+    }
+}
+
+impl<'stmt> Iterator for Values<'stmt> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = self.width;
+
+        match self.rows.next() {
+            Ok(Some(row)) => Some(
+                (0..width)
+                    .map(|idx| row.get(idx))
+                    .collect::<rusqlite::Result<Row>>()
+                    .map_err(Into::into),
+            ),
+            Ok(None) => None,
+            Err(err) => Some(Err(err.into())),
+        }
+    }
+}
+
+/// Renders the rows in the given mode, returning the number of rows rendered.
+///
+/// At most `config.max_rows` rows are taken from `rows`, pass `&mut rows` to keep the rest.
+pub fn render<I>(
+    mode: OutputMode,
+    rows: I,
+    columns: &[String],
+    out: &mut dyn Write,
+    config: &Config,
+) -> Result<usize>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    let mut rows = Capped::new(rows, config.max_rows);
+
+    match mode {
+        OutputMode::Tabbed => render_tabbed(&mut rows, columns, out)?,
+        OutputMode::Table => render_table(&mut rows, columns, out, config)?,
+        OutputMode::Jsonline => render_jsonline(&mut rows, columns, out)?,
+        OutputMode::Vertical => render_vertical(&mut rows, columns, out)?,
+    }
+
+    Ok(rows.taken)
+}
+
+/// Caps the rows of a result, keeping count of the ones taken.
+///
+/// A limit of `0` means no cap.
+struct Capped<I> {
+    rows: I,
+    limit: usize,
+    taken: usize,
+}
+
+impl<I> Capped<I>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    fn new(rows: I, limit: usize) -> Self {
+        Self {
+            rows,
+            limit,
+            taken: 0,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Row>> {
+        if self.limit != 0 && self.taken >= self.limit {
+            return Ok(None);
+        }
+
+        let row = self.rows.next().transpose()?;
+
+        if row.is_some() {
+            self.taken += 1;
+        }
+
+        Ok(row)
+    }
+}
+
+fn render_tabbed<I>(rows: &mut Capped<I>, columns: &[String], out: &mut dyn Write) -> Result<()>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    let mut tw = TabWriter::new(out).padding(2);
+    writeln!(tw, "{}", columns.join("\t"))?;
+
+    while let Some(row) = rows.next()? {
+        let tup: Vec<String> = row
+            .into_iter()
+            .map(|value| value.unwrap_or_default())
+            .collect();
+        writeln!(tw, "{}", tup.join("\t"))?;
+    }
+
+    tw.flush()?;
+
+    Ok(())
+}
+
+/// Writes a table as wide as the terminal unless `config.width` fixes one.
+fn render_table<I>(
+    rows: &mut Capped<I>,
+    columns: &[String],
+    out: &mut dyn Write,
+    config: &Config,
+) -> Result<()>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    use comfy_table::presets::UTF8_FULL;
+    use comfy_table::*;
+
+    let mut table = Table::new();
+    let headers: Vec<Cell> = columns.iter().map(Cell::new).collect();
+
+    table.load_preset(UTF8_FULL).set_header(headers);
+
+    if let Some(width) = config.width {
+        table.set_width(width);
+    }
+
+    table.set_content_arrangement(content_arrangement(config));
+
+    while let Some(row) = rows.next()? {
+        let tup: Vec<Cell> = row
+            .into_iter()
+            .map(|value| Cell::new(value.unwrap_or_default()))
+            .collect();
+
+        table.add_row(tup);
+    }
+
+    writeln!(out, "{table}")?;
+
+    Ok(())
+}
+
+/// The table arrangement for the configuration, keeping columns to their content when the width
+/// is fixed and filling the terminal otherwise unless told so.
+fn content_arrangement(config: &Config) -> comfy_table::ContentArrangement {
+    use comfy_table::ContentArrangement;
+
+    match (config.arrangement, config.width) {
+        (Some(Arrangement::Dynamic), _) | (None, Some(_)) => ContentArrangement::Dynamic,
+        (Some(Arrangement::FullWidth), _) | (None, None) => ContentArrangement::DynamicFullWidth,
+        (Some(Arrangement::Disabled), _) => ContentArrangement::Disabled,
+    }
+}
+
+/// Writes each row as a JSON object on its own line.
+fn render_jsonline<I>(rows: &mut Capped<I>, columns: &[String], out: &mut dyn Write) -> Result<()>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    while let Some(row) = rows.next()? {
+        // TODO: column names are unqualified so joins with name clashes won't be collected
+        // correctly using a map.
+        let tup: serde_json::Map<String, serde_json::Value> = columns
+            .iter()
+            .cloned()
+            .zip(row.into_iter().map(|value| value.into()))
+            .collect();
+
+        writeln!(out, "{}", serde_json::to_string(&tup)?)?;
+    }
+
+    Ok(())
+}
+
+/// Writes each column on its own `name = value` line, with a blank line between rows.
+fn render_vertical<I>(rows: &mut Capped<I>, columns: &[String], out: &mut dyn Write) -> Result<()>
+where
+    I: Iterator<Item = Result<Row>>,
+{
+    let width = columns
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut first = true;
+
+    while let Some(row) = rows.next()? {
+        if !first {
+            writeln!(out)?;
+        }
+        first = false;
+
+        for (name, value) in columns.iter().zip(row) {
+            writeln!(
+                out,
+                "{:>width$} = {}",
+                name,
+                value.unwrap_or_default(),
+                width = width
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<String> {
+        vec!["id".into(), "name".into()]
+    }
+
+    fn rows() -> Vec<Result<Row>> {
+        vec![
+            Ok(vec![Some("rust".into()), Some("Rust".into())]),
+            Ok(vec![Some("zig".into()), None]),
+        ]
+    }
+
+    fn rendered(mode: OutputMode) -> Result<String> {
+        let config = Config {
+            width: Some(80),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let taken = render(mode, rows().into_iter(), &columns(), &mut out, &config)?;
+
+        assert_eq!(taken, 2);
+
+        Ok(String::from_utf8(out).expect("UTF-8 output"))
+    }
+
+    #[test]
+    fn fixed_width_full_table() -> Result<()> {
+        let config = Config {
+            width: Some(30),
+            arrangement: Some(Arrangement::FullWidth),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        render(
+            OutputMode::Table,
+            rows().into_iter(),
+            &columns(),
+            &mut out,
+            &config,
+        )?;
+        let table = String::from_utf8(out).expect("UTF-8 output");
+
+        assert_eq!(
+            content_arrangement(&config),
+            comfy_table::ContentArrangement::DynamicFullWidth
+        );
+        assert!(table.lines().all(|line| line.chars().count() == 30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_arrangement() {
+        use comfy_table::ContentArrangement;
+
+        let mut config = Config::default();
+        assert_eq!(
+            content_arrangement(&config),
+            ContentArrangement::DynamicFullWidth
+        );
+
+        config.width = Some(80);
+        assert_eq!(content_arrangement(&config), ContentArrangement::Dynamic);
+
+        config.arrangement = Some(Arrangement::Disabled);
+        assert_eq!(content_arrangement(&config), ContentArrangement::Disabled);
+    }
+
+    #[test]
+    fn every_mode_bytes() -> Result<()> {
+        assert_eq!(
+            rendered(OutputMode::Tabbed)?,
+            "id    name\nrust  Rust\nzig   \n"
+        );
+        assert_eq!(
+            rendered(OutputMode::Table)?,
+            concat!(
+                "┌──────┬──────┐\n",
+                "│ id   ┆ name │\n",
+                "╞══════╪══════╡\n",
+                "│ rust ┆ Rust │\n",
+                "├╌╌╌╌╌╌┼╌╌╌╌╌╌┤\n",
+                "│ zig  ┆      │\n",
+                "└──────┴──────┘\n",
+            )
+        );
+        assert_eq!(
+            rendered(OutputMode::Jsonline)?,
+            "{\"id\":\"rust\",\"name\":\"Rust\"}\n{\"id\":\"zig\",\"name\":null}\n"
+        );
+        assert_eq!(
+            rendered(OutputMode::Vertical)?,
+            "  id = rust\nname = Rust\n\n  id = zig\nname = \n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn max_rows_leaves_the_rest() -> Result<()> {
+        let config = Config {
+            max_rows: 1,
+            ..Default::default()
+        };
+        let mut rows = rows().into_iter();
+        let taken = render(
+            OutputMode::Vertical,
+            &mut rows,
+            &columns(),
+            &mut Vec::<u8>::new(),
+            &config,
+        )?;
+
+        assert_eq!(taken, 1);
+        assert_eq!(rows.count(), 1);
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     Result,
 };
 
-use super::{params, Connection};
+use super::{count, params, Connection};
 
 #[derive(Debug)]
 pub struct ChangeStore;
@@ -62,7 +62,7 @@ impl ChangeStore {
                 data
             FROM
                 staging.changelog
-            ORDER BY timestamp ASC
+            ORDER BY timestamp ASC, rowid ASC
             "#;
 
         let mut stmt = conn.prepare(query)?;
@@ -84,27 +84,80 @@ impl ChangeStore {
         Ok(items)
     }
 
-    pub fn len<Conn>(conn: &Conn) -> Result<usize>
+    /// The events recorded after `since` and up to `until`, both as Unix timestamps, in the
+    /// order they happened.
+    pub fn between<Conn>(conn: &Conn, since: i64, until: i64) -> Result<Vec<Event>>
     where
         Conn: Deref<Target = Connection>,
     {
         let query = r#"
             SELECT
-                count(1)
+                unixepoch(timestamp),
+                data
             FROM
                 staging.changelog
+            WHERE
+                unixepoch(timestamp) > $1
+            AND
+                unixepoch(timestamp) <= $2
+            ORDER BY timestamp ASC, rowid ASC
             "#;
+
         let mut stmt = conn.prepare(query)?;
-        let mut rows = stmt.query_map([], |row| {
-            let count: usize = row.get(0)?;
+        let rows = stmt.query_map([since, until], |row| {
+            let ts: usize = row.get(0)?;
+            let raw: String = row.get(1)?;
 
-            Ok(count)
+            Ok((ts, raw))
         })?;
+        let mut items = Vec::new();
 
-        match rows.next() {
-            Some(value) => Ok(value?),
-            None => unreachable!(),
+        for row in rows {
+            let (timestamp, raw) = row?;
+            let change: Change = serde_json::from_str(&raw)?;
+            items.push(Event { timestamp, change });
         }
+
+        Ok(items)
+    }
+
+    pub fn len<Conn>(conn: &Conn) -> Result<usize>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        count(&**conn, "staging.changelog")
+    }
+
+    /// Whether an insert of the given kind and id is pending in the changelog.
+    pub fn is_pending_insert<Conn>(
+        conn: &Conn,
+        kind: &str,
+        id: &str,
+        ignore_case: bool,
+    ) -> Result<bool>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = if ignore_case {
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM staging.changelog
+                WHERE operation = 'insert' AND kind = $1 AND id = $2 COLLATE NOCASE
+            )
+            "#
+        } else {
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM staging.changelog
+                WHERE operation = 'insert' AND kind = $1 AND id = $2
+            )
+            "#
+        };
+        let exists = conn.query_row(query, [kind, id], |row| row.get(0))?;
+
+        Ok(exists)
     }
 
     pub fn add<Conn>(conn: &Conn, entity: &Change) -> Result<()>
@@ -0,0 +1,71 @@
+//! SQL functions registered on every store connection, on top of the SQLite built-ins.
+
+use regex::Regex;
+use rusqlite::functions::{Context, FunctionFlags};
+use rusqlite::Connection;
+use std::sync::Arc;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The name and usage of each registered function, for help messages.
+pub const FUNCTIONS: &[(&str, &str)] = &[
+    (
+        "regexp",
+        "X REGEXP Y is true when the text X matches the regular expression Y.",
+    ),
+    (
+        "lower_ascii",
+        "lower_ascii(X) lowercases the ASCII letters of X, leaving the rest untouched.",
+    ),
+    (
+        "slugify",
+        "slugify(X) lowercases X and joins its alphanumeric runs with dashes.",
+    ),
+];
+
+pub fn register(conn: &Connection) -> rusqlite::Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+
+    conn.create_scalar_function("regexp", 2, flags, regexp)?;
+    conn.create_scalar_function("lower_ascii", 1, flags, |ctx| {
+        let text: Option<String> = ctx.get(0)?;
+
+        Ok(text.map(|text| text.to_ascii_lowercase()))
+    })?;
+    conn.create_scalar_function("slugify", 1, flags, |ctx| {
+        let text: Option<String> = ctx.get(0)?;
+
+        Ok(text.map(|text| slugify(&text)))
+    })?;
+
+    Ok(())
+}
+
+/// Matches the second argument against the pattern in the first one, compiling the pattern once
+/// per statement.
+fn regexp(ctx: &Context) -> rusqlite::Result<Option<bool>> {
+    let pattern: Arc<Regex> = ctx.get_or_create_aux(0, |value| -> Result<Regex, BoxError> {
+        Ok(Regex::new(value.as_str()?)?)
+    })?;
+    let text: Option<String> = ctx.get(1)?;
+
+    Ok(text.map(|text| pattern.is_match(&text)))
+}
+
+fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_joins_words() {
+        assert_eq!(slugify("  Data & Storage! "), "data-storage");
+    }
+}
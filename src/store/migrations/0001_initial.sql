@@ -0,0 +1,34 @@
+-- The staging schema as of the first migration. Statements are idempotent so databases created
+-- before migrations existed are adopted as they are.
+
+CREATE TABLE IF NOT EXISTS staging.changelog (
+    timestamp datetime DEFAULT (datetime('now')),
+    data      text NOT NULL,
+    operation text GENERATED ALWAYS AS (lower(json_extract(data, '$.operation'))) NOT NULL,
+    kind      text GENERATED ALWAYS AS (lower(json_extract(data, '$.kind'))) NOT NULL,
+    id        text GENERATED ALWAYS AS (json_extract(data, '$.id')) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS staging.tag (
+    id      text NOT NULL,
+    name    text,
+    summary text,
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS staging.thing (
+    url         text NOT NULL,
+    name        text NOT NULL,
+    summary     text,
+    category_id text NOT NULL,
+
+    PRIMARY KEY (url)
+);
+
+CREATE TABLE IF NOT EXISTS staging.thing_tag (
+    thing_id text NOT NULL,
+    tag_id   text NOT NULL,
+
+    PRIMARY KEY (thing_id, tag_id)
+);
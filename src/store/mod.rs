@@ -8,25 +8,36 @@
 //!
 //! The rings are implemented as distinct SQLite databases such that the main database contains just convenience views to query both rings.
 
+use encoding_rs::{Encoding, UTF_8};
 pub use rusqlite::{params, Connection};
-use rusqlite::{Row, Transaction};
+use rusqlite::{DatabaseName, Row, Transaction};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::fs::{create_dir_all, File};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use thiserror::Error;
 
+use crate::package::resource::InlineRow;
+use crate::package::{Package, DESCRIPTOR_PATH};
 use crate::Result;
 
 mod change;
+pub mod functions;
 mod tag;
 mod thing;
 mod thing_tag;
 pub use change::ChangeStore;
-pub use tag::TagStore;
+pub use tag::{TagCount, TagStore};
 pub use thing::ThingStore;
 pub use thing_tag::ThingtagStore;
 
 pub const DEFAULT_PATH: &str = ":memory:";
+/// The location of the staging database, relative to the package, when stored on disk.
+pub const STAGING_PATH: &str = ".some.db";
+/// The location of the run metadata, relative to the package, written for disk-backed stores.
+pub const RUN_METADATA_PATH: &str = ".some/run.json";
 
 #[macro_export]
 macro_rules! table {
@@ -89,50 +100,61 @@ macro_rules! virtual_table {
     };
 }
 
-// TODO: this approach does not handle deletes.
-pub const OVERLAY_SCHEMA: &str = r#"
-CREATE TEMPORARY VIEW tag AS
+/// The staged and committed things, without a trailing semicolon so it can be nested.
+macro_rules! thing_overlay {
+    () => {
+        r#"
     SELECT
-        json_extract(data, '$.id') AS id,
+        json_extract(data, '$.id') AS url,
         json_extract(data, '$.name') AS name,
-        json_extract(data, '$.summary') AS summary
+        json_extract(data, '$.summary') AS summary,
+        json_extract(data, '$.category') AS category_id
     FROM staging.changelog
     WHERE
-        kind = 'tag'
+        kind = 'thing'
     AND
         operation = 'insert'
-
     UNION ALL
     SELECT
-        id,
+        url,
         name,
-        iif(summary = '', NULL, summary)
-    FROM source.tag;
+        iif(summary = '', NULL, summary),
+        category_id
+    FROM source.thing"#
+    };
+}
 
-CREATE TEMPORARY VIEW thing AS
+// TODO: this approach does not handle deletes.
+pub const OVERLAY_SCHEMA: &str = concat!(
+    r#"
+CREATE TEMPORARY VIEW tag AS
     SELECT
-        json_extract(data, '$.id') AS url,
+        json_extract(data, '$.id') AS id,
         json_extract(data, '$.name') AS name,
-        json_extract(data, '$.summary') AS summary,
-        json_extract(data, '$.category') AS category_id
+        json_extract(data, '$.summary') AS summary
     FROM staging.changelog
     WHERE
-        kind = 'thing'
+        kind = 'tag'
     AND
         operation = 'insert'
+
     UNION ALL
     SELECT
-        url,
+        id,
         name,
-        iif(summary = '', NULL, summary),
-        category_id
-    FROM source.thing;
+        iif(summary = '', NULL, summary)
+    FROM source.tag;
+
+CREATE TEMPORARY VIEW thing AS"#,
+    thing_overlay!(),
+    r#";
 
 CREATE TEMPORARY VIEW thing_tag AS
     SELECT * FROM staging.thing_tag
     UNION ALL
     SELECT * FROM source.thing_tag;
-"#;
+"#
+);
 
 pub type Tx<'a> = Transaction<'a>;
 
@@ -155,9 +177,44 @@ pub trait Repository<'a> {
     }
 
     // Write
-    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<()>;
-    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<()>;
-    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<()>;
+    //
+    // Each write returns the number of affected entities so a no-op can be told apart.
+    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<usize>;
+    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<usize>;
+    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<usize>;
+}
+
+/// Quotes the text as a SQL `LIKE` pattern matching it anywhere, its wildcards escaped with `\`.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+        .replace('\'', "''");
+
+    format!("'%{}%'", escaped)
+}
+
+/// Quotes the name as a SQL identifier, doubling any embedded double quote.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The tables and views [`count`] accepts.
+const COUNTABLE: &[&str] = &["tag", "thing", "thing_tag", "staging.changelog"];
+
+/// Counts the rows of the given table or view.
+///
+/// The name is interpolated in the query so only the [`COUNTABLE`] ones are accepted.
+fn count(conn: &Connection, table: &str) -> Result<usize> {
+    if !COUNTABLE.contains(&table) {
+        return Err(StoreError::UncountableTable(table.to_string()).into());
+    }
+
+    let query = format!("SELECT count(1) FROM {}", table);
+    let count = conn.query_row(&query, [], |row| row.get(0))?;
+
+    Ok(count)
 }
 
 /// A strategy to connect to the storage.
@@ -181,19 +238,60 @@ impl FromStr for Strategy {
     }
 }
 
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Memory => write!(f, "{}", DEFAULT_PATH),
+            Strategy::Disk(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Serialises as the same string [`Strategy::from_str`] accepts.
+impl Serialize for Strategy {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// The configuration a store was opened with, recorded to ease debugging.
+#[derive(Debug, Serialize)]
+pub struct RunMetadata<'a> {
+    pub strategy: &'a Strategy,
+    pub package_id: &'a str,
+}
+
 #[derive(Debug)]
 pub struct Store {
     pub strategy: Strategy,
     pub path: PathBuf,
+    /// The encoding the resource CSV files are read with.
+    pub encoding: &'static Encoding,
     /// DB connection.
     pub conn: Connection,
 }
 
 impl Store {
     pub fn open(path: PathBuf, strategy: &Strategy) -> Result<Self> {
+        Self::open_with_encoding(path, strategy, UTF_8)
+    }
+
+    /// Opens the store reading the resource CSV files with the given encoding instead of UTF-8.
+    ///
+    /// Files in any encoding other than UTF-8 are decoded and loaded in full when the store is
+    /// opened, instead of being read on demand.
+    pub fn open_with_encoding(
+        path: PathBuf,
+        strategy: &Strategy,
+        encoding: &'static Encoding,
+    ) -> Result<Self> {
         let conn = Connection::open_in_memory()?;
 
-        create_source_db(&conn, &path)?;
+        functions::register(&conn)?;
+        create_source_db(&conn, &path, encoding)?;
         create_staging_db(&conn, &path, strategy)?;
 
         conn.execute_batch(&OVERLAY_SCHEMA)?;
@@ -201,6 +299,7 @@ impl Store {
         let store = Self {
             strategy: strategy.clone(),
             path,
+            encoding,
             conn,
         };
 
@@ -211,6 +310,147 @@ impl Store {
         Ok(self.conn.transaction()?)
     }
 
+    /// Runs `f` in a transaction, committing when it succeeds.
+    ///
+    /// When `f` fails the transaction is dropped, rolling back every write it made.
+    pub fn in_transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction) -> Result<T>,
+    {
+        let tx = self.conn.transaction()?;
+        let value = f(&tx)?;
+
+        tx.commit()?;
+
+        Ok(value)
+    }
+
+    /// Writes the strategy and the given package id to [`RUN_METADATA_PATH`].
+    ///
+    /// Only a disk-backed store leaves anything behind so the memory strategy is a no-op.
+    pub fn write_run_metadata(&self, package_id: &str) -> Result<()> {
+        if let Strategy::Memory = self.strategy {
+            return Ok(());
+        }
+
+        let path = self.path.join(RUN_METADATA_PATH);
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let metadata = RunMetadata {
+            strategy: &self.strategy,
+            package_id,
+        };
+
+        serde_json::to_writer_pretty(File::create(path)?, &metadata)?;
+
+        Ok(())
+    }
+
+    /// Narrows the `thing` view to the things where any of the `keywords` appears in the name,
+    /// the summary or a tag id, ignoring ASCII case.
+    ///
+    /// Every query on things, such as the ones building the README, sees the narrowed view from
+    /// then on. Each call replaces the previous filter.
+    pub fn filter_things(&self, keywords: &[String]) -> Result<()> {
+        let predicate = keywords
+            .iter()
+            .map(|keyword| {
+                let pattern = like_pattern(keyword);
+
+                format!(
+                    "overlay.name LIKE {pattern} ESCAPE '\\' \
+                     OR overlay.summary LIKE {pattern} ESCAPE '\\' \
+                     OR EXISTS (\
+                        SELECT 1 FROM thing_tag \
+                        WHERE thing_tag.thing_id = overlay.url \
+                        AND thing_tag.tag_id LIKE {pattern} ESCAPE '\\')",
+                    pattern = pattern
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" OR ");
+        let predicate = if predicate.is_empty() {
+            "1".to_string()
+        } else {
+            predicate
+        };
+
+        self.conn.execute_batch(&format!(
+            "DROP VIEW temp.thing;
+            CREATE TEMPORARY VIEW thing AS
+                SELECT * FROM ({overlay}) AS overlay
+                WHERE {predicate};",
+            overlay = thing_overlay!(),
+            predicate = predicate,
+        ))?;
+
+        Ok(())
+    }
+
+    /// Reclaims unused space and refreshes the query planner statistics of the staging database.
+    ///
+    /// Only a disk-backed staging database benefits from it so the memory strategy is a no-op.
+    pub fn optimize(&self) -> Result<()> {
+        if let Strategy::Disk(_) = self.strategy {
+            self.conn.execute_batch(
+                r#"
+                PRAGMA staging.optimize;
+                VACUUM staging;
+                "#,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `tag`, `thing` and `thing_tag` tables to a standalone SQLite database at `path`.
+    ///
+    /// The tables hold the source data unless `overlay` is set, in which case they hold the
+    /// overlay views, staged changes included.
+    pub fn export(&self, path: &Path, overlay: bool) -> Result<()> {
+        let location = path.display().to_string();
+
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS export", [&location])
+            .map_err(|source| StoreError::AttachFailed {
+                alias: "export".into(),
+                path: location.clone(),
+                source,
+            })?;
+
+        let result = self.fill_export(overlay);
+
+        self.conn.execute_batch("DETACH DATABASE export")?;
+
+        result
+    }
+
+    fn fill_export(&self, overlay: bool) -> Result<()> {
+        let ring = if overlay { "temp" } else { "source" };
+        let tables = [
+            ("tag", table!("tag", "export")),
+            ("thing", table!("thing", "export")),
+            ("thing_tag", table!("thing_tag", "export")),
+        ];
+        let tx = self.conn.unchecked_transaction()?;
+
+        for (name, schema) in tables {
+            tx.execute_batch(&schema)?;
+            tx.execute_batch(&format!(
+                "INSERT INTO export.{name} SELECT * FROM {ring}.{name};",
+                name = name,
+                ring = ring
+            ))?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
     /// A query mapped over the given function.
     pub fn query<T, P, F>(&mut self, query: &str, params: P, f: F) -> Result<Vec<T>>
     where
@@ -236,22 +476,10 @@ impl Store {
     }
 }
 
-fn create_source_db(conn: &Connection, path: &Path) -> Result<()> {
-    rusqlite::vtab::csvtab::load_module(&conn)?;
-
-    let tag_schema = virtual_table!("tag", path.join("data/tag.csv"), table!("tag", "source"));
-    let thing_schema = virtual_table!(
-        "thing",
-        path.join("data/thing.csv"),
-        table!("thing", "source")
-    );
-    let thing_tag_schema = virtual_table!(
-        "thing_tag",
-        path.join("data/thing_tag.csv"),
-        table!("thing_tag", "source")
-    );
-
-    let schema = format!(
+fn create_source_db(conn: &Connection, path: &Path, encoding: &'static Encoding) -> Result<()> {
+    rusqlite::vtab::csvtab::load_module(&conn).map_err(StoreError::CsvModuleLoad)?;
+
+    conn.execute_batch(
         r#"
         ATTACH DATABASE ':memory:' AS source;
 
@@ -260,46 +488,195 @@ fn create_source_db(conn: &Connection, path: &Path) -> Result<()> {
             hash text NOT NULL,
             id   text GENERATED ALWAYS AS (json_extract(body, '$.id')) VIRTUAL NOT NULL UNIQUE
         );
-
-        {} {} {}
         "#,
-        tag_schema, thing_schema, thing_tag_schema
-    );
+    )
+    .map_err(|source| StoreError::AttachFailed {
+        alias: "source".into(),
+        path: ":memory:".into(),
+        source,
+    })?;
+
+    let resources = [
+        ("tag", table!("tag", "source")),
+        ("thing", table!("thing", "source")),
+        ("thing_tag", table!("thing_tag", "source")),
+    ];
+    // The descriptor says where each resource file is or whether its rows are inline.
+    let package = if path.join(DESCRIPTOR_PATH).is_file() {
+        Some(Package::from_path(path)?)
+    } else {
+        None
+    };
+
+    for (resource, schema) in resources {
+        let declared = package
+            .iter()
+            .flat_map(|package| package.resources())
+            .find(|candidate| candidate.id() == resource);
+        let inline = declared.and_then(|candidate| candidate.data.as_ref());
+        let csv_path = match declared {
+            Some(declared) => declared.absolute_path(path),
+            None => path.join(format!("data/{}.csv", resource)),
+        };
+
+        match inline {
+            Some(rows) => create_inline_table(conn, resource, rows, &schema)?,
+            None if encoding != UTF_8 => {
+                create_decoded_table(conn, resource, &csv_path, &schema, encoding)?
+            }
+            None => create_virtual_table(conn, resource, &csv_path, &schema)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the `source` table for the given resource filled with its inline rows.
+///
+/// Values are stored as text, like the CSV virtual tables do, with missing ones as `NULL`.
+fn create_inline_table(
+    conn: &Connection,
+    resource: &str,
+    rows: &[InlineRow],
+    schema: &str,
+) -> Result<()> {
+    conn.execute_batch(schema)?;
+
+    let columns: Vec<String> = conn
+        .prepare(&format!("SELECT * FROM source.'{}'", resource))?
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "INSERT INTO source.'{}' ({}) VALUES ({})",
+        resource,
+        columns.join(", "),
+        placeholders
+    ))?;
 
-    conn.execute_batch(&schema)?;
+    for row in rows {
+        let values = columns.iter().map(|column| match row.get(column) {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::String(value)) => Some(value.clone()),
+            Some(value) => Some(value.to_string()),
+        });
+
+        stmt.execute(rusqlite::params_from_iter(values))?;
+    }
 
     Ok(())
 }
 
+/// Creates the `source` table for the given resource CSV file, decoding it from `encoding`.
+///
+/// The rows are mapped by header so the table looks the same as the virtual one would.
+fn create_decoded_table(
+    conn: &Connection,
+    resource: &str,
+    path: &Path,
+    schema: &str,
+    encoding: &'static Encoding,
+) -> Result<()> {
+    if !path.is_file() {
+        return Err(StoreError::MissingCsv {
+            resource: resource.into(),
+            path: path.display().to_string(),
+        }
+        .into());
+    }
+
+    let bytes = std::fs::read(path)?;
+    let (text, _, malformed) = encoding.decode(&bytes);
+
+    if malformed {
+        return Err(StoreError::Decode {
+            resource: resource.into(),
+            encoding: encoding.name().into(),
+        }
+        .into());
+    }
+
+    let mut rdr = csv::Reader::from_reader(text.as_bytes());
+    let headers = rdr.headers()?.clone();
+    let mut rows = Vec::new();
+
+    for record in rdr.records() {
+        let row: InlineRow = headers
+            .iter()
+            .zip(record?.iter())
+            .map(|(header, value)| (header.to_string(), value.into()))
+            .collect();
+
+        rows.push(row);
+    }
+
+    create_inline_table(conn, resource, &rows, schema)
+}
+
+/// Creates the `source` virtual table for the given resource CSV file.
+fn create_virtual_table(
+    conn: &Connection,
+    resource: &str,
+    path: &Path,
+    schema: &str,
+) -> Result<()> {
+    if !path.is_file() {
+        return Err(StoreError::MissingCsv {
+            resource: resource.into(),
+            path: path.display().to_string(),
+        }
+        .into());
+    }
+
+    conn.execute_batch(&virtual_table!(resource, path, schema))
+        .map_err(|source| StoreError::VirtualTable {
+            resource: resource.into(),
+            source,
+        })?;
+
+    Ok(())
+}
+
+/// The staging migrations in order. The staging `user_version` pragma records how many of them
+/// have been applied.
+const MIGRATIONS: &[&str] = &[include_str!("migrations/0001_initial.sql")];
+
 // TODO: If changelog has the data as a json blob, I can get rid of the staging ring
 // alltogether.
 fn create_staging_db(conn: &Connection, path: &Path, strategy: &Strategy) -> Result<()> {
     let path = match strategy {
         Strategy::Memory => ":memory:".to_string(),
-        Strategy::Disk(_) => path.join(".some.db").to_str().unwrap().to_string(),
+        Strategy::Disk(_) => path.join(STAGING_PATH).to_str().unwrap().to_string(),
     };
 
-    let schema = format!(
-        r#"
-        ATTACH DATABASE '{}' AS staging;
-
-        CREATE TABLE staging.changelog (
-            timestamp datetime DEFAULT (datetime('now')),
-            data      text NOT NULL,
-            operation text GENERATED ALWAYS AS (lower(json_extract(data, '$.operation'))) NOT NULL,
-            kind      text GENERATED ALWAYS AS (lower(json_extract(data, '$.kind'))) NOT NULL,
-            id        text GENERATED ALWAYS AS (json_extract(data, '$.id')) NOT NULL
-        );
+    conn.execute("ATTACH DATABASE ?1 AS staging", [&path])
+        .map_err(|source| StoreError::AttachFailed {
+            alias: "staging".into(),
+            path: path.clone(),
+            source,
+        })?;
 
-        {} {} {}
-        "#,
-        path,
-        table!("tag", "staging"),
-        table!("thing", "staging"),
-        table!("thing_tag", "staging"),
-    );
+    migrate_staging(conn)
+}
+
+/// Applies every migration the staging database is behind on, bumping its `user_version` as it
+/// goes.
+fn migrate_staging(conn: &Connection) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA staging.user_version", [], |row| row.get(0))?;
 
-    conn.execute_batch(&schema)?;
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.unchecked_transaction()?;
+
+        tx.execute_batch(migration)?;
+        tx.pragma_update(
+            Some(DatabaseName::Attached("staging")),
+            "user_version",
+            (idx + 1) as i64,
+        )?;
+        tx.commit()?;
+    }
 
     Ok(())
 }
@@ -366,6 +743,413 @@ pub enum StoreError {
         "The given strategy `{0}` is not an acceptable path nor the special `:memory:` token."
     )]
     StrategyError(String),
+    #[error("The table `{0}` can't be counted.")]
+    UncountableTable(String),
     #[error("The given query expected a non-empty result:\n\n{0}")]
     EmptyError(String),
+    #[error("The store was opened for a package other than `{0}`.")]
+    PathMismatch(String),
+    #[error(
+        "The store for `{0}` keeps its staging database on disk and can't be used for a dry run."
+    )]
+    DryRunOnDisk(String),
+    #[error("Failed to load the SQLite CSV module: {0}")]
+    CsvModuleLoad(#[source] rusqlite::Error),
+    #[error("The `{resource}` resource file `{path}` is missing.")]
+    MissingCsv { resource: String, path: String },
+    #[error(
+        "The `{resource}` resource file is not valid {encoding}. Check the `--encoding` given."
+    )]
+    Decode { resource: String, encoding: String },
+    #[error("Failed to load the `{resource}` resource: {source}")]
+    VirtualTable {
+        resource: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+    #[error("Failed to attach `{path}` as `{alias}`: {source}")]
+    AttachFailed {
+        alias: String,
+        path: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::change::{Change, Data};
+    use crate::fixtures;
+    use crate::SomeError;
+    use std::fs;
+
+    #[test]
+    fn count_seeded_table() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        let tx = store.transaction()?;
+
+        for id in ["rust", "zig"] {
+            let change = Change::Insert(Data::Tag {
+                id: id.into(),
+                name: None,
+                summary: None,
+            });
+            ChangeStore::add(&tx, &change)?;
+        }
+
+        assert_eq!(count(&tx, "tag")?, 3);
+        assert_eq!(count(&tx, "staging.changelog")?, 2);
+        assert_eq!(count(&tx, "thing")?, 0);
+        assert!(matches!(
+            count(&tx, "tag; DROP TABLE tag"),
+            Err(SomeError::StoreError(StoreError::UncountableTable(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn disk_run_writes_metadata() -> Result<()> {
+        let dir = fixtures::package();
+        let staging_path = dir.path().join(STAGING_PATH);
+        let store = Store::open(
+            dir.path().to_path_buf(),
+            &Strategy::Disk(staging_path.clone()),
+        )?;
+
+        store.write_run_metadata("some-id")?;
+
+        let raw = std::fs::read_to_string(dir.path().join(RUN_METADATA_PATH))?;
+        let metadata: serde_json::Value = serde_json::from_str(&raw)?;
+
+        assert_eq!(metadata["strategy"], staging_path.display().to_string());
+        assert_eq!(metadata["package_id"], "some-id");
+
+        let memory = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        std::fs::remove_file(dir.path().join(RUN_METADATA_PATH))?;
+        memory.write_run_metadata("some-id")?;
+
+        assert!(!dir.path().join(RUN_METADATA_PATH).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_transaction_rolls_back() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        let result: Result<()> = store.in_transaction(|tx| {
+            let change = Change::Insert(Data::Tag {
+                id: "rust".into(),
+                name: None,
+                summary: None,
+            });
+            ChangeStore::add(tx, &change)?;
+
+            Err(SomeError::Unknown("failure".into()))
+        });
+
+        assert!(result.is_err());
+
+        let tx = store.transaction()?;
+        assert_eq!(ChangeStore::len(&tx)?, 0);
+        drop(tx);
+
+        let len = store.in_transaction(|tx| {
+            let change = Change::Insert(Data::Tag {
+                id: "rust".into(),
+                name: None,
+                summary: None,
+            });
+            ChangeStore::add(tx, &change)?;
+
+            ChangeStore::len(tx)
+        })?;
+
+        assert_eq!(len, 1);
+
+        let tx = store.transaction()?;
+        assert_eq!(ChangeStore::len(&tx)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_opens_standalone() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        let tx = store.transaction()?;
+        let change = Change::Insert(Data::Thing {
+            url: "https://www.rust-lang.org/".into(),
+            name: "Rust".into(),
+            summary: None,
+            category: "miscellaneous".into(),
+            tags: vec![],
+        });
+        ChangeStore::add(&tx, &change)?;
+        tx.commit()?;
+
+        let source_path = dir.path().join("source.sqlite");
+        let overlay_path = dir.path().join("overlay.sqlite");
+        store.export(&source_path, false)?;
+        store.export(&overlay_path, true)?;
+
+        let len = |path: &Path, table: &str| -> Result<usize> {
+            let conn = Connection::open(path)?;
+            let query = format!("SELECT count(*) FROM {}", table);
+
+            Ok(conn.query_row(&query, [], |row| row.get(0))?)
+        };
+
+        assert_eq!(len(&source_path, "tag")?, 1);
+        assert_eq!(len(&source_path, "thing")?, 0);
+        assert_eq!(len(&overlay_path, "tag")?, 1);
+        assert_eq!(len(&overlay_path, "thing")?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn optimize_after_flush() -> Result<()> {
+        let dir = fixtures::package();
+        let strategy = Strategy::Disk(dir.path().join(STAGING_PATH));
+        let mut store = Store::open(dir.path().to_path_buf(), &strategy)?;
+
+        let tx = store.transaction()?;
+        for idx in 0..1000 {
+            let change = Change::Insert(Data::Tag {
+                id: format!("tag-{}", idx),
+                name: None,
+                summary: None,
+            });
+            ChangeStore::add(&tx, &change)?;
+        }
+        ChangeStore::flush(&tx)?;
+        tx.commit()?;
+
+        store.optimize()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn regexp_matches_things() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        let tx = store.transaction()?;
+        for (url, name) in [
+            ("https://www.rust-lang.org/", "Rust"),
+            ("https://ziglang.org/", "Zig"),
+            ("https://www.ruby-lang.org/", "Ruby"),
+        ] {
+            let change = Change::Insert(Data::Thing {
+                url: url.into(),
+                name: name.into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            });
+            ChangeStore::add(&tx, &change)?;
+        }
+        tx.commit()?;
+
+        let names = store.query(
+            "SELECT slugify(name) FROM thing WHERE name REGEXP ?1 ORDER BY name",
+            ["^Ru"],
+            |row| row.get::<_, String>(0),
+        )?;
+
+        assert_eq!(names, vec!["ruby", "rust"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_without_tag_csv() -> Result<()> {
+        let dir = fixtures::package();
+        std::fs::remove_file(dir.path().join("data/tag.csv"))?;
+
+        match Store::open(dir.path().to_path_buf(), &Strategy::Memory) {
+            Err(SomeError::StoreError(StoreError::MissingCsv { resource, .. })) => {
+                assert_eq!(resource, "tag");
+            }
+            other => panic!("expected a missing CSV error, got {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_windows_1252_csv() -> Result<()> {
+        let dir = fixtures::package();
+        let mut csv = b"url,name,summary,category_id\n".to_vec();
+        csv.extend_from_slice(
+            b"https://example.org/,Caf\xe9,Cr\xe8me br\xfbl\xe9e.,miscellaneous\n",
+        );
+        fs::write(dir.path().join("data/thing.csv"), csv)?;
+
+        let store = Store::open_with_encoding(
+            dir.path().to_path_buf(),
+            &Strategy::Memory,
+            encoding_rs::WINDOWS_1252,
+        )?;
+        let thing = ThingStore::get(&store.conn, &"https://example.org/".to_string())?
+            .expect("the decoded thing");
+
+        assert_eq!(thing.name(), "Café");
+        assert_eq!(thing.summary(), Some("Crème brûlée.".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_resource_at_declared_path() -> Result<()> {
+        let dir = fixtures::package();
+        let mut package = Package::from_path(dir.path())?;
+        let resource = package
+            .resources
+            .iter_mut()
+            .find(|resource| resource.id() == "thing")
+            .expect("a thing resource");
+        resource.path = PathBuf::from("data/links.csv");
+        fs::rename(
+            dir.path().join("data/thing.csv"),
+            dir.path().join("data/links.csv"),
+        )?;
+        fs::write(
+            dir.path().join(DESCRIPTOR_PATH),
+            package.to_canonical_string()?,
+        )?;
+
+        let mut links = fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("data/links.csv"))?;
+        std::io::Write::write_all(
+            &mut links,
+            b"https://www.rust-lang.org/,Rust,A language.,miscellaneous\n",
+        )?;
+
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        assert_eq!(ThingStore::len(&store.conn)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_migrates_old_staging() -> Result<()> {
+        let dir = fixtures::package();
+        let staging_path = dir.path().join(STAGING_PATH);
+
+        // A staging database as created before migrations existed.
+        let old = Connection::open(&staging_path)?;
+        old.execute_batch(
+            r#"
+            CREATE TABLE changelog (
+                timestamp datetime DEFAULT (datetime('now')),
+                data      text NOT NULL,
+                operation text GENERATED ALWAYS AS (lower(json_extract(data, '$.operation'))) NOT NULL,
+                kind      text GENERATED ALWAYS AS (lower(json_extract(data, '$.kind'))) NOT NULL,
+                id        text GENERATED ALWAYS AS (json_extract(data, '$.id')) NOT NULL
+            );
+            "#,
+        )?;
+        drop(old);
+
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Disk(staging_path))?;
+        let version: i64 = store
+            .conn
+            .query_row("PRAGMA staging.user_version", [], |row| row.get(0))?;
+
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        assert!(ThingtagStore::is_empty(&store.conn)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn filter_things_by_keyword() -> Result<()> {
+        let dir = fixtures::package();
+        let data = dir.path().join("data");
+        fs::write(
+            data.join("thing.csv"),
+            concat!(
+                "url,name,summary,category_id\n",
+                "https://www.rust-lang.org/,Rust,A language.,miscellaneous\n",
+                "https://www.sqlite.org/,SQLite,An embedded database.,miscellaneous\n",
+                "https://ziglang.org/,Zig,,miscellaneous\n",
+            ),
+        )?;
+        fs::write(
+            data.join("thing_tag.csv"),
+            "thing_id,tag_id\nhttps://ziglang.org/,systems\n",
+        )?;
+
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        let urls = |keywords: &[&str]| -> Result<Vec<String>> {
+            let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+            store.filter_things(&keywords)?;
+
+            let mut urls: Vec<String> = ThingStore::to_vec(&store.conn)?
+                .iter()
+                .map(|thing| thing.url().to_string())
+                .collect();
+            urls.sort();
+
+            Ok(urls)
+        };
+
+        assert_eq!(urls(&["RUST"])?, vec!["https://www.rust-lang.org/"]);
+        assert_eq!(urls(&["embedded"])?, vec!["https://www.sqlite.org/"]);
+        assert_eq!(urls(&["system"])?, vec!["https://ziglang.org/"]);
+        assert_eq!(urls(&["cobol"])?, Vec::<String>::new());
+        assert_eq!(
+            urls(&["embedded", "systems"])?,
+            vec!["https://www.sqlite.org/", "https://ziglang.org/"]
+        );
+        assert_eq!(urls(&[])?.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn quoted_identifiers() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let name = r#"odd "name", with select"#;
+
+        assert_eq!(quote_identifier("url"), r#""url""#);
+
+        conn.execute_batch(&format!(
+            "CREATE TABLE {} ({} TEXT)",
+            quote_identifier("odd table"),
+            quote_identifier(name)
+        ))?;
+        conn.execute(
+            &format!(
+                "INSERT INTO {} ({}) VALUES ('x')",
+                quote_identifier("odd table"),
+                quote_identifier(name)
+            ),
+            [],
+        )?;
+
+        let value: String = conn.query_row(
+            &format!(
+                "SELECT {} FROM {}",
+                quote_identifier(name),
+                quote_identifier("odd table")
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+
+        assert_eq!(value, "x");
+
+        Ok(())
+    }
 }
@@ -1,8 +1,9 @@
 use std::ops::Deref;
 
-use crate::{entities::tag, Result};
+use crate::entities::change::{Change, Data, DataRef};
+use crate::{entities::tag, entities::thing, Result};
 
-use super::{params, Connection, Repository};
+use super::{count, params, ChangeStore, Connection, Repository, ThingtagStore};
 
 #[derive(Debug)]
 pub struct TagStore;
@@ -47,6 +48,7 @@ impl<'a> Repository<'a> for TagStore {
                 summary
             FROM
                 tag
+            ORDER BY id ASC
             "#;
 
         let mut stmt = conn.prepare(query)?;
@@ -67,26 +69,10 @@ impl<'a> Repository<'a> for TagStore {
     }
 
     fn len(conn: Self::Conn) -> Result<usize> {
-        let query = r#"
-            SELECT
-                count(1)
-            FROM
-                tag
-            "#;
-        let mut stmt = conn.prepare(query)?;
-        let mut rows = stmt.query_map([], |row| {
-            let count: usize = row.get(0)?;
-
-            Ok(count)
-        })?;
-
-        match rows.next() {
-            Some(value) => Ok(value?),
-            None => unreachable!(),
-        }
+        count(conn, "tag")
     }
 
-    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<()> {
+    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<usize> {
         let record = params![entity.id(), entity.name(), entity.summary(),];
 
         let mut stmt = conn.prepare(
@@ -98,13 +84,13 @@ impl<'a> Repository<'a> for TagStore {
             "#,
         )?;
 
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 
     // TODO: With the split of source and staging removing an item is non trivial.
-    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<()> {
+    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<usize> {
         let query = r#"
             DELETE
             FROM
@@ -113,7 +99,7 @@ impl<'a> Repository<'a> for TagStore {
                 id = $1
             "#;
         let mut stmt = conn.prepare(query)?;
-        stmt.execute([entity_id])?;
+        let count = stmt.execute([entity_id])?;
 
         // Remove relations.
         let query_rel = r#"
@@ -126,10 +112,10 @@ impl<'a> Repository<'a> for TagStore {
         let mut stmt_rel = conn.prepare(query_rel)?;
         stmt_rel.execute([entity_id])?;
 
-        Ok(())
+        Ok(count)
     }
 
-    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<()> {
+    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<usize> {
         let record = params![entity.id(), entity.name(), entity.summary(),];
 
         let mut stmt = conn.prepare(
@@ -141,13 +127,49 @@ impl<'a> Repository<'a> for TagStore {
             "#,
         )?;
 
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 }
 
+/// A tag with its usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCount {
+    pub tag: tag::Record,
+    /// The number of things referencing the tag.
+    pub things: usize,
+    /// Whether any thing uses the tag as its category.
+    pub is_category: bool,
+}
+
 impl TagStore {
+    /// Whether a tag with the given id exists, without fetching it.
+    pub fn exists<Conn>(conn: Conn, id: &tag::Id) -> Result<bool>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT EXISTS(SELECT 1 FROM tag WHERE id = $1)
+            "#;
+        let exists = conn.query_row(query, [id], |row| row.get(0))?;
+
+        Ok(exists)
+    }
+
+    /// Whether a tag with the given id exists, ignoring ASCII case.
+    pub fn exists_ignore_case<Conn>(conn: Conn, id: &tag::Id) -> Result<bool>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT EXISTS(SELECT 1 FROM tag WHERE id = $1 COLLATE NOCASE)
+            "#;
+        let exists = conn.query_row(query, [id], |row| row.get(0))?;
+
+        Ok(exists)
+    }
+
     pub fn list<Conn>(conn: Conn) -> Result<Vec<tag::Record>>
     where
         Conn: Deref<Target = Connection>,
@@ -155,6 +177,79 @@ impl TagStore {
         TagStore::to_vec(&conn)
     }
 
+    /// Lists every tag with the number of things referencing it, either as a tag or as their
+    /// category, sorted by id.
+    pub fn list_with_counts<Conn>(conn: Conn) -> Result<Vec<TagCount>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                tag.id,
+                tag.name,
+                tag.summary,
+                (
+                    SELECT count(*) FROM thing
+                    WHERE thing.category_id = tag.id
+                    OR thing.url IN (SELECT thing_id FROM thing_tag WHERE tag_id = tag.id)
+                ) AS things,
+                EXISTS(SELECT 1 FROM thing WHERE thing.category_id = tag.id) AS is_category
+            FROM
+                tag
+            ORDER BY tag.id ASC
+            "#;
+
+        let mut stmt = conn.prepare(query)?;
+        let rows = stmt.query_map([], |row| {
+            let id: tag::Id = row.get(0)?;
+            let name: Option<String> = row.get(1)?;
+            let summary: Option<String> = row.get(2)?;
+            let things: usize = row.get(3)?;
+            let is_category: bool = row.get(4)?;
+
+            Ok(TagCount {
+                tag: tag::Record::new(id, name, summary),
+                things,
+                is_category,
+            })
+        })?;
+        let mut items = Vec::new();
+
+        for row in rows {
+            items.push(row?);
+        }
+
+        Ok(items)
+    }
+
+    /// Lists every known tag id paired with the URL of each thing referencing it, either as a tag
+    /// or as its category, once per pair and sorted by tag id.
+    pub fn list_tagged<Conn>(conn: Conn) -> Result<Vec<(tag::Id, String)>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT tagged.tag_id, tagged.thing_id
+            FROM (
+                SELECT tag_id, thing_id FROM thing_tag
+                UNION
+                SELECT category_id, url FROM thing
+            ) AS tagged
+            JOIN tag ON tag.id = tagged.tag_id
+            ORDER BY tagged.tag_id ASC, tagged.thing_id ASC
+            "#;
+
+        let mut stmt = conn.prepare(query)?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut items = Vec::new();
+
+        for row in rows {
+            items.push(row?);
+        }
+
+        Ok(items)
+    }
+
     pub fn list_without<Conn>(conn: Conn, ids: &[tag::Id]) -> Result<Vec<tag::Record>>
     where
         Conn: Deref<Target = Connection>,
@@ -169,6 +264,7 @@ impl TagStore {
                 tag
             WHERE
                 id NOT IN ({})
+            ORDER BY id ASC
             "#,
             ids.iter()
                 .map(|x| format!("'{}'", x))
@@ -204,7 +300,7 @@ impl TagStore {
                 tag.summary
             FROM
                 source.thing AS thing
-            LEFT JOIN
+            JOIN
                 source.tag AS tag ON thing.category_id = tag.id
             ORDER BY tag.id ASC
         "#;
@@ -225,4 +321,80 @@ impl TagStore {
 
         Ok(items)
     }
+
+    /// Records the changes to merge the `from` tag into the `into` tag.
+    ///
+    /// Things referencing `from` as category or tag are replaced to reference `into` instead,
+    /// collapsing any duplicate tag, and `from` is deleted. Meant to run within a transaction.
+    ///
+    /// Returns the number of things replaced.
+    pub fn merge<Conn>(conn: &Conn, from: &tag::Id, into: &tag::Id) -> Result<usize>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                url,
+                name,
+                summary,
+                category_id
+            FROM
+                thing
+            WHERE
+                category_id = $1
+            OR
+                url IN (SELECT thing_id FROM thing_tag WHERE tag_id = $1)
+            "#;
+
+        let mut stmt = conn.prepare(query)?;
+        let rows = stmt.query_map([from], |row| {
+            let url: thing::Id = row.get(0)?;
+            let name: String = row.get(1)?;
+            let summary: Option<String> = row.get(2)?;
+            let category_id: tag::Id = row.get(3)?;
+
+            Ok(thing::Record::new(url, name, summary, category_id))
+        })?;
+        let mut things = Vec::new();
+
+        for row in rows {
+            things.push(row?);
+        }
+
+        for thing in &things {
+            let mut tags: Vec<tag::Id> = Vec::new();
+
+            for relation in ThingtagStore::list_for_thing(&**conn, thing.url())? {
+                let tag_id = if relation.tag_id() == from {
+                    into.clone()
+                } else {
+                    relation.tag_id().clone()
+                };
+
+                if !tags.contains(&tag_id) {
+                    tags.push(tag_id);
+                }
+            }
+
+            let category = if thing.category_id() == from {
+                into.clone()
+            } else {
+                thing.category_id().clone()
+            };
+
+            let change = Change::Replace(Data::Thing {
+                url: thing.url().to_string(),
+                name: thing.name().to_string(),
+                summary: thing.summary(),
+                category,
+                tags,
+            });
+
+            ChangeStore::add(conn, &change)?;
+        }
+
+        ChangeStore::add(conn, &Change::Delete(DataRef::Tag { id: from.clone() }))?;
+
+        Ok(things.len())
+    }
 }
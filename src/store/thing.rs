@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-use super::{params, Connection, Repository};
+use super::{count, params, Connection, Repository};
 use crate::entities::tag;
 use crate::entities::thing;
 use crate::Result;
@@ -43,55 +43,21 @@ impl<'a> Repository<'a> for ThingStore {
     }
 
     fn to_vec(conn: Self::Conn) -> Result<Vec<Self::Entity>> {
-        let query = r#"
-            SELECT
-                url,
-                name,
-                summary,
-                category_id
-            FROM
-                thing
-            "#;
-
-        let mut stmt = conn.prepare(query)?;
-        let rows = stmt.query_map([], |row| {
-            let url: Self::EntityId = row.get(0)?;
-            let name: String = row.get(1)?;
-            let summary: Option<String> = row.get(2)?;
-            let category_id: tag::Id = row.get(3)?;
-
-            Ok(Self::Entity::new(url, name, summary, category_id))
-        })?;
         let mut items = Vec::new();
 
-        for row in rows {
-            items.push(row?);
-        }
+        ThingStore::for_each(conn, |record| {
+            items.push(record);
+            Ok(())
+        })?;
 
         Ok(items)
     }
 
     fn len(conn: Self::Conn) -> Result<usize> {
-        let query = r#"
-            SELECT
-                count(1)
-            FROM
-                thing
-            "#;
-        let mut stmt = conn.prepare(query)?;
-        let mut rows = stmt.query_map([], |row| {
-            let count: usize = row.get(0)?;
-
-            Ok(count)
-        })?;
-
-        match rows.next() {
-            Some(value) => Ok(value?),
-            None => unreachable!(),
-        }
+        count(conn, "thing")
     }
 
-    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<()> {
+    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<usize> {
         let record = params![
             entity.url(),
             entity.name(),
@@ -108,13 +74,13 @@ impl<'a> Repository<'a> for ThingStore {
             "#,
         )?;
 
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 
     // TODO: With the split of source and staging removing an item is non trivial.
-    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<()> {
+    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<usize> {
         let query = r#"
             DELETE
             FROM
@@ -123,7 +89,7 @@ impl<'a> Repository<'a> for ThingStore {
                 url = $1
             "#;
         let mut stmt = conn.prepare(query)?;
-        stmt.execute([entity_id])?;
+        let count = stmt.execute([entity_id])?;
 
         // Remove relations.
         let query_rel = r#"
@@ -136,10 +102,10 @@ impl<'a> Repository<'a> for ThingStore {
         let mut stmt_rel = conn.prepare(query_rel)?;
         stmt_rel.execute([entity_id])?;
 
-        Ok(())
+        Ok(count)
     }
 
-    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<()> {
+    fn replace(conn: Self::Conn, entity: &Self::Entity) -> Result<usize> {
         let record = params![
             entity.url(),
             entity.name(),
@@ -156,16 +122,20 @@ impl<'a> Repository<'a> for ThingStore {
             "#,
         )?;
 
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 }
 
 impl ThingStore {
-    pub fn list_categorised<Conn>(conn: Conn, category_id: &tag::Id) -> Result<Vec<thing::Thing>>
+    /// Calls `f` with every thing, one row at a time, without collecting them.
+    ///
+    /// Stops at the first error, either reading a row or returned by `f`.
+    pub fn for_each<Conn, F>(conn: Conn, mut f: F) -> Result<()>
     where
         Conn: Deref<Target = Connection>,
+        F: FnMut(thing::Record) -> Result<()>,
     {
         let query = r#"
             SELECT
@@ -175,31 +145,170 @@ impl ThingStore {
                 category_id
             FROM
                 thing
-            WHERE
-                category_id = $1
             "#;
 
         let mut stmt = conn.prepare(query)?;
-        let rows = stmt.query_map([category_id], |row| {
+        let rows = stmt.query_map([], |row| {
             let url: thing::Id = row.get(0)?;
             let name: String = row.get(1)?;
             let summary: Option<String> = row.get(2)?;
-            let category: tag::Id = row.get(3)?;
-            // TODO: propagate error rather than excepting.
-            let tags = tags_for(&conn, &url).expect("Failed to fetch tags.");
+            let category_id: tag::Id = row.get(3)?;
 
-            Ok(thing::Thing {url, name, summary, category, tags})
+            Ok(thing::Record::new(url, name, summary, category_id))
         })?;
-        let mut items = Vec::new();
 
         for row in rows {
-            items.push(row?);
+            f(row?)?;
         }
 
-        Ok(items)
+        Ok(())
+    }
+
+    /// Whether a thing with the given id exists, without fetching it.
+    pub fn exists<Conn>(conn: Conn, id: &thing::Id) -> Result<bool>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT EXISTS(SELECT 1 FROM thing WHERE url = $1)
+            "#;
+        let exists = conn.query_row(query, [id], |row| row.get(0))?;
+
+        Ok(exists)
+    }
+
+    /// Whether a thing with the given id exists, ignoring ASCII case.
+    pub fn exists_ignore_case<Conn>(conn: Conn, id: &thing::Id) -> Result<bool>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT EXISTS(SELECT 1 FROM thing WHERE url = $1 COLLATE NOCASE)
+            "#;
+        let exists = conn.query_row(query, [id], |row| row.get(0))?;
+
+        Ok(exists)
+    }
+
+    /// Lists the things in the given category sorted by name, then by url.
+    ///
+    /// The tags of each thing are sorted by id so the same store always yields the same list.
+    pub fn list_categorised<Conn>(conn: Conn, category_id: &tag::Id) -> Result<Vec<thing::Thing>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                url,
+                name,
+                summary,
+                category_id
+            FROM
+                thing
+            WHERE
+                category_id = $1
+            ORDER BY name, url
+            "#;
+
+        list_things(&conn, query, [category_id])
+    }
+
+    /// Lists the things related to the given tag.
+    pub fn list_by_tag<Conn>(conn: Conn, tag_id: &tag::Id) -> Result<Vec<thing::Thing>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                url,
+                name,
+                summary,
+                category_id
+            FROM
+                thing
+            WHERE
+                url IN (SELECT thing_id FROM thing_tag WHERE tag_id = $1)
+            ORDER BY url
+            "#;
+
+        list_things(&conn, query, [tag_id])
+    }
+
+    /// Lists the things without any tag.
+    pub fn list_untagged<Conn>(conn: Conn) -> Result<Vec<thing::Thing>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                url,
+                name,
+                summary,
+                category_id
+            FROM
+                thing
+            WHERE
+                url NOT IN (SELECT thing_id FROM thing_tag)
+            ORDER BY url
+            "#;
+
+        list_things(&conn, query, params![])
+    }
+
+    /// Lists the things whose category is not a known tag.
+    pub fn list_uncategorised<Conn>(conn: Conn) -> Result<Vec<thing::Thing>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                url,
+                name,
+                summary,
+                category_id
+            FROM
+                thing
+            WHERE
+                category_id NOT IN (SELECT id FROM tag)
+            ORDER BY url
+            "#;
+
+        list_things(&conn, query, params![])
+    }
+}
+
+fn list_things<Conn, P>(conn: &Conn, query: &str, params: P) -> Result<Vec<thing::Thing>>
+where
+    Conn: Deref<Target = Connection>,
+    P: rusqlite::Params,
+{
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map(params, |row| {
+        let url: thing::Id = row.get(0)?;
+        let name: String = row.get(1)?;
+        let summary: Option<String> = row.get(2)?;
+        let category: tag::Id = row.get(3)?;
+        // TODO: propagate error rather than excepting.
+        let tags = tags_for(conn, &url).expect("Failed to fetch tags.");
+
+        Ok(thing::Thing {
+            url,
+            name,
+            summary,
+            category,
+            tags,
+        })
+    })?;
+    let mut items = Vec::new();
+
+    for row in rows {
+        items.push(row?);
     }
+
+    Ok(items)
 }
 
+/// The tags of the given thing sorted by id.
 fn tags_for<Conn>(conn: &Conn, thing_id: &thing::Id) -> Result<Vec<tag::Id>>
 where
     Conn: Deref<Target = Connection>,
@@ -228,3 +337,142 @@ where
 
     Ok(items)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::change::{Change, Data};
+    use crate::entities::thing::ThingError;
+    use crate::fixtures;
+    use crate::store::{ChangeStore, Store, Strategy, TagStore};
+    use crate::SomeError;
+
+    #[test]
+    fn write_counts() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        let url = "https://www.rust-lang.org/".to_string();
+        let thing = thing::Record::new(url.clone(), "Rust".into(), None, "miscellaneous".into());
+
+        assert_eq!(ThingStore::add(&store.conn, &thing)?, 1);
+        assert_eq!(ThingStore::remove(&store.conn, &url)?, 1);
+        assert_eq!(ThingStore::remove(&store.conn, &url)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_visits_every_row() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        for url in ["https://www.rust-lang.org/", "https://go.dev/"] {
+            let thing = thing::Record::new(url.into(), "Lang".into(), None, "miscellaneous".into());
+            ThingStore::add(&store.conn, &thing)?;
+        }
+
+        let mut urls = Vec::new();
+        ThingStore::for_each(&store.conn, |record| {
+            urls.push(record.url().to_string());
+            Ok(())
+        })?;
+        urls.sort();
+
+        assert_eq!(urls, vec!["https://go.dev/", "https://www.rust-lang.org/"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_stops_on_error() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+
+        for url in ["https://www.rust-lang.org/", "https://go.dev/"] {
+            let thing = thing::Record::new(url.into(), "Lang".into(), None, "miscellaneous".into());
+            ThingStore::add(&store.conn, &thing)?;
+        }
+
+        let mut visited = 0;
+        let result = ThingStore::for_each(&store.conn, |record| {
+            visited += 1;
+            Err(ThingError::Duplicate(record.url().to_string()).into())
+        });
+
+        assert!(matches!(
+            result,
+            Err(SomeError::Thing(ThingError::Duplicate(_)))
+        ));
+        assert_eq!(visited, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn exists() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        let tx = store.transaction()?;
+        let url = "https://www.rust-lang.org/".to_string();
+        let missing = "https://nowhere.test/".to_string();
+        let change = Change::Insert(Data::Thing {
+            url: url.clone(),
+            name: "Rust".into(),
+            summary: None,
+            category: "miscellaneous".into(),
+            tags: vec![],
+        });
+
+        ChangeStore::add(&tx, &change)?;
+
+        assert!(ThingStore::exists(&*tx, &url)?);
+        assert!(!ThingStore::exists(&*tx, &missing)?);
+        assert!(TagStore::exists(&*tx, &"miscellaneous".to_string())?);
+        assert!(!TagStore::exists(&*tx, &"nope".to_string())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn list_categorised_by_name() -> Result<()> {
+        let dir = fixtures::package();
+        let mut store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        let tx = store.transaction()?;
+
+        for (url, name) in [
+            ("https://ziglang.org/", "Zig"),
+            ("https://www.rust-lang.org/", "Rust"),
+            ("https://crates.io/", "Rust"),
+            ("https://go.dev/", "Go"),
+        ] {
+            let change = Change::Insert(Data::Thing {
+                url: url.into(),
+                name: name.into(),
+                summary: None,
+                category: "miscellaneous".into(),
+                tags: vec![],
+            });
+
+            ChangeStore::add(&tx, &change)?;
+        }
+
+        let category = "miscellaneous".to_string();
+        let urls = |things: Vec<thing::Thing>| -> Vec<String> {
+            things.into_iter().map(|thing| thing.url).collect()
+        };
+        let first = urls(ThingStore::list_categorised(&*tx, &category)?);
+
+        assert_eq!(
+            first,
+            vec![
+                "https://go.dev/",
+                "https://crates.io/",
+                "https://www.rust-lang.org/",
+                "https://ziglang.org/",
+            ]
+        );
+        assert_eq!(urls(ThingStore::list_categorised(&*tx, &category)?), first);
+
+        Ok(())
+    }
+}
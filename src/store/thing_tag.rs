@@ -1,4 +1,6 @@
-use super::{params, Connection, Repository};
+use std::ops::Deref;
+
+use super::{count, params, Connection, Repository};
 use crate::entities::{tag, thing, thingtag::Thingtag};
 use crate::Result;
 
@@ -11,35 +13,38 @@ impl<'a> Repository<'a> for ThingtagStore {
     type EntityId = (thing::Id, tag::Id);
     type Conn = &'a Connection;
 
+    /// A relation is fully described by its id so there is nothing to fetch. Use
+    /// [`Repository::contains`] or the `list_for_*` methods instead.
     fn get(_conn: Self::Conn, _entity_id: &Self::EntityId) -> Result<Option<Self::Entity>> {
         unimplemented!()
     }
 
-    fn to_vec(_conn: Self::Conn) -> Result<Vec<Self::Entity>> {
-        unimplemented!()
-    }
-
-    fn len(conn: Self::Conn) -> Result<usize> {
+    fn to_vec(conn: Self::Conn) -> Result<Vec<Self::Entity>> {
         let query = r#"
             SELECT
-                count(1)
+                thing_id,
+                tag_id
             FROM
                 thing_tag
+            ORDER BY thing_id, tag_id
             "#;
-        let mut stmt = conn.prepare(query)?;
-        let mut rows = stmt.query_map([], |row| {
-            let count: usize = row.get(0)?;
 
-            Ok(count)
-        })?;
+        let mut stmt = conn.prepare(query)?;
+        let rows = stmt.query_map([], |row| Ok(Thingtag::new(row.get(0)?, row.get(1)?)))?;
+        let mut items = Vec::new();
 
-        match rows.next() {
-            Some(value) => Ok(value?),
-            None => unreachable!(),
+        for row in rows {
+            items.push(row?);
         }
+
+        Ok(items)
     }
 
-    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<()> {
+    fn len(conn: Self::Conn) -> Result<usize> {
+        count(conn, "thing_tag")
+    }
+
+    fn add(conn: Self::Conn, entity: &Self::Entity) -> Result<usize> {
         let record = params![entity.thing_id(), entity.tag_id(),];
 
         let mut stmt = conn.prepare(
@@ -51,13 +56,13 @@ impl<'a> Repository<'a> for ThingtagStore {
             "#,
         )?;
 
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 
     // TODO: With the split of source and staging removing an item is non trivial.
-    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<()> {
+    fn remove(conn: Self::Conn, entity_id: &Self::EntityId) -> Result<usize> {
         let record = params![entity_id.0, entity_id.1,];
 
         let query = r#"
@@ -70,12 +75,138 @@ impl<'a> Repository<'a> for ThingtagStore {
                 tag_id = $2
             "#;
         let mut stmt = conn.prepare(query)?;
-        stmt.execute(record)?;
+        let count = stmt.execute(record)?;
 
-        Ok(())
+        Ok(count)
     }
 
-    fn replace(_conn: Self::Conn, _entity: &Self::Entity) -> Result<()> {
+    /// A relation has no attributes beyond its id so there is nothing to replace.
+    fn replace(_conn: Self::Conn, _entity: &Self::Entity) -> Result<usize> {
         unimplemented!()
     }
 }
+
+impl ThingtagStore {
+    /// The relations for the given thing, ordered by tag.
+    pub fn list_for_thing<Conn>(conn: Conn, thing_id: &str) -> Result<Vec<Thingtag>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                thing_id,
+                tag_id
+            FROM
+                thing_tag
+            WHERE
+                thing_id = $1
+            ORDER BY tag_id
+            "#;
+
+        list_by(&conn, query, thing_id)
+    }
+
+    /// The relations for the given tag, ordered by thing.
+    pub fn list_for_tag<Conn>(conn: Conn, tag_id: &str) -> Result<Vec<Thingtag>>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            SELECT
+                thing_id,
+                tag_id
+            FROM
+                thing_tag
+            WHERE
+                tag_id = $1
+            ORDER BY thing_id
+            "#;
+
+        list_by(&conn, query, tag_id)
+    }
+
+    /// Removes every staged relation.
+    pub fn flush<Conn>(conn: &Conn) -> Result<()>
+    where
+        Conn: Deref<Target = Connection>,
+    {
+        let query = r#"
+            DELETE
+            FROM
+                staging.thing_tag
+            "#;
+        let mut stmt = conn.prepare(query)?;
+        stmt.execute([])?;
+
+        Ok(())
+    }
+}
+
+fn list_by<Conn>(conn: &Conn, query: &str, id: &str) -> Result<Vec<Thingtag>>
+where
+    Conn: Deref<Target = Connection>,
+{
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([id], |row| Ok(Thingtag::new(row.get(0)?, row.get(1)?)))?;
+    let mut items = Vec::new();
+
+    for row in rows {
+        items.push(row?);
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::store::{Store, Strategy};
+
+    fn seed(conn: &Connection) -> Result<()> {
+        for (thing_id, tag_id) in [("a", "x"), ("a", "y"), ("b", "x")] {
+            ThingtagStore::add(conn, &Thingtag::new(thing_id.into(), tag_id.into()))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn thing_with_many_tags() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        seed(&store.conn)?;
+
+        let actual = ThingtagStore::list_for_thing(&store.conn, "a")?;
+
+        assert_eq!(
+            actual,
+            vec![
+                Thingtag::new("a".into(), "x".into()),
+                Thingtag::new("a".into(), "y".into()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tag_with_many_things() -> Result<()> {
+        let dir = fixtures::package();
+        let store = Store::open(dir.path().to_path_buf(), &Strategy::Memory)?;
+        seed(&store.conn)?;
+
+        let actual = ThingtagStore::list_for_tag(&store.conn, "x")?;
+
+        assert_eq!(
+            actual,
+            vec![
+                Thingtag::new("a".into(), "x".into()),
+                Thingtag::new("b".into(), "x".into()),
+            ]
+        );
+        assert_eq!(ThingtagStore::to_vec(&store.conn)?.len(), 3);
+
+        Ok(())
+    }
+}